@@ -0,0 +1,60 @@
+//! Compare serde_json and simd-json when parsing a large render result.
+//!
+//! Run with: `cargo bench --features simd-json --bench json_backend`
+
+use serde_json::{json, Value};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 20;
+
+/// Build a result document of roughly 5 MB, similar in shape to content-1.
+fn build_document() -> String {
+    let rows: Vec<Value> = (0..31_000)
+        .map(|i| json!({
+            "id": i,
+            "name": format!("item-{}", i),
+            "price": i as f64 * 1.25,
+            "active": i % 2 == 0,
+            "tags": ["alpha", "beta", "gamma"],
+            "description": "Lorem ipsum dolor sit amet, consectetur adipiscing elit."
+        }))
+        .collect();
+
+    json!({
+        "has_error": false,
+        "status_code": "200",
+        "status_text": "OK",
+        "status_param": "",
+        "rows": rows
+    }).to_string()
+}
+
+fn measure<F: FnMut()>(name: &str, mut f: F) -> Duration {
+    f();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed = start.elapsed() / ITERATIONS;
+    println!("{:<12} {:>10.2?} per parse", name, elapsed);
+    elapsed
+}
+
+fn main() {
+    let document = build_document();
+    println!("document size: {:.2} MB", document.len() as f64 / (1024.0 * 1024.0));
+
+    let serde = measure("serde_json", || {
+        let value: Value = serde_json::from_str(black_box(&document)).unwrap();
+        black_box(value);
+    });
+
+    let simd = measure("simd-json", || {
+        let mut buffer = black_box(&document).as_bytes().to_vec();
+        let value: Value = simd_json::serde::from_slice(&mut buffer).unwrap();
+        black_box(value);
+    });
+
+    println!("speedup:     {:.2}x", serde.as_secs_f64() / simd.as_secs_f64());
+}
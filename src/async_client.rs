@@ -0,0 +1,318 @@
+//! Runtime-agnostic async transport for the IPC client.
+//!
+//! Available with the `async-tokio` and `async-std` features. The protocol
+//! exchange (request framing, header and chunked content reads) is written
+//! once against the `futures_io::{AsyncRead, AsyncWrite}` traits; each runtime
+//! only provides a thin adapter that opens the TCP connection and applies the
+//! configured timeout. smol users can use the `async-std` adapter, which runs
+//! on the same `async-io` reactor.
+
+use futures_io::{AsyncRead, AsyncWrite};
+use futures_lite::io::{AsyncReadExt, AsyncWriteExt};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::client::{ConnectionPermit, NeutralIpcClient};
+use crate::error::{NeutralIpcError, Result};
+use crate::record::NeutralIpcRecord;
+
+/// Interval between attempts to take a slot of the connection limit while queued.
+const QUEUE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+impl NeutralIpcClient {
+    /// Start the IPC communication with the Neutral server asynchronously.
+    ///
+    /// Same as `start`, but the connection is driven by the async runtime. The
+    /// configured timeout applies to the whole exchange. When both runtime
+    /// features are enabled, tokio is used inside a tokio runtime and
+    /// async-std otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Same as `start`. Returns `NeutralIpcError::Io` with
+    /// `io::ErrorKind::TimedOut` if the exchange exceeds the timeout, and
+    /// `NeutralIpcError::NotSupported` if a SOCKS5 proxy is configured.
+    pub(crate) async fn start_async(&mut self) -> Result<&HashMap<String, Value>> {
+        #[cfg(feature = "proxy")]
+        if self.config().get_proxy().is_some() {
+            return Err(NeutralIpcError::NotSupported);
+        }
+
+        let timeout = self.config().get_timeout_duration();
+        let _permit = acquire_permit(self).await?;
+
+        // Sent a second time, uncompressed, if the server rejects compressed formats.
+        loop {
+            #[cfg(all(feature = "async-tokio", feature = "async-std"))]
+            if tokio::runtime::Handle::try_current().is_ok() {
+                tokio_runtime::start(self, timeout).await?;
+            } else {
+                async_std_runtime::start(self, timeout).await?;
+            }
+            #[cfg(all(feature = "async-tokio", not(feature = "async-std")))]
+            tokio_runtime::start(self, timeout).await?;
+            #[cfg(all(feature = "async-std", not(feature = "async-tokio")))]
+            async_std_runtime::start(self, timeout).await?;
+
+            if !self.fall_back_uncompressed() {
+                return Ok(&self.result);
+            }
+        }
+    }
+}
+
+/// Take a slot of the `max_concurrent_connections` limit for `client`.
+///
+/// Queued requests poll for a free slot instead of blocking the runtime, for
+/// as long as the `total_timeout` allows, like `start` does.
+///
+/// # Errors
+///
+/// Returns `NeutralIpcError::TooManyConnections` if the limit is reached and
+/// `queue_connections` is disabled, or a timeout error naming the `queue` phase.
+async fn acquire_permit(client: &NeutralIpcClient) -> Result<Option<ConnectionPermit>> {
+    let config = client.config();
+    let deadline = config.get_total_timeout().map(|total| (total, Instant::now() + Duration::from_secs(total as u64)));
+
+    loop {
+        match ConnectionPermit::try_acquire(config) {
+            Err(NeutralIpcError::TooManyConnections { .. }) if config.get_queue_connections() => {
+                if let Some((total, deadline)) = deadline {
+                    if Instant::now() >= deadline {
+                        return Err(NeutralIpcError::timeout(format!("total_timeout of {}s exhausted during queue", total)));
+                    }
+                }
+                sleep(QUEUE_POLL_INTERVAL).await;
+            }
+            permit => return permit,
+        }
+    }
+}
+
+/// Sleep for `duration` on the runtime `start_async` runs on.
+async fn sleep(duration: Duration) {
+    #[cfg(all(feature = "async-tokio", feature = "async-std"))]
+    if tokio::runtime::Handle::try_current().is_ok() {
+        tokio::time::sleep(duration).await;
+    } else {
+        async_std::task::sleep(duration).await;
+    }
+    #[cfg(all(feature = "async-tokio", not(feature = "async-std")))]
+    tokio::time::sleep(duration).await;
+    #[cfg(all(feature = "async-std", not(feature = "async-tokio")))]
+    async_std::task::sleep(duration).await;
+}
+
+/// Send the request and read the response over an established connection.
+///
+/// The response is decoded and stored as the result of `client`.
+async fn exchange<S>(client: &mut NeutralIpcClient, stream: &mut S) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let write_buffer_size = client.config().get_write_buffer_size();
+    let read_buffer_size = client.config().get_read_buffer_size();
+
+    let request = client.encode_request();
+    for chunk in request.chunks(write_buffer_size) {
+        stream.write_all(chunk).await?;
+    }
+    stream.flush().await?;
+
+    let (response, response_header) = NeutralIpcRecord::decode_header_from_async_reader(stream).await?;
+
+    let content1 = read_content(stream, response.length1 as usize, read_buffer_size).await?;
+    let content2 = read_content(stream, response.length2 as usize, read_buffer_size).await?;
+
+    client.decode_response(&response_header, &content1, &content2)?;
+    Ok(())
+}
+
+/// Read exactly `length` bytes of content in chunks of at most `buffer_size` bytes.
+///
+/// # Errors
+///
+/// Returns `NeutralIpcError::ConnectionClosed` if the connection is closed
+/// before all data is read. Interrupted reads are retried.
+async fn read_content<R: AsyncRead + Unpin>(reader: &mut R, length: usize, buffer_size: usize) -> Result<Vec<u8>> {
+    let mut content = vec![0u8; length];
+    let mut filled = 0;
+
+    while filled < length {
+        let end = std::cmp::min(filled + buffer_size, length);
+        let bytes_read = match reader.read(&mut content[filled..end]).await {
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            read => read?,
+        };
+        if bytes_read == 0 {
+            return Err(NeutralIpcError::ConnectionClosed);
+        }
+        filled += bytes_read;
+    }
+
+    Ok(content)
+}
+
+/// Error returned when the exchange exceeds the configured timeout.
+fn timed_out() -> NeutralIpcError {
+    NeutralIpcError::timeout("IPC exchange timed out")
+}
+
+/// tokio adapter.
+#[cfg(feature = "async-tokio")]
+mod tokio_runtime {
+    use super::*;
+    use tokio_util::compat::TokioAsyncReadCompatExt;
+
+    /// Connect with tokio and run the exchange within `timeout`.
+    pub(super) async fn start(client: &mut NeutralIpcClient, timeout: Duration) -> Result<()> {
+        let host = client.config().get_host();
+        let port = client.config().get_port();
+
+        let run = async {
+            let mut stream = tokio::net::TcpStream::connect((host.as_str(), port)).await?.compat();
+            exchange(client, &mut stream).await
+        };
+        tokio::time::timeout(timeout, run).await.map_err(|_| timed_out())?
+    }
+}
+
+/// async-std adapter.
+#[cfg(feature = "async-std")]
+mod async_std_runtime {
+    use super::*;
+
+    /// Connect with async-std and run the exchange within `timeout`.
+    pub(super) async fn start(client: &mut NeutralIpcClient, timeout: Duration) -> Result<()> {
+        let host = client.config().get_host();
+        let port = client.config().get_port();
+
+        let run = async {
+            let mut stream = async_std::net::TcpStream::connect((host.as_str(), port)).await?;
+            exchange(client, &mut stream).await
+        };
+        async_std::future::timeout(timeout, run).await.map_err(|_| timed_out())?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NeutralIpcConfig;
+    use crate::constants::*;
+    use crate::mock::{read_request, write_response, MockServer};
+
+    /// Render scenario shared by every runtime: the mock server echoes the template.
+    async fn echo_scenario(config: NeutralIpcConfig) -> Result<String> {
+        let mut client = NeutralIpcClient::with_config(
+            config,
+            CTRL_PARSE_TEMPLATE,
+            CONTENT_JSON,
+            br#"{"data":{"text":"Hello"}}"#,
+            CONTENT_TEXT,
+            b"{:;text:} async",
+        )?;
+        let result = client.start_async().await?;
+        Ok(result.get("content-2").and_then(|v| v.as_str()).unwrap_or_default().to_string())
+    }
+
+    fn echo_server() -> MockServer {
+        MockServer::start(|mut stream| {
+            if let Some((_, _, content2)) = read_request(&mut stream) {
+                write_response(&mut stream, CTRL_STATUS_OK, b"{}", &content2);
+            }
+        })
+    }
+
+    #[cfg(feature = "async-tokio")]
+    #[test]
+    fn test_start_async_tokio() {
+        let server = echo_server();
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+
+        let content = runtime.block_on(echo_scenario(server.config())).unwrap();
+        assert_eq!(content, "{:;text:} async");
+    }
+
+    #[cfg(feature = "async-std")]
+    #[test]
+    fn test_start_async_async_std() {
+        let server = echo_server();
+
+        let content = async_std::task::block_on(echo_scenario(server.config())).unwrap();
+        assert_eq!(content, "{:;text:} async");
+    }
+
+    #[cfg(feature = "async-tokio")]
+    #[test]
+    fn test_start_async_takes_a_slot_of_the_limit() {
+        let _limit = crate::mock::CONNECTION_LIMIT.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let server = echo_server();
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let mut config = server.config();
+        config.set_max_concurrent_connections(Some(1));
+        config.set_queue_connections(false);
+
+        let mut held = NeutralIpcClient::with_config(config.clone(), CTRL_PARSE_TEMPLATE, CONTENT_JSON, b"{}", CONTENT_TEXT, b"").unwrap();
+        let (_, stream) = held.start_stream().unwrap();
+        assert!(matches!(
+            runtime.block_on(echo_scenario(config.clone())),
+            Err(NeutralIpcError::TooManyConnections { limit: 1 })
+        ));
+
+        // Queued requests wait until the slot is released.
+        config.set_queue_connections(true);
+        let release = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            std::mem::drop(stream);
+        });
+        assert_eq!(runtime.block_on(echo_scenario(config)).unwrap(), "{:;text:} async");
+        release.join().unwrap();
+    }
+
+    #[test]
+    fn test_read_content_reports_closed_connection() {
+        let mut reader: &[u8] = b"abc";
+        let content = futures_lite::future::block_on(read_content(&mut reader, 3, 2)).unwrap();
+        assert_eq!(content, b"abc");
+
+        let mut reader: &[u8] = b"ab";
+        assert!(matches!(
+            futures_lite::future::block_on(read_content(&mut reader, 3, 2)),
+            Err(NeutralIpcError::ConnectionClosed)
+        ));
+    }
+
+    /// Reader returning `Interrupted` before every byte it yields.
+    struct InterruptingReader {
+        data: Vec<u8>,
+        interrupt: bool,
+    }
+
+    impl AsyncRead for InterruptingReader {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+            buf: &mut [u8],
+        ) -> std::task::Poll<io::Result<usize>> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return std::task::Poll::Ready(Err(io::Error::from(io::ErrorKind::Interrupted)));
+            }
+            if self.data.is_empty() {
+                return std::task::Poll::Ready(Ok(0));
+            }
+            buf[0] = self.data.remove(0);
+            std::task::Poll::Ready(Ok(1))
+        }
+    }
+
+    #[test]
+    fn test_read_content_retries_interrupted_reads() {
+        let mut reader = InterruptingReader { data: b"interrupted".to_vec(), interrupt: false };
+        let content = futures_lite::future::block_on(read_content(&mut reader, 11, 4)).unwrap();
+        assert_eq!(content, b"interrupted");
+    }
+}
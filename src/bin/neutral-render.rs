@@ -0,0 +1,175 @@
+//! `neutral-render`: render a Neutral template from the command line.
+//!
+//! Available with the `cli` feature. The rendered output is written to stdout;
+//! errors are written to stderr and reported with a non-zero exit code.
+//!
+//! ```text
+//! neutral-render --template <source_or_path> [--schema <json_or_file_or_->] [--config <file>]
+//! ```
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+use neutralipcrs::{NeutralIpcConfig, NeutralIpcTemplate};
+use serde_json::Value;
+
+const USAGE: &str = "\
+Usage: neutral-render --template <source_or_path> [--schema <json_or_file>] [--config <file>]
+
+Options:
+  -t, --template <source_or_path>  Template file path, or template source code if no such file exists
+  -s, --schema <json_or_file>      JSON schema file, JSON string, or - to read it from stdin
+  -c, --config <file>              IPC configuration file (default: /etc/neutral-ipc-cfg.json)
+  -h, --help                       Print this help";
+
+/// Parsed command line arguments.
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    /// Template source code or file path
+    template: String,
+    /// Schema argument, if given
+    schema: Option<String>,
+    /// Configuration file, if given
+    config: Option<String>,
+    /// Whether help was requested
+    help: bool,
+}
+
+/// Parse the command line arguments, without the program name.
+fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut template = None;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("missing value for {}", name));
+        match arg.as_str() {
+            "-t" | "--template" => template = Some(value(&arg)?),
+            "-s" | "--schema" => parsed.schema = Some(value(&arg)?),
+            "-c" | "--config" => parsed.config = Some(value(&arg)?),
+            "-h" | "--help" => parsed.help = true,
+            other => return Err(format!("unexpected argument '{}'", other)),
+        }
+    }
+
+    if parsed.help {
+        return Ok(parsed);
+    }
+    parsed.template = template.ok_or("missing required argument --template")?;
+    Ok(parsed)
+}
+
+/// Read the schema from stdin (`-`), a file, or the argument itself as JSON.
+fn read_schema(schema: Option<&str>) -> Result<Value, String> {
+    let source = match schema {
+        None => return Ok(Value::Object(Default::default())),
+        Some("-") => {
+            let mut source = String::new();
+            io::stdin().read_to_string(&mut source).map_err(|err| format!("cannot read schema from stdin: {}", err))?;
+            source
+        }
+        Some(arg) if Path::new(arg).is_file() => {
+            fs::read_to_string(arg).map_err(|err| format!("cannot read schema file '{}': {}", arg, err))?
+        }
+        Some(arg) => arg.to_string(),
+    };
+
+    serde_json::from_str(&source).map_err(|err| format!("invalid JSON schema: {}", err))
+}
+
+/// Load the configuration file given with `--config`, or the default one.
+fn load_config(config: Option<&str>) -> Result<NeutralIpcConfig, String> {
+    match config {
+        None => Ok(NeutralIpcConfig::new()),
+        Some(path) if !Path::new(path).is_file() => Err(format!("configuration file '{}' not found", path)),
+        Some(path) => NeutralIpcConfig::builder().file(path).build().map_err(|err| err.to_string()),
+    }
+}
+
+/// Render the template described by the arguments.
+fn render(args: &Args) -> Result<String, String> {
+    let schema = read_schema(args.schema.as_deref())?;
+    let config = load_config(args.config.as_deref())?;
+
+    let template = match fs::canonicalize(&args.template) {
+        Ok(path) if path.is_file() => {
+            let path = path.to_str().ok_or("template path is not valid UTF-8")?;
+            NeutralIpcTemplate::from_file_value(path, schema)
+        }
+        _ => NeutralIpcTemplate::from_src_value(&args.template, schema),
+    };
+
+    template
+        .map_err(|err| err.to_string())?
+        .with_config(config)
+        .render()
+        .map_err(|err| err.to_string())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("neutral-render: {}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    if args.help {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    match render(&args) {
+        Ok(output) => {
+            let mut stdout = io::stdout();
+            if stdout.write_all(output.as_bytes()).and_then(|_| stdout.flush()).is_err() {
+                return ExitCode::FAILURE;
+            }
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("neutral-render: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Result<Args, String> {
+        parse_args(list.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = args(&["-t", "{:;text:}", "--schema", "-", "-c", "/tmp/cfg.json"]).unwrap();
+        assert_eq!(parsed, Args {
+            template: "{:;text:}".to_string(),
+            schema: Some("-".to_string()),
+            config: Some("/tmp/cfg.json".to_string()),
+            help: false,
+        });
+
+        assert!(args(&["--help"]).unwrap().help);
+        assert!(args(&[]).unwrap_err().contains("--template"));
+        assert!(args(&["-t"]).unwrap_err().contains("missing value"));
+        assert!(args(&["-t", "x", "--verbose"]).unwrap_err().contains("--verbose"));
+    }
+
+    #[test]
+    fn test_read_schema_from_argument() {
+        assert_eq!(read_schema(None).unwrap(), serde_json::json!({}));
+        assert_eq!(read_schema(Some(r#"{"data":{"text":"Hi"}}"#)).unwrap()["data"]["text"], "Hi");
+        assert!(read_schema(Some("{not json")).unwrap_err().starts_with("invalid JSON schema"));
+    }
+
+    #[test]
+    fn test_load_config_requires_existing_file() {
+        assert!(load_config(Some("/nonexistent/neutral-ipc-cfg.json")).unwrap_err().contains("not found"));
+    }
+}
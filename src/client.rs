@@ -5,11 +5,13 @@
 
 use serde_json::Value;
 use std::collections::HashMap;
-use std::io::{Read, Write};
-use std::net::TcpStream;
-use std::time::Duration;
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::time::{Duration, Instant};
 
-use crate::config::NeutralIpcConfig;
+use crate::config::{NeutralIpcConfig, SchemaSizePolicy};
 use crate::constants::*;
 use crate::error::{NeutralIpcError, Result};
 use crate::record::NeutralIpcRecord;
@@ -31,9 +33,18 @@ pub(crate) struct NeutralIpcClient {
     /// Format identifier for the second content field
     format2: u8,
     /// Second content field (typically template content)
-    content2: String,
+    content2: Vec<u8>,
     /// Parsed result from the server response
     pub(crate) result: HashMap<String, Value>,
+    /// Connection settings loaded when the client is created
+    config: NeutralIpcConfig,
+    /// Request record encoded beforehand, sent instead of encoding the fields above
+    encoded: Option<Arc<[u8]>>,
+    /// Reader streamed as the second content field instead of `content2`
+    source: Option<Arc<SourceReader>>,
+    /// Formats and contents before compression, sent again if the server rejects compressed formats
+    #[cfg(feature = "compression")]
+    uncompressed: Option<[(u8, Vec<u8>); 2]>,
 }
 
 impl NeutralIpcClient {
@@ -46,26 +57,159 @@ impl NeutralIpcClient {
     /// * `content1` - First content field, typically a JSON schema
     /// * `format2` - Format identifier for the second content field (e.g., `CONTENT_TEXT`)
     /// * `content2` - Second content field, typically template content
-    pub(crate) fn new(control: u8, format1: u8, content1: &[u8], format2: u8, content2: &str) -> Self {
-        Self {
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if compression is configured and a content block cannot
+    /// be compressed, or if a template root is configured and the `CONTENT_PATH`
+    /// template in `content2` cannot be resolved (see `resolve_template_path`).
+    pub(crate) fn new(control: u8, format1: u8, content1: &[u8], format2: u8, content2: &[u8]) -> Result<Self> {
+        Self::with_config(NeutralIpcConfig::new(), control, format1, content1, format2, content2)
+    }
+
+    /// Create a new IPC client using the given configuration.
+    ///
+    /// Same as `new`, but uses `config` instead of loading the configuration file.
+    pub(crate) fn with_config(
+        config: NeutralIpcConfig,
+        control: u8,
+        format1: u8,
+        content1: &[u8],
+        format2: u8,
+        content2: &[u8],
+    ) -> Result<Self> {
+        let content2 = if format2 == CONTENT_PATH && config.get_template_root().is_some() {
+            let path = String::from_utf8(content2.to_vec()).map_err(NeutralIpcError::InvalidUtf8)?;
+            resolve_template_path(&config, &path)?.into_bytes()
+        } else {
+            content2.to_vec()
+        };
+
+        #[allow(unused_mut)]
+        let mut client = Self {
             control,
             format1,
             content1: content1.to_vec(),
             format2,
-            content2: content2.to_string(),
+            content2,
+            result: HashMap::new(),
+            config,
+            encoded: None,
+            source: None,
+            #[cfg(feature = "compression")]
+            uncompressed: None,
+        };
+        #[cfg(feature = "compression")]
+        client.compress()?;
+        Ok(client)
+    }
+
+    /// Compress the JSON and text content blocks with the configured compression.
+    ///
+    /// Compressed blocks are flagged with the compressed formats, e.g.
+    /// `CONTENT_JSON_GZ`; paths and other formats are sent as they are. The
+    /// original blocks are kept for `fall_back_uncompressed`.
+    #[cfg(feature = "compression")]
+    fn compress(&mut self) -> Result<()> {
+        let Some(compression) = self.config.get_compression() else {
+            return Ok(());
+        };
+        let format1 = crate::compression::compressed_format(self.format1, compression);
+        let format2 = crate::compression::compressed_format(self.format2, compression);
+        if format1.is_none() && format2.is_none() {
+            return Ok(());
+        }
+
+        let uncompressed = [(self.format1, self.content1.clone()), (self.format2, self.content2.clone())];
+        if let Some(format1) = format1 {
+            self.content1 = crate::compression::compress(compression, &self.content1)?;
+            self.format1 = format1;
+        }
+        if let Some(format2) = format2 {
+            self.content2 = crate::compression::compress(compression, &self.content2)?;
+            self.format2 = format2;
+        }
+        self.uncompressed = Some(uncompressed);
+        Ok(())
+    }
+
+    /// Restore the uncompressed content blocks if the server rejected the compressed formats.
+    ///
+    /// Servers without compressed formats answer `CTRL_STATUS_KO` with a
+    /// diagnostic naming an unsupported format. Requests whose source is
+    /// streamed from a reader cannot be sent again and are not restored.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the blocks were restored and the request must be sent again.
+    pub(crate) fn fall_back_uncompressed(&mut self) -> bool {
+        #[cfg(feature = "compression")]
+        {
+            let rejected = self.result.get("control").and_then(|v| v.as_u64()) == Some(CTRL_STATUS_KO as u64)
+                && self
+                    .result
+                    .get("content-1")
+                    .and_then(|v| v.as_str())
+                    .is_some_and(|diagnostics| diagnostics.to_lowercase().contains("unsupported format"));
+            if rejected && self.source.is_none() {
+                if let Some([(format1, content1), (format2, content2)]) = self.uncompressed.take() {
+                    self.format1 = format1;
+                    self.content1 = content1;
+                    self.format2 = format2;
+                    self.content2 = content2;
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Create an IPC client sending a request record encoded beforehand.
+    ///
+    /// The record is sent as is with `control` as its control code; the
+    /// template root and compression of `config` are not applied to it.
+    pub(crate) fn with_encoded_request(config: NeutralIpcConfig, control: u8, request: Arc<[u8]>) -> Self {
+        Self {
+            control,
+            format1: CONTENT_JSON,
+            content1: Vec::new(),
+            format2: CONTENT_TEXT,
+            content2: Vec::new(),
             result: HashMap::new(),
+            config,
+            encoded: Some(request),
+            source: None,
+            #[cfg(feature = "compression")]
+            uncompressed: None,
         }
     }
 
+    /// Stream the second content field from `source` instead of `content2`.
+    ///
+    /// The reader is consumed by the first request; sending it again fails
+    /// with `NeutralIpcError::SourceReaderUnavailable`.
+    ///
+    /// The streamed source is sent uncompressed.
+    pub(crate) fn set_source_reader(&mut self, source: Arc<SourceReader>) {
+        #[cfg(feature = "compression")]
+        if let Some([_, (format2, _)]) = &self.uncompressed {
+            self.format2 = *format2;
+        }
+        self.source = Some(source);
+    }
+
     /// Start the IPC communication with the Neutral server.
     ///
     /// This method:
-    /// 1. Loads configuration for host, port, timeout, and buffer size
+    /// 1. Reads the configured host, port, timeout, and buffer sizes
     /// 2. Establishes a TCP connection to the configured server
-    /// 3. Sets read/write timeouts based on configuration
-    /// 4. Encodes and sends the request record
-    /// 5. Reads and decodes the response
-    /// 6. Stores the parsed result
+    /// 3. Sets read/write timeouts based on configuration, shrunk to the time
+    ///    left of the `total_timeout` budget if one is configured
+    /// 4. Encodes and sends the request record in write-buffer-sized chunks
+    ///    through a `BufWriter`, flushed before waiting for the response
+    /// 5. Reads and decodes the response through a read-buffer-sized `BufReader`
+    /// 6. Stores the parsed result, with the address of the server (or proxy)
+    ///    the connection was made to under `endpoint`
     ///
     /// # Returns
     ///
@@ -76,48 +220,211 @@ impl NeutralIpcClient {
     /// Returns an error if:
     /// - Connection to the server fails
     /// - Network I/O operations fail
+    /// - A timeout expires (`NeutralIpcError::Io` with `ErrorKind::TimedOut`,
+    ///   whose message names the exhausted budget)
     /// - The server response is invalid or malformed
     /// - UTF-8 decoding of response content fails
+    ///
+    /// If the server rejects the compressed formats of the request, it is
+    /// sent again uncompressed (see `fall_back_uncompressed`).
     pub(crate) fn start(&mut self) -> Result<&HashMap<String, Value>> {
-        let config = NeutralIpcConfig::new();
+        self.exchange()?;
+        if self.fall_back_uncompressed() {
+            self.exchange()?;
+        }
+        Ok(&self.result)
+    }
+
+    /// Send the request and store the decoded response as the result, see `start`.
+    fn exchange(&mut self) -> Result<()> {
+        let read_buffer_size = self.config.get_read_buffer_size();
+        let (stream, budget, _permit) = self.send_request()?;
+
+        let mut reader = BufReader::with_capacity(read_buffer_size, &stream);
+        stream.set_read_timeout(Some(budget.next("read")?))?;
+        let response_header = budget.check(NeutralIpcRecord::read_header(&mut reader), "read")?;
+        let response = NeutralIpcRecord::decode_header(&response_header)?;
+
+        let content1 = self.read_content(&mut reader, response.length1 as usize, read_buffer_size, &budget)?;
+        let content2 = self.read_content(&mut reader, response.length2 as usize, read_buffer_size, &budget)?;
+
+        self.decode_response(&response_header, &content1, &content2)?;
+        if let Ok(peer) = stream.peer_addr() {
+            self.result.insert("endpoint".to_string(), Value::String(peer.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Start the IPC communication, streaming the second content block of the response.
+    ///
+    /// Same as `start`, but returns once the response header and `content-1`
+    /// are read. The stored result holds both with an empty `content-2`; the
+    /// returned stream yields `content-2` as it arrives. A compressed
+    /// `content-2` (`CONTENT_BIN` or a compressed format) is read and
+    /// decompressed in full first.
+    ///
+    /// # Errors
+    ///
+    /// See `start`.
+    pub(crate) fn start_stream(&mut self) -> Result<(&HashMap<String, Value>, RenderStream)> {
+        let mut stream = self.exchange_stream()?;
+        if self.fall_back_uncompressed() {
+            // Release the connection and its slot before sending again.
+            drop(stream);
+            stream = self.exchange_stream()?;
+        }
+        Ok((&self.result, stream))
+    }
+
+    /// Send the request and read the response up to `content-1`, see `start_stream`.
+    fn exchange_stream(&mut self) -> Result<RenderStream> {
+        let read_buffer_size = self.config.get_read_buffer_size();
+        let (stream, budget, permit) = self.send_request()?;
+        let peer = stream.peer_addr();
+
+        let mut reader = BufReader::with_capacity(read_buffer_size, stream);
+        reader.set_read_timeout(Some(budget.next("read")?))?;
+        let response_header = budget.check(NeutralIpcRecord::read_header(&mut reader), "read")?;
+        let response = NeutralIpcRecord::decode_header(&response_header)?;
+        let content1 = self.read_content(&mut reader, response.length1 as usize, read_buffer_size, &budget)?;
+
+        self.result = NeutralIpcRecord::decode_record_head(&response_header, &content1)?;
+        if let Ok(peer) = peer {
+            self.result.insert("endpoint".to_string(), Value::String(peer.to_string()));
+        }
+
+        let checksum = NeutralIpcRecord::header_checksum(&response_header).map(|expected| {
+            let mut hasher = crc32fast::Hasher::new();
+            hasher.update(&content1);
+            (expected, hasher)
+        });
+        let mut stream = RenderStream {
+            reader: Some(reader),
+            permit,
+            remaining: response.length2 as usize,
+            chunk_size: read_buffer_size,
+            budget,
+            checksum,
+            pending: None,
+            buffer: Vec::new(),
+            position: 0,
+        };
+
+        if response.format2.is_compressed() {
+            let content2 = Iterator::by_ref(&mut stream).collect::<Result<Vec<_>>>()?.concat();
+            let content2 = NeutralIpcRecord::decode_content(response.format2, &content2)?;
+            stream.pending = Some(content2.into_bytes());
+        }
+        Ok(stream)
+    }
+
+    /// Connect to the server and send the request record.
+    ///
+    /// The record is written in write-buffer-sized chunks through a
+    /// `BufWriter`, flushed before returning.
+    ///
+    /// A slot of the `max_concurrent_connections` limit is taken before
+    /// connecting, waiting for one within the `total_timeout` budget.
+    ///
+    /// # Returns
+    ///
+    /// The connected stream, the timeouts of the request, started before
+    /// connecting, and the slot of the connection limit, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::TooManyConnections` if the limit is reached
+    /// and `queue_connections` is disabled.
+    fn send_request(&self) -> Result<(TcpStream, RequestBudget, Option<ConnectionPermit>)> {
+        let config = &self.config;
         let host = config.get_host();
         let port = config.get_port();
-        let timeout = config.get_timeout();
-        let buffer_size = config.get_buffer_size();
+        let write_buffer_size = config.get_write_buffer_size();
+        let budget = RequestBudget::new(config);
+        let permit = ConnectionPermit::acquire(config, &budget)?;
 
-        let mut stream = TcpStream::connect(format!("{}:{}", host, port))?;
-        stream.set_read_timeout(Some(Duration::from_secs(timeout as u64)))?;
-        stream.set_write_timeout(Some(Duration::from_secs(timeout as u64)))?;
+        let stream = budget.check(connect(config, &host, port, Some(budget.next("connect")?)), "connect")?;
 
-        let request = NeutralIpcRecord::encode_record(
+        let mut writer = BufWriter::with_capacity(write_buffer_size, &stream);
+        let mut write = |chunk: &[u8]| -> Result<()> {
+            stream.set_write_timeout(Some(budget.next("write")?))?;
+            budget.check(writer.write_all(chunk).map_err(NeutralIpcError::from), "write")
+        };
+        match (&self.encoded, &self.source) {
+            (Some(request), _) => request.chunks(write_buffer_size).try_for_each(&mut write)?,
+            // The checksum precedes the contents, so the source is read in full first.
+            (None, Some(source)) if !config.get_checksum() => {
+                let header = NeutralIpcRecord::encode_header(
+                    self.control,
+                    self.format1,
+                    self.content1.len() as u32,
+                    self.format2,
+                    source.len as u32,
+                );
+                write(&header)?;
+                self.content1.chunks(write_buffer_size).try_for_each(&mut write)?;
+                source.stream(write_buffer_size, &mut write)?;
+            }
+            (None, Some(source)) => {
+                let mut content2 = Vec::new();
+                source.stream(write_buffer_size, |chunk| {
+                    content2.extend_from_slice(chunk);
+                    Ok(())
+                })?;
+                let request = NeutralIpcRecord::encode_record_checksum(
+                    self.control,
+                    self.format1,
+                    &self.content1,
+                    self.format2,
+                    &content2,
+                );
+                request.chunks(write_buffer_size).try_for_each(&mut write)?;
+            }
+            (None, None) => self.encode_request().chunks(write_buffer_size).try_for_each(&mut write)?,
+        }
+        stream.set_write_timeout(Some(budget.next("write")?))?;
+        budget.check(writer.flush().map_err(NeutralIpcError::from), "write")?;
+        drop(writer);
+
+        Ok((stream, budget, permit))
+    }
+
+    /// Get the connection settings of this client.
+    #[cfg(any(feature = "async-tokio", feature = "async-std"))]
+    pub(crate) fn config(&self) -> &NeutralIpcConfig {
+        &self.config
+    }
+
+    /// Encode the request record, with a checksum if enabled in the configuration.
+    pub(crate) fn encode_request(&self) -> Vec<u8> {
+        let encode = if self.config.get_checksum() {
+            NeutralIpcRecord::encode_record_checksum
+        } else {
+            NeutralIpcRecord::encode_record
+        };
+        encode(
             self.control,
             self.format1,
             &self.content1,
             self.format2,
-            self.content2.as_bytes(),
-        );
-        stream.write_all(&request)?;
-
-        let mut response_header = vec![0u8; HEADER_LEN];
-        stream.read_exact(&mut response_header)?;
-
-        let response = NeutralIpcRecord::decode_header(&response_header)?;
-        let length1 = response.get("length-1")
-            .and_then(|v| v.as_u64())
-            .ok_or(NeutralIpcError::InvalidResponse)? as usize;
-        let length2 = response.get("length-2")
-            .and_then(|v| v.as_u64())
-            .ok_or(NeutralIpcError::InvalidResponse)? as usize;
-
-        let content1 = self.read_content(&mut stream, length1, buffer_size)?;
-        let content2 = self.read_content(&mut stream, length2, buffer_size)?;
+            &self.content2,
+        )
+    }
 
-        self.result = NeutralIpcRecord::decode_record(&response_header, &content1, &content2)?;
+    /// Decode the response record and store it as the result.
+    ///
+    /// Invalid UTF-8 in the rendered content is replaced if `lossy_utf8` is enabled.
+    pub(crate) fn decode_response(&mut self, header: &[u8], content1: &[u8], content2: &[u8]) -> Result<&HashMap<String, Value>> {
+        self.result = if self.config.get_lossy_utf8() {
+            NeutralIpcRecord::decode_record_lossy(header, content1, content2)?
+        } else {
+            NeutralIpcRecord::decode_record(header, content1, content2)?
+        };
 
         Ok(&self.result)
     }
 
-    /// Read content from the TCP stream in chunks.
+    /// Read content from the buffered TCP stream in chunks.
     ///
     /// This method reads exactly `length` bytes from the stream, handling
     /// partial reads and buffering. It ensures that the entire content is
@@ -125,22 +432,22 @@ impl NeutralIpcClient {
     ///
     /// # Arguments
     ///
-    /// * `stream` - The TCP stream to read from
+    /// * `reader` - The buffered TCP stream to read from
     /// * `length` - The exact number of bytes to read
     /// * `buffer_size` - The maximum size of each read chunk
+    /// * `budget` - Timeouts of the request, applied to every read
     ///
     /// # Returns
     ///
-    /// The read content as a UTF-8 string.
+    /// The raw content bytes.
     ///
     /// # Errors
     ///
-    /// Returns an error if:
-    /// - The connection is closed before all data is read
-    /// - The content cannot be decoded as valid UTF-8
-    fn read_content(&self, stream: &mut TcpStream, length: usize, buffer_size: usize) -> Result<String> {
+    /// Returns `NeutralIpcError::ConnectionClosed` if the connection is closed
+    /// before all data is read. Interrupted reads are retried.
+    fn read_content<R: TimedRead>(&self, reader: &mut R, length: usize, buffer_size: usize, budget: &RequestBudget) -> Result<Vec<u8>> {
         if length == 0 {
-            return Ok(String::new());
+            return Ok(Vec::new());
         }
 
         let mut chunks = Vec::new();
@@ -149,7 +456,11 @@ impl NeutralIpcClient {
         while remaining > 0 {
             let chunk_size = std::cmp::min(buffer_size, remaining);
             let mut chunk = vec![0u8; chunk_size];
-            let bytes_read = stream.read(&mut chunk)?;
+            reader.set_read_timeout(Some(budget.next("read")?))?;
+            let bytes_read = match reader.read(&mut chunk) {
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                read => budget.check(read.map_err(NeutralIpcError::from), "read")?,
+            };
 
             if bytes_read == 0 {
                 return Err(NeutralIpcError::ConnectionClosed);
@@ -159,16 +470,444 @@ impl NeutralIpcClient {
             remaining -= bytes_read;
         }
 
-        String::from_utf8(chunks).map_err(|_| NeutralIpcError::InvalidUtf8)
+        Ok(chunks)
+    }
+}
+
+/// Reader whose timeout can be changed between reads.
+trait TimedRead: Read {
+    /// Set the timeout of the next reads.
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+}
+
+impl TimedRead for BufReader<&TcpStream> {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.get_ref().set_read_timeout(timeout)
+    }
+}
+
+impl TimedRead for BufReader<TcpStream> {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.get_ref().set_read_timeout(timeout)
+    }
+}
+
+/// Rendered content streamed from the server as it arrives.
+///
+/// Returned by `NeutralIpcTemplate::render_stream` once the status of the
+/// render has been read. Iterating yields the rendered content (`content-2`)
+/// in chunks of at most the read buffer size, as the bytes are received; the
+/// stream also implements `Read`. The configured timeouts apply to every
+/// read. After the last chunk, the checksum of v1 records is verified; the
+/// iterator ends after the first error.
+///
+/// Dropping the stream before the end shuts the connection down.
+///
+/// # Example
+///
+/// ```no_run
+/// use neutralipcrs::NeutralIpcTemplate;
+/// use serde_json::json;
+/// use std::io::Write;
+///
+/// let mut template = NeutralIpcTemplate::from_file_value("events.ntpl", json!({})).unwrap();
+/// let mut out = std::io::stdout();
+/// for chunk in template.render_stream().unwrap() {
+///     out.write_all(&chunk.unwrap()).unwrap();
+/// }
+/// ```
+pub struct RenderStream {
+    /// Buffered connection; `None` once the content is read or after an error
+    reader: Option<BufReader<TcpStream>>,
+    /// Slot of the connection limit, released with the connection
+    permit: Option<ConnectionPermit>,
+    /// Bytes of the content not yet read
+    remaining: usize,
+    /// Maximum size of each chunk
+    chunk_size: usize,
+    /// Timeouts of the request
+    budget: RequestBudget,
+    /// Checksum declared by a v1 header and the hasher of the content read so far
+    checksum: Option<(u32, crc32fast::Hasher)>,
+    /// Content to yield before reading, e.g. a decompressed content
+    pending: Option<Vec<u8>>,
+    /// Chunk being consumed through `Read`
+    buffer: Vec<u8>,
+    /// Position of the next byte of `buffer`
+    position: usize,
+}
+
+impl RenderStream {
+    /// Read the next chunk of the content.
+    ///
+    /// # Returns
+    ///
+    /// The chunk, or `None` once the whole content is read.
+    fn read_chunk(&mut self) -> Result<Option<Vec<u8>>> {
+        let reader = match self.reader.as_mut() {
+            Some(reader) => reader,
+            None => return Ok(None),
+        };
+        if self.remaining == 0 {
+            self.reader = None;
+            self.permit = None;
+            if let Some((expected, hasher)) = self.checksum.take() {
+                let actual = hasher.finalize();
+                if actual != expected {
+                    return Err(NeutralIpcError::ChecksumMismatch { expected, actual });
+                }
+            }
+            return Ok(None);
+        }
+
+        let mut chunk = vec![0u8; self.chunk_size.min(self.remaining)];
+        let bytes_read = loop {
+            reader.set_read_timeout(Some(self.budget.next("read")?))?;
+            match reader.read(&mut chunk) {
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                read => break self.budget.check(read.map_err(NeutralIpcError::from), "read")?,
+            }
+        };
+        if bytes_read == 0 {
+            return Err(NeutralIpcError::ConnectionClosed);
+        }
+
+        chunk.truncate(bytes_read);
+        self.remaining -= bytes_read;
+        if let Some((_, hasher)) = self.checksum.as_mut() {
+            hasher.update(&chunk);
+        }
+        Ok(Some(chunk))
+    }
+
+    /// Shut the connection down, if still open.
+    fn shutdown(&mut self) {
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.get_ref().shutdown(Shutdown::Both);
+        }
+        self.permit = None;
+    }
+}
+
+impl Iterator for RenderStream {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(pending) = self.pending.take() {
+            return Some(Ok(pending));
+        }
+        match self.read_chunk() {
+            Ok(chunk) => chunk.map(Ok),
+            Err(err) => {
+                self.shutdown();
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl Read for RenderStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.buffer.len() {
+            match self.next() {
+                Some(Ok(chunk)) => {
+                    self.buffer = chunk;
+                    self.position = 0;
+                }
+                Some(Err(NeutralIpcError::Io(err))) => return Err(err),
+                Some(Err(err)) => return Err(io::Error::other(err)),
+                None => return Ok(0),
+            }
+        }
+
+        let len = buf.len().min(self.buffer.len() - self.position);
+        buf[..len].copy_from_slice(&self.buffer[self.position..self.position + len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+impl Drop for RenderStream {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Template source streamed from a reader instead of held in memory.
+///
+/// Shared by the template and the clients of its renders; the reader is
+/// taken by the first request that sends it.
+pub(crate) struct SourceReader {
+    /// Declared length of the source in bytes
+    len: u64,
+    /// Reader of the source, until a request takes it
+    reader: Mutex<Option<Box<dyn Read + Send>>>,
+}
+
+impl SourceReader {
+    /// Wrap a reader that yields exactly `len` bytes.
+    pub(crate) fn new(len: u64, reader: Box<dyn Read + Send>) -> Self {
+        Self { len, reader: Mutex::new(Some(reader)) }
+    }
+
+    /// Get the declared length of the source in bytes.
+    pub(crate) fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Take the reader and pass its bytes to `write` in chunks of at most `chunk_size` bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::SourceReaderUnavailable` if the reader was
+    /// already taken, `NeutralIpcError::ContentLengthMismatch` as soon as the
+    /// reader yields more bytes than declared or ends before, or the error of
+    /// the reader or of `write`.
+    fn stream(&self, chunk_size: usize, mut write: impl FnMut(&[u8]) -> Result<()>) -> Result<()> {
+        let mut reader = self
+            .reader
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+            .ok_or_else(|| NeutralIpcError::SourceReaderUnavailable("already consumed by an earlier render".to_string()))?;
+
+        let mut buffer = vec![0; chunk_size.max(1)];
+        let mut sent = 0u64;
+        loop {
+            let read = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            };
+            if sent + read as u64 > self.len {
+                return Err(NeutralIpcError::ContentLengthMismatch { expected: self.len, actual: sent + read as u64 });
+            }
+            write(&buffer[..read])?;
+            sent += read as u64;
+        }
+
+        if sent != self.len {
+            return Err(NeutralIpcError::ContentLengthMismatch { expected: self.len, actual: sent });
+        }
+        Ok(())
+    }
+}
+
+/// Timeouts of a single request.
+///
+/// Every socket operation gets the per-phase `timeout` of the configuration,
+/// shrunk to the time left of the `total_timeout` budget when one is set.
+struct RequestBudget {
+    /// Per-phase timeout
+    timeout: Duration,
+    /// Timeout of the connect phase
+    connect_timeout: Duration,
+    /// Total budget in seconds and the deadline it sets, if configured
+    total: Option<(u16, Instant)>,
+}
+
+impl RequestBudget {
+    /// Start the budget of a request now.
+    fn new(config: &NeutralIpcConfig) -> Self {
+        Self {
+            timeout: config.get_timeout_duration(),
+            connect_timeout: config.get_connect_timeout(),
+            total: config
+                .get_total_timeout()
+                .map(|total| (total, Instant::now() + Duration::from_secs(total as u64))),
+        }
+    }
+
+    /// Get the socket timeout for the next operation of `phase`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a timeout error if the total budget is already exhausted.
+    fn next(&self, phase: &str) -> Result<Duration> {
+        let timeout = self.phase_timeout(phase);
+        match self.total {
+            None => Ok(timeout),
+            Some((_, deadline)) => match deadline.checked_duration_since(Instant::now()).filter(|left| !left.is_zero()) {
+                Some(left) => Ok(left.min(timeout)),
+                None => Err(self.timed_out(phase)),
+            },
+        }
+    }
+
+    /// Replace a socket timeout error of `phase` with one naming the exhausted budget.
+    fn check<T>(&self, result: Result<T>, phase: &str) -> Result<T> {
+        result.map_err(|err| if err.is_timeout() { self.timed_out(phase) } else { err })
+    }
+
+    /// Build the timeout error of `phase`.
+    fn timed_out(&self, phase: &str) -> NeutralIpcError {
+        let message = match self.total {
+            Some((total, deadline)) if Instant::now() >= deadline => {
+                format!("total_timeout of {}s exhausted during {}", total, phase)
+            }
+            _ => format!("timeout of {:?} exceeded during {}", self.phase_timeout(phase), phase),
+        };
+        NeutralIpcError::timeout(message)
+    }
+
+    /// Get the socket timeout of `phase`, before the total budget applies.
+    fn phase_timeout(&self, phase: &str) -> Duration {
+        if phase == "connect" { self.connect_timeout } else { self.timeout }
     }
 }
 
+/// Number of connections holding a slot of the connection limit in the process.
+static OPEN_CONNECTIONS: Mutex<usize> = Mutex::new(0);
+
+/// Notified when a connection releases its slot.
+static CONNECTION_RELEASED: Condvar = Condvar::new();
+
+/// Slot of the process-wide `max_concurrent_connections` limit, released when dropped.
+pub(crate) struct ConnectionPermit;
+
+impl ConnectionPermit {
+    /// Take a slot if the configuration limits the open connections.
+    ///
+    /// While the limit is reached, waits for a slot until the total budget of
+    /// the request is exhausted, or fails at once if `queue_connections` is
+    /// disabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::TooManyConnections` if the limit is reached
+    /// and queueing is disabled, or a timeout error naming the `queue` phase.
+    fn acquire(config: &NeutralIpcConfig, budget: &RequestBudget) -> Result<Option<Self>> {
+        let Some(limit) = config.get_max_concurrent_connections() else {
+            return Ok(None);
+        };
+
+        let mut open = OPEN_CONNECTIONS.lock().unwrap_or_else(PoisonError::into_inner);
+        while *open >= limit {
+            if !config.get_queue_connections() {
+                return Err(NeutralIpcError::TooManyConnections { limit });
+            }
+            open = match budget.total {
+                None => CONNECTION_RELEASED.wait(open).unwrap_or_else(PoisonError::into_inner),
+                Some((_, deadline)) => {
+                    let left = deadline
+                        .checked_duration_since(Instant::now())
+                        .filter(|left| !left.is_zero())
+                        .ok_or_else(|| budget.timed_out("queue"))?;
+                    CONNECTION_RELEASED.wait_timeout(open, left).unwrap_or_else(PoisonError::into_inner).0
+                }
+            };
+        }
+        *open += 1;
+        Ok(Some(Self))
+    }
+
+    /// Take a slot for a connection opened outside `send_request`.
+    ///
+    /// Waits as long as the `total_timeout` of `config` allows, like `acquire`.
+    pub(crate) fn acquire_for(config: &NeutralIpcConfig) -> Result<Option<Self>> {
+        Self::acquire(config, &RequestBudget::new(config))
+    }
+
+    /// Take a slot if one is free, without waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::TooManyConnections` if the limit is reached,
+    /// whether or not `queue_connections` is enabled.
+    #[cfg(any(feature = "async-tokio", feature = "async-std"))]
+    pub(crate) fn try_acquire(config: &NeutralIpcConfig) -> Result<Option<Self>> {
+        let Some(limit) = config.get_max_concurrent_connections() else {
+            return Ok(None);
+        };
+
+        let mut open = OPEN_CONNECTIONS.lock().unwrap_or_else(PoisonError::into_inner);
+        if *open >= limit {
+            return Err(NeutralIpcError::TooManyConnections { limit });
+        }
+        *open += 1;
+        Ok(Some(Self))
+    }
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        *OPEN_CONNECTIONS.lock().unwrap_or_else(PoisonError::into_inner) -= 1;
+        // Waiters may have different limits: the one woken by `notify_one`
+        // could still be over its own limit while another could proceed.
+        CONNECTION_RELEASED.notify_all();
+    }
+}
+
+/// Apply the schema size policy of `config` to a schema of `size` bytes over `limit`.
+///
+/// With `SchemaSizePolicy::Warn` the request is sent anyway: the warning is
+/// logged with the `logging` feature and written to stderr otherwise.
+///
+/// # Errors
+///
+/// Returns `NeutralIpcError::SchemaTooLarge` if the schema is over the limit
+/// and the policy is `SchemaSizePolicy::Fail`.
+pub(crate) fn check_schema_size(config: &NeutralIpcConfig, size: usize, limit: Option<usize>) -> Result<()> {
+    let Some(limit) = limit.filter(|limit| size > *limit) else {
+        return Ok(());
+    };
+    match config.get_schema_size_policy() {
+        SchemaSizePolicy::Fail => Err(NeutralIpcError::SchemaTooLarge { size, limit }),
+        SchemaSizePolicy::Warn => {
+            #[cfg(feature = "logging")]
+            log::warn!("schema of {} bytes exceeds the limit of {} bytes", size, limit);
+            #[cfg(not(feature = "logging"))]
+            eprintln!("neutralipcrs: schema of {} bytes exceeds the limit of {} bytes", size, limit);
+            Ok(())
+        }
+    }
+}
+
+/// Open a TCP connection to the server, through the configured proxy if any.
+///
+/// A direct connection attempt is bounded by `timeout`, if given. Connections
+/// through a proxy are not.
+///
+/// # Errors
+///
+/// Returns `NeutralIpcError::ProxyError` if the proxy handshake fails, or an
+/// IO error if the direct connection fails.
+pub(crate) fn connect(config: &NeutralIpcConfig, host: &str, port: u16, timeout: Option<Duration>) -> Result<TcpStream> {
+    #[cfg(feature = "proxy")]
+    if let Some((proxy_host, proxy_port)) = config.get_proxy() {
+        return socks::Socks5Stream::connect((proxy_host.as_str(), proxy_port), (host, port))
+            .map(|stream| stream.into_inner())
+            .map_err(|err| NeutralIpcError::ProxyError(err.to_string()));
+    }
+    #[cfg(not(feature = "proxy"))]
+    let _ = config;
+
+    let Some(timeout) = timeout else {
+        return Ok(TcpStream::connect((host, port))?);
+    };
+    let mut last_error = io::Error::new(ErrorKind::InvalidInput, "host resolved to no addresses");
+    for addr in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last_error = err,
+        }
+    }
+    Err(last_error.into())
+}
+
+/// Maximum time budget of `is_server_available`, whatever the configured timeout.
+const MAX_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Connect timeout of `is_server_available_nonblocking`.
+const NONBLOCKING_PROBE_TIMEOUT: Duration = Duration::from_millis(1);
+
 /// Check if the Neutral server is available and responding.
 ///
-/// This function performs a lightweight availability check by:
-/// 1. Attempting to connect to the server with a 1-second timeout
-/// 2. Sending a minimal valid request
-/// 3. Reading the response header to verify the server is responsive
+/// Loads the configuration once and probes the server within its timeout,
+/// capped at 2 seconds (or within 2 seconds if the timeout is zero). See
+/// `is_server_available_within` for a probe with a custom configuration and
+/// timeout.
 ///
 /// # Returns
 ///
@@ -179,34 +918,892 @@ impl NeutralIpcClient {
 /// This function is primarily used in tests, but may be useful for runtime server availability checks.
 pub fn is_server_available() -> bool {
     let config = NeutralIpcConfig::new();
-    let host = config.get_host();
-    let port = config.get_port();
-
-    match TcpStream::connect_timeout(
-        &format!("{}:{}", host, port).parse().unwrap(),
-        std::time::Duration::from_secs(1)
-    ) {
-        Ok(mut stream) => {
-
-            let minimal_request = NeutralIpcRecord::encode_record(
-                CTRL_PARSE_TEMPLATE,
-                CONTENT_JSON,
-                b"{}",
-                CONTENT_TEXT,
-                b""
-            );
-
-            stream.set_read_timeout(Some(std::time::Duration::from_secs(1))).ok();
-            stream.set_write_timeout(Some(std::time::Duration::from_secs(1))).ok();
-
-            match stream.write_all(&minimal_request) {
-                Ok(_) => {
-                    let mut header_buffer = [0u8; HEADER_LEN];
-                    stream.read_exact(&mut header_buffer).is_ok()
-                },
-                Err(_) => false
-            }
-        },
-        Err(_) => false
+    let timeout = match config.get_timeout_duration() {
+        timeout if timeout.is_zero() => MAX_PROBE_TIMEOUT,
+        timeout => timeout.min(MAX_PROBE_TIMEOUT),
+    };
+    is_server_available_within(&config, timeout)
+}
+
+/// Check if the Neutral server is available and responding within `timeout`.
+///
+/// Same as `is_server_available`, with a custom time budget for the probe;
+/// see `is_server_available_within` to also pass the configuration.
+///
+/// # Arguments
+///
+/// * `timeout` - Total time budget for the probe
+///
+/// # Returns
+///
+/// `true` if the server is available and responding correctly, `false` otherwise.
+pub fn is_server_available_with_timeout(timeout: Duration) -> bool {
+    is_server_available_within(&NeutralIpcConfig::new(), timeout)
+}
+
+/// Check if the configured server port is accepting connections.
+///
+/// Only opens a TCP connection, with a 1-millisecond timeout, and closes it
+/// without sending a record; cheap enough for health checks run on every
+/// request. A listening port does not prove the server is responding, see
+/// `is_server_available_with_timeout` for a full probe.
+///
+/// # Returns
+///
+/// `true` if the connection was accepted, `false` otherwise.
+pub fn is_server_available_nonblocking() -> bool {
+    is_port_listening(&NeutralIpcConfig::new(), NONBLOCKING_PROBE_TIMEOUT)
+}
+
+/// Check if the server port of `config` accepts a connection within `timeout`.
+fn is_port_listening(config: &NeutralIpcConfig, timeout: Duration) -> bool {
+    match (config.get_host().as_str(), config.get_port()).to_socket_addrs() {
+        Ok(addrs) => addrs.into_iter().any(|addr| TcpStream::connect_timeout(&addr, timeout).is_ok()),
+        Err(_) => false,
+    }
+}
+
+/// Check if the Neutral server is available and responding within a time budget.
+///
+/// This function performs a lightweight availability check by:
+/// 1. Connecting to the configured server the same way as a render: every
+///    address the host resolves to is tried, through the configured proxy if
+///    any (`proxy` feature)
+/// 2. Sending a minimal valid request
+/// 3. Reading the response header to verify the server is responsive
+/// 4. Reading the rest of the response and shutting the connection down, so
+///    no unread bytes are left on the socket
+///
+/// Each step is bounded by the time left of `timeout`, so the whole probe never
+/// takes longer than the supplied budget, except for the connection through a
+/// proxy, which is not bounded, as for renders.
+///
+/// # Arguments
+///
+/// * `config` - Configuration providing the server host and port
+/// * `timeout` - Total time budget for the probe
+///
+/// # Returns
+///
+/// `true` if the server is available and responding correctly, `false` otherwise.
+///
+/// # Example
+///
+/// ```no_run
+/// use neutralipcrs::client::is_server_available_within;
+/// use neutralipcrs::NeutralIpcConfig;
+/// use std::time::Duration;
+///
+/// let config = NeutralIpcConfig::new();
+/// if is_server_available_within(&config, Duration::from_millis(100)) {
+///     println!("Neutral server is up");
+/// }
+/// ```
+pub fn is_server_available_within(config: &NeutralIpcConfig, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    let remaining = || deadline.checked_duration_since(Instant::now()).filter(|d| !d.is_zero());
+
+    let budget = match remaining() {
+        Some(budget) => budget,
+        None => return false,
+    };
+    let mut stream = match connect(config, &config.get_host(), config.get_port(), Some(budget)) {
+        Ok(stream) => stream,
+        Err(_) => return false,
+    };
+
+    let minimal_request = NeutralIpcRecord::encode_record(
+        CTRL_PARSE_TEMPLATE,
+        CONTENT_JSON,
+        b"{}",
+        CONTENT_TEXT,
+        b""
+    );
+
+    match remaining() {
+        Some(budget) if stream.set_write_timeout(Some(budget)).is_ok() => {}
+        _ => return false,
+    }
+    if stream.write_all(&minimal_request).is_err() {
+        return false;
+    }
+
+    match remaining() {
+        Some(budget) if stream.set_read_timeout(Some(budget)).is_ok() => {}
+        _ => return false,
+    }
+    let header = match NeutralIpcRecord::decode_header_from_reader(&mut stream) {
+        Ok(header) => header,
+        Err(_) => return false,
+    };
+
+    let mut unread = header.length1 as usize + header.length2 as usize;
+    let mut buffer = [0u8; 1024];
+    while unread > 0 {
+        match remaining() {
+            Some(budget) if stream.set_read_timeout(Some(budget)).is_ok() => {}
+            _ => return false,
+        }
+        let len = buffer.len().min(unread);
+        match stream.read(&mut buffer[..len]) {
+            Ok(0) => return false,
+            Ok(read) => unread -= read,
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(_) => return false,
+        }
+    }
+    stream.shutdown(Shutdown::Both).is_ok()
+}
+
+/// Measure the round-trip time of a minimal request to the server.
+///
+/// Sends the same empty-template parse request as `is_server_available`,
+/// waits for the complete response including both content blocks and returns
+/// the elapsed time, connection setup included. The configured timeout and
+/// proxy apply as for a render.
+///
+/// # Arguments
+///
+/// * `config` - Connection settings of the server
+///
+/// # Errors
+///
+/// Returns `NeutralIpcError::Io` if the connection fails (e.g. with
+/// `std::io::ErrorKind::ConnectionRefused` when the server is down) or times
+/// out (`TimedOut` or `WouldBlock`, depending on the platform), or an error if
+/// the response is invalid.
+///
+/// # Example
+///
+/// ```no_run
+/// use neutralipcrs::client::ping;
+/// use neutralipcrs::NeutralIpcConfig;
+///
+/// let rtt = ping(&NeutralIpcConfig::new()).unwrap();
+/// println!("round-trip: {:?}", rtt);
+/// ```
+pub fn ping(config: &NeutralIpcConfig) -> Result<Duration> {
+    let mut client = NeutralIpcClient::with_config(config.clone(), CTRL_PARSE_TEMPLATE, CONTENT_JSON, b"{}", CONTENT_TEXT, b"")?;
+    let started = Instant::now();
+    client.start()?;
+    Ok(started.elapsed())
+}
+
+/// Request information about the server.
+///
+/// Sends a request with the given control code and returns the response
+/// `content-1` as sent by the server. The protocol defines no information
+/// control code yet, so the code is the one the server implementation
+/// documents for it.
+///
+/// # Arguments
+///
+/// * `config` - Connection settings of the server
+/// * `control` - Control code of the information request of the server
+///
+/// # Errors
+///
+/// Returns `NeutralIpcError::NotSupported` if the server responds with
+/// `CTRL_STATUS_KO`, or an error if IPC communication fails.
+///
+/// # Example
+///
+/// ```no_run
+/// use neutralipcrs::client::server_info;
+/// use neutralipcrs::{NeutralIpcConfig, NeutralIpcError};
+///
+/// // Code defined by the server implementation.
+/// const SERVER_INFO: u8 = 200;
+///
+/// match server_info(&NeutralIpcConfig::new(), SERVER_INFO) {
+///     Ok(info) => println!("server: {}", info),
+///     Err(NeutralIpcError::NotSupported) => println!("server info not supported"),
+///     Err(err) => eprintln!("{}", err),
+/// }
+/// ```
+pub fn server_info(config: &NeutralIpcConfig, control: u8) -> Result<String> {
+    let mut client = NeutralIpcClient::with_config(config.clone(), control, CONTENT_JSON, b"{}", CONTENT_TEXT, b"")?;
+    let result = client.start()?;
+
+    let status = result.get("control")
+        .and_then(|v| v.as_u64())
+        .ok_or(NeutralIpcError::InvalidResponse)? as u8;
+    if status == CTRL_STATUS_KO {
+        return Err(NeutralIpcError::NotSupported);
+    }
+
+    result.get("content-1")
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+        .ok_or(NeutralIpcError::InvalidResponse)
+}
+
+/// Resolve a file-path template against the configured template root.
+///
+/// Without a template root the path is returned unchanged, as are absolute
+/// paths unless `enforce_root` is set. Otherwise the path is made absolute
+/// and canonicalized. For paths that do not exist yet, the deepest existing
+/// ancestor is canonicalized and the remaining components are appended, so a
+/// symlinked parent directory cannot point outside the root.
+///
+/// # Errors
+///
+/// Returns `NeutralIpcError::PathTraversal` if the resolved path is outside
+/// the template root, or `NeutralIpcError::Io` if the root cannot be
+/// canonicalized.
+pub(crate) fn resolve_template_path(config: &NeutralIpcConfig, template: &str) -> Result<String> {
+    let root = match config.get_template_root() {
+        Some(root) => root,
+        None => return Ok(template.to_string()),
+    };
+    let path = Path::new(template);
+    if path.is_absolute() && !config.get_enforce_root() {
+        return Ok(template.to_string());
+    }
+
+    let root = root.canonicalize()?;
+    let joined = root.join(path);
+    let resolved = canonicalize_existing(&joined);
+
+    if !resolved.starts_with(&root) {
+        return Err(NeutralIpcError::PathTraversal(template.to_string()));
+    }
+    resolved.into_os_string().into_string().map_err(|_| {
+        NeutralIpcError::InvalidConfig("template root is not valid UTF-8".to_string())
+    })
+}
+
+/// Canonicalize the deepest existing ancestor of `path` and append the remaining components.
+///
+/// The remaining components do not exist, so they hold no symlinks and are
+/// normalized lexically.
+fn canonicalize_existing(path: &Path) -> PathBuf {
+    let components: Vec<Component> = path.components().collect();
+    for existing in (1..=components.len()).rev() {
+        if let Ok(mut resolved) = components[..existing].iter().collect::<PathBuf>().canonicalize() {
+            resolved.extend(&components[existing..]);
+            return normalize_lexically(&resolved);
+        }
+    }
+    normalize_lexically(path)
+}
+
+/// Remove `.` and `..` components from a path without touching the file system.
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{read_request, write_response, MockServer, CONNECTION_LIMIT};
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_version_info() {
+        assert_eq!(crate::version(), env!("CARGO_PKG_VERSION"));
+        assert_eq!(crate::protocol_version(), PROTOCOL_V1);
+    }
+
+    /// Start a mock server that stalls for `stall` before the response header
+    /// and again before the response content.
+    fn stalling_server(stall: Duration) -> MockServer {
+        MockServer::start(move |mut stream| {
+            if read_request(&mut stream).is_some() {
+                let response = NeutralIpcRecord::encode_record(CTRL_STATUS_OK, CONTENT_JSON, b"{}", CONTENT_TEXT, b"ok");
+                std::thread::sleep(stall);
+                let _ = stream.write_all(&response[..HEADER_LEN]);
+                std::thread::sleep(stall);
+                let _ = stream.write_all(&response[HEADER_LEN..]);
+            }
+        })
+    }
+
+    fn start_with(config: NeutralIpcConfig) -> Result<String> {
+        let mut client = NeutralIpcClient::with_config(config, CTRL_PARSE_TEMPLATE, CONTENT_JSON, b"{}", CONTENT_TEXT, b"")?;
+        let result = client.start()?;
+        Ok(result["content-2"].as_str().unwrap().to_string())
+    }
+
+    /// Start a mock server implementing the compressed formats: compressed
+    /// requests are answered with their own contents, compressed the same way,
+    /// or rejected with an unsupported format diagnostic if `compressed_formats`
+    /// is false. Uncompressed requests are echoed as they are.
+    #[cfg(feature = "compression")]
+    fn compressing_server(compressed_formats: bool) -> MockServer {
+        use crate::compression::{compress, decompress_as, format_compression};
+        use crate::record::ContentFormat;
+
+        MockServer::start(move |mut stream| {
+            if let Some((header, content1, content2)) = read_request(&mut stream) {
+                let (format1, format2) = (header[2], header[7]);
+                match format_compression(ContentFormat::from(format1)) {
+                    Some(_) if !compressed_formats => {
+                        write_response(&mut stream, CTRL_STATUS_KO, br#"{"message":"unsupported format 11"}"#, b"")
+                    }
+                    Some(compression) => {
+                        let schema = decompress_as(compression, &content1).unwrap();
+                        let template = decompress_as(compression, &content2).unwrap();
+                        let response = NeutralIpcRecord::encode_record(
+                            CTRL_STATUS_OK,
+                            format1,
+                            &compress(compression, &schema).unwrap(),
+                            format2,
+                            &compress(compression, &template).unwrap(),
+                        );
+                        let _ = stream.write_all(&response);
+                    }
+                    None => write_response(&mut stream, CTRL_STATUS_OK, &content1, &content2),
+                }
+            }
+        })
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_compressed_formats_round_trip() {
+        use crate::config::CompressionType;
+
+        let server = compressing_server(true);
+        for (compression, format2) in [(CompressionType::Gzip, CONTENT_TEXT_GZ), (CompressionType::Zlib, CONTENT_TEXT_ZLIB)] {
+            let config = server.config().with_compression(compression);
+            let mut client =
+                NeutralIpcClient::with_config(config, CTRL_PARSE_TEMPLATE, CONTENT_JSON, br#"{"a":1}"#, CONTENT_TEXT, b"tpl").unwrap();
+            let result = client.start().unwrap();
+            assert_eq!(result["content-1"], r#"{"a":1}"#);
+            assert_eq!(result["content-2"], "tpl");
+            assert_eq!(result["format-2"], format2);
+
+            let (_, stream) = client.start_stream().unwrap();
+            assert_eq!(stream.collect::<Result<Vec<_>>>().unwrap().concat(), b"tpl");
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_compressed_formats_fall_back_uncompressed() {
+        use crate::config::CompressionType;
+
+        let server = compressing_server(false);
+        // A single connection slot: the rejected connection must be released before sending again.
+        let config = server
+            .config()
+            .with_compression(CompressionType::Gzip)
+            .with_max_concurrent_connections(1)
+            .with_total_timeout(5);
+        let new_client = || {
+            NeutralIpcClient::with_config(config.clone(), CTRL_PARSE_TEMPLATE, CONTENT_JSON, b"{}", CONTENT_TEXT, b"tpl").unwrap()
+        };
+
+        let mut client = new_client();
+        let result = client.start().unwrap();
+        assert_eq!(result["control"], CTRL_STATUS_OK);
+        assert_eq!(result["format-1"], CONTENT_JSON);
+        assert_eq!(result["content-2"], "tpl");
+
+        let mut client = new_client();
+        let (result, stream) = client.start_stream().unwrap();
+        assert_eq!(result["control"], CTRL_STATUS_OK);
+        assert_eq!(stream.collect::<Result<Vec<_>>>().unwrap().concat(), b"tpl");
+    }
+
+    #[test]
+    fn test_check_schema_size_policies() {
+        let config = NeutralIpcConfig::without_file();
+        assert_eq!(config.get_schema_size_policy(), SchemaSizePolicy::Fail);
+        assert!(check_schema_size(&config, 8, None).is_ok());
+        assert!(check_schema_size(&config, 8, Some(8)).is_ok());
+        match check_schema_size(&config, 9, Some(8)) {
+            Err(NeutralIpcError::SchemaTooLarge { size: 9, limit: 8 }) => {}
+            other => panic!("expected SchemaTooLarge, got {:?}", other),
+        }
+
+        let config = config.with_schema_size_policy(SchemaSizePolicy::Warn);
+        assert!(check_schema_size(&config, 9, Some(8)).is_ok());
+    }
+
+    #[test]
+    fn test_total_timeout_caps_the_whole_request() {
+        let server = stalling_server(Duration::from_millis(700));
+        let mut config = server.config();
+        config.set_timeout(1);
+
+        // Each stall fits the per-phase timeout.
+        assert_eq!(start_with(config.clone()).unwrap(), "ok");
+
+        config.set_total_timeout(Some(1));
+        let started = Instant::now();
+        match start_with(config) {
+            Err(NeutralIpcError::Io(err)) => {
+                assert_eq!(err.kind(), ErrorKind::TimedOut);
+                assert_eq!(err.to_string(), "total_timeout of 1s exhausted during read");
+            }
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+        assert!(started.elapsed() < Duration::from_millis(1300));
+    }
+
+    #[test]
+    fn test_stricter_per_phase_timeout_wins() {
+        let server = stalling_server(Duration::from_millis(1500));
+        let mut config = server.config();
+        config.set_timeout(1);
+        config.set_total_timeout(Some(10));
+
+        match start_with(config) {
+            Err(NeutralIpcError::Io(err)) => {
+                assert_eq!(err.kind(), ErrorKind::TimedOut);
+                assert_eq!(err.to_string(), "timeout of 1s exceeded during read");
+            }
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_millisecond_timeout() {
+        let server = stalling_server(Duration::from_millis(400));
+        let mut config = server.config();
+        config.set_timeout_ms(100);
+
+        let started = Instant::now();
+        match start_with(config) {
+            Err(NeutralIpcError::Io(err)) => {
+                assert_eq!(err.kind(), ErrorKind::TimedOut);
+                assert_eq!(err.to_string(), "timeout of 100ms exceeded during read");
+            }
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+        assert!(started.elapsed() < Duration::from_millis(400));
+    }
+
+    #[test]
+    fn test_ping_measures_round_trip() {
+        let server = MockServer::respond_with(CTRL_STATUS_OK, b"{}", b"");
+        let config = server.config();
+
+        let rtt = ping(&config).unwrap();
+
+        assert!(rtt > Duration::ZERO);
+        assert!(rtt < Duration::from_secs(config.get_timeout() as u64));
+    }
+
+    #[test]
+    fn test_ping_reports_connection_errors() {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let mut config = NeutralIpcConfig::default();
+        config.set_host("127.0.0.1".to_string());
+        config.set_port(port);
+
+        let err = ping(&config).unwrap_err();
+        assert!(matches!(err, NeutralIpcError::Io(_)));
+        assert!(err.is_connection_refused());
+        assert!(!err.is_timeout());
+    }
+
+    #[test]
+    fn test_server_info() {
+        let server = MockServer::start(|mut stream| {
+            if let Some((header, _, _)) = read_request(&mut stream) {
+                assert_eq!(header[1], 200);
+                write_response(&mut stream, CTRL_STATUS_OK, br#"{"version":"1.0"}"#, b"");
+            }
+        });
+
+        assert_eq!(server_info(&server.config(), 200).unwrap(), r#"{"version":"1.0"}"#);
+    }
+
+    #[test]
+    fn test_server_info_unsupported() {
+        let server = MockServer::respond_with(CTRL_STATUS_KO, b"{}", b"");
+
+        assert!(matches!(
+            server_info(&server.config(), 200),
+            Err(NeutralIpcError::NotSupported)
+        ));
+    }
+
+    #[cfg(feature = "proxy")]
+    #[test]
+    fn test_connect_through_socks5_proxy() {
+        let proxy = MockServer::start(|mut stream| {
+            // Greeting: version 5, one method, no authentication.
+            let mut greeting = [0u8; 3];
+            stream.read_exact(&mut greeting).unwrap();
+            assert_eq!(greeting, [5, 1, 0]);
+            stream.write_all(&[5, 0]).unwrap();
+
+            // Connect request to an IPv4 address: version, command, reserved, type, address, port.
+            let mut request = [0u8; 10];
+            stream.read_exact(&mut request).unwrap();
+            assert_eq!(&request[..4], &[5, 1, 0, 1]);
+            assert_eq!(&request[4..8], &[127, 0, 0, 1]);
+            assert_eq!(u16::from_be_bytes([request[8], request[9]]), 4273);
+            stream.write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0x10, 0xb1]).unwrap();
+
+            // From here on the tunnel carries the plain IPC protocol.
+            if read_request(&mut stream).is_some() {
+                write_response(&mut stream, CTRL_STATUS_OK, b"{}", b"tunneled");
+            }
+        });
+        let config = NeutralIpcConfig::default().with_proxy("127.0.0.1", proxy.config().get_port());
+
+        let mut stream = connect(&config, "127.0.0.1", 4273, None).unwrap();
+        let request = NeutralIpcRecord::encode_record(CTRL_PARSE_TEMPLATE, CONTENT_JSON, b"{}", CONTENT_TEXT, b"");
+        stream.write_all(&request).unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+
+        assert_eq!(&response[HEADER_LEN + 2..], b"tunneled");
+    }
+
+    #[cfg(feature = "proxy")]
+    #[test]
+    fn test_connect_through_rejecting_proxy() {
+        let proxy = MockServer::start(|mut stream| {
+            let mut greeting = [0u8; 3];
+            stream.read_exact(&mut greeting).unwrap();
+            // No acceptable authentication method.
+            stream.write_all(&[5, 0xff]).unwrap();
+        });
+        let config = NeutralIpcConfig::default().with_proxy("127.0.0.1", proxy.config().get_port());
+
+        assert!(matches!(connect(&config, "127.0.0.1", 4273, None), Err(NeutralIpcError::ProxyError(_))));
+    }
+
+    #[test]
+    fn test_encode_request_keeps_binary_content() {
+        let content2 = [0x00, 0xff, 0xfe, b'a'];
+        let client = NeutralIpcClient::with_config(
+            NeutralIpcConfig::default(), CTRL_PARSE_TEMPLATE, CONTENT_MSGPACK, &[0x80], CONTENT_BIN, &content2,
+        ).unwrap();
+
+        let request = client.encode_request();
+        assert_eq!(&request[HEADER_LEN..], &[0x80, 0x00, 0xff, 0xfe, b'a']);
+    }
+
+    #[test]
+    fn test_start_invalid_utf8_content_strict_and_lossy() {
+        let server = MockServer::start(|mut stream| {
+            if read_request(&mut stream).is_some() {
+                write_response(&mut stream, CTRL_STATUS_OK, b"{}", b"Hello \xff world");
+            }
+        });
+
+        let mut client = NeutralIpcClient::with_config(server.config(), CTRL_PARSE_TEMPLATE, CONTENT_JSON, b"{}", CONTENT_TEXT, b"tpl").unwrap();
+        match client.start() {
+            Err(NeutralIpcError::InvalidUtf8(err)) => assert_eq!(err.utf8_error().valid_up_to(), 6),
+            other => panic!("expected InvalidUtf8, got {:?}", other),
+        }
+
+        let mut config = server.config();
+        config.set_lossy_utf8(true);
+        let mut client = NeutralIpcClient::with_config(config, CTRL_PARSE_TEMPLATE, CONTENT_JSON, b"{}", CONTENT_TEXT, b"tpl").unwrap();
+        let result = client.start().unwrap();
+        assert_eq!(result.get("content-2").and_then(|v| v.as_str()), Some("Hello \u{FFFD} world"));
+    }
+
+    #[test]
+    fn test_start_with_small_buffers_and_partial_reads() {
+        // The response trickles in a few bytes per write.
+        let server = MockServer::start(|mut stream| {
+            if let Some((_, content1, content2)) = read_request(&mut stream) {
+                let response = NeutralIpcRecord::encode_record(CTRL_STATUS_OK, CONTENT_JSON, &content1, CONTENT_TEXT, &content2);
+                for piece in response.chunks(5) {
+                    let _ = stream.write_all(piece);
+                    let _ = stream.flush();
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+            }
+        });
+        let template = "x".repeat(1000);
+
+        let mut config = server.config();
+        config.set_read_buffer_size(3);
+        config.set_write_buffer_size(7);
+        let mut client = NeutralIpcClient::with_config(config, CTRL_PARSE_TEMPLATE, CONTENT_JSON, b"{\"a\":1}", CONTENT_TEXT, template.as_bytes()).unwrap();
+        let result = client.start().unwrap();
+
+        assert_eq!(result.get("content-1").and_then(|v| v.as_str()), Some("{\"a\":1}"));
+        assert_eq!(result.get("content-2").and_then(|v| v.as_str()), Some(template.as_str()));
+    }
+
+    #[test]
+    fn test_start_connection_closed_early() {
+        // Closed inside the content: the header announces more bytes than sent.
+        let server = MockServer::start(|mut stream| {
+            if read_request(&mut stream).is_some() {
+                let response = NeutralIpcRecord::encode_record(CTRL_STATUS_OK, CONTENT_JSON, b"{}", CONTENT_TEXT, b"truncated");
+                let _ = stream.write_all(&response[..response.len() - 4]);
+            }
+        });
+        let mut client = NeutralIpcClient::with_config(server.config(), CTRL_PARSE_TEMPLATE, CONTENT_JSON, b"{}", CONTENT_TEXT, b"tpl").unwrap();
+        assert!(matches!(client.start(), Err(NeutralIpcError::ConnectionClosed)));
+
+        // Closed inside the header.
+        let server = MockServer::start(|mut stream| {
+            if read_request(&mut stream).is_some() {
+                let _ = stream.write_all(&[0, CTRL_STATUS_OK, CONTENT_JSON]);
+            }
+        });
+        let mut client = NeutralIpcClient::with_config(server.config(), CTRL_PARSE_TEMPLATE, CONTENT_JSON, b"{}", CONTENT_TEXT, b"tpl").unwrap();
+        match client.start() {
+            Err(NeutralIpcError::Io(err)) => assert_eq!(err.kind(), ErrorKind::UnexpectedEof),
+            other => panic!("expected UnexpectedEof, got {:?}", other),
+        }
+    }
+
+    /// Reader returning `Interrupted` on every other call and at most 3 bytes otherwise.
+    struct InterruptingReader {
+        data: Vec<u8>,
+        position: usize,
+        calls: usize,
+    }
+
+    impl Read for InterruptingReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.calls += 1;
+            if self.calls % 2 == 1 {
+                return Err(io::Error::from(ErrorKind::Interrupted));
+            }
+            let end = (self.position + 3).min(self.data.len()).min(self.position + buf.len());
+            buf[..end - self.position].copy_from_slice(&self.data[self.position..end]);
+            let read = end - self.position;
+            self.position = end;
+            Ok(read)
+        }
+    }
+
+    impl TimedRead for InterruptingReader {
+        fn set_read_timeout(&self, _timeout: Option<Duration>) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_read_content_retries_interrupted_reads() {
+        let client = NeutralIpcClient::with_config(NeutralIpcConfig::default(), CTRL_PARSE_TEMPLATE, CONTENT_JSON, b"{}", CONTENT_TEXT, b"").unwrap();
+        let budget = RequestBudget::new(&client.config);
+        let data: Vec<u8> = (0..=255).collect();
+
+        let mut reader = InterruptingReader { data: data.clone(), position: 0, calls: 0 };
+        assert_eq!(client.read_content(&mut reader, data.len(), 8, &budget).unwrap(), data);
+        assert!(reader.calls > data.len() / 3 * 2);
+
+        let mut reader = InterruptingReader { data: data[..10].to_vec(), position: 0, calls: 0 };
+        assert!(matches!(client.read_content(&mut reader, 11, 8, &budget), Err(NeutralIpcError::ConnectionClosed)));
+    }
+
+    /// Create an empty scratch directory unique to the calling test.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("neutralipcrs-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn test_resolve_template_path_inside_root() {
+        let dir = scratch_dir("resolve-inside");
+        let root = dir.join("root");
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("index.ntpl"), "").unwrap();
+
+        let mut config = NeutralIpcConfig::default();
+        assert_eq!(resolve_template_path(&config, "index.ntpl").unwrap(), "index.ntpl");
+
+        config.set_template_root(Some(root.clone()));
+        let expected = root.join("index.ntpl").into_os_string().into_string().unwrap();
+        assert_eq!(resolve_template_path(&config, "index.ntpl").unwrap(), expected);
+        assert_eq!(resolve_template_path(&config, "./sub/../index.ntpl").unwrap(), expected);
+
+        let missing = root.join("sub/missing.ntpl").into_os_string().into_string().unwrap();
+        assert_eq!(resolve_template_path(&config, "sub/./missing.ntpl").unwrap(), missing);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_template_path_rejects_escapes() {
+        let dir = scratch_dir("resolve-escape");
+        let root = dir.join("root");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(dir.join("secret.ntpl"), "").unwrap();
+
+        let mut config = NeutralIpcConfig::default();
+        config.set_template_root(Some(root.clone()));
+
+        for template in ["../secret.ntpl", "missing/../../secret.ntpl", "../root-sibling/x.ntpl"] {
+            match resolve_template_path(&config, template) {
+                Err(NeutralIpcError::PathTraversal(path)) => assert_eq!(path, template),
+                other => panic!("expected PathTraversal for {}, got {:?}", template, other),
+            }
+        }
+
+        let absolute = dir.join("secret.ntpl").into_os_string().into_string().unwrap();
+        assert_eq!(resolve_template_path(&config, &absolute).unwrap(), absolute);
+        config.set_enforce_root(true);
+        assert!(matches!(resolve_template_path(&config, &absolute), Err(NeutralIpcError::PathTraversal(_))));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_template_path_follows_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let dir = scratch_dir("resolve-symlink");
+        let root = dir.join("root");
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::create_dir_all(dir.join("outside")).unwrap();
+        std::fs::write(root.join("sub/page.ntpl"), "").unwrap();
+        std::fs::write(dir.join("outside/secret.ntpl"), "").unwrap();
+        symlink(root.join("sub"), root.join("inner")).unwrap();
+        symlink(dir.join("outside"), root.join("outer")).unwrap();
+
+        let mut config = NeutralIpcConfig::default();
+        config.set_template_root(Some(root.clone()));
+
+        let page = root.join("sub/page.ntpl").into_os_string().into_string().unwrap();
+        assert_eq!(resolve_template_path(&config, "inner/page.ntpl").unwrap(), page);
+        for template in ["outer/secret.ntpl", "outer/missing.ntpl", "outer/new/missing.ntpl"] {
+            assert!(matches!(
+                resolve_template_path(&config, template),
+                Err(NeutralIpcError::PathTraversal(_))
+            ));
+        }
+        let missing = root.join("sub/missing.ntpl").into_os_string().into_string().unwrap();
+        assert_eq!(resolve_template_path(&config, "inner/missing.ntpl").unwrap(), missing);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_is_server_available_within_live_mock_server() {
+        let server = MockServer::respond_with(CTRL_STATUS_OK, b"{}", b"");
+
+        assert!(is_server_available_within(&server.config(), Duration::from_secs(2)));
+
+        // Tried on every resolved address, e.g. `::1` before `127.0.0.1`.
+        let mut config = server.config();
+        config.set_host("localhost".to_string());
+        assert!(is_server_available_within(&config, Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn test_is_server_available_within_drains_and_closes() {
+        let (closed, observed) = std::sync::mpsc::channel();
+        let server = MockServer::start(move |mut stream| {
+            if read_request(&mut stream).is_some() {
+                write_response(&mut stream, CTRL_STATUS_OK, br#"{"status_code":"200"}"#, &[b'x'; 5000]);
+                let _ = closed.send(stream.read(&mut [0u8; 1]));
+            }
+        });
+
+        assert!(is_server_available_within(&server.config(), Duration::from_secs(2)));
+        assert!(matches!(observed.recv_timeout(Duration::from_secs(2)).unwrap(), Ok(0)));
+    }
+
+    #[test]
+    fn test_is_server_available_within_truncated_response() {
+        let server = MockServer::start(|mut stream| {
+            if read_request(&mut stream).is_some() {
+                let response = NeutralIpcRecord::encode_record(CTRL_STATUS_OK, CONTENT_JSON, b"{}", CONTENT_TEXT, b"content");
+                stream.write_all(&response[..response.len() - 3]).unwrap();
+            }
+        });
+
+        assert!(!is_server_available_within(&server.config(), Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn test_is_port_listening() {
+        let server = MockServer::respond_with(CTRL_STATUS_OK, b"{}", b"");
+        assert!(is_port_listening(&server.config(), Duration::from_secs(1)));
+
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let mut config = NeutralIpcConfig::default();
+        config.set_port(port);
+        assert!(!is_port_listening(&config, NONBLOCKING_PROBE_TIMEOUT));
+    }
+
+    #[test]
+    fn test_is_server_available_within_dead_port_respects_budget() {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let mut config = NeutralIpcConfig::default();
+        config.set_port(port);
+
+        let budget = Duration::from_millis(200);
+        let start = Instant::now();
+        assert!(!is_server_available_within(&config, budget));
+        assert!(start.elapsed() < budget + Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_is_server_available_within_silent_server_respects_budget() {
+        let server = MockServer::start(|stream| {
+            std::thread::sleep(Duration::from_secs(2));
+            drop(stream);
+        });
+
+        let budget = Duration::from_millis(200);
+        let start = Instant::now();
+        assert!(!is_server_available_within(&server.config(), budget));
+        assert!(start.elapsed() < budget + Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_max_concurrent_connections() {
+        let _limit = CONNECTION_LIMIT.lock().unwrap_or_else(PoisonError::into_inner);
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let (active, peak) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let (server_active, server_peak) = (Arc::clone(&active), Arc::clone(&peak));
+        let server = MockServer::start(move |mut stream| {
+            let (active, peak) = (Arc::clone(&server_active), Arc::clone(&server_peak));
+            std::thread::spawn(move || {
+                peak.fetch_max(active.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                if read_request(&mut stream).is_some() {
+                    std::thread::sleep(Duration::from_millis(50));
+                    active.fetch_sub(1, Ordering::SeqCst);
+                    write_response(&mut stream, CTRL_STATUS_OK, b"{}", b"done");
+                }
+            });
+        });
+        let mut config = server.config();
+        config.set_max_concurrent_connections(Some(2));
+
+        let renders: Vec<_> = (0..6)
+            .map(|_| {
+                let config = config.clone();
+                std::thread::spawn(move || {
+                    let mut client = NeutralIpcClient::with_config(config, CTRL_PARSE_TEMPLATE, CONTENT_JSON, b"{}", CONTENT_TEXT, b"")?;
+                    client.start().map(|result| result["content-2"].clone())
+                })
+            })
+            .collect();
+        for render in renders {
+            assert_eq!(render.join().unwrap().unwrap(), "done");
+        }
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+
+        config.set_max_concurrent_connections(Some(1));
+        config.set_queue_connections(false);
+        let mut held = NeutralIpcClient::with_config(config.clone(), CTRL_PARSE_TEMPLATE, CONTENT_JSON, b"{}", CONTENT_TEXT, b"").unwrap();
+        let (_, stream) = held.start_stream().unwrap();
+        let mut client = NeutralIpcClient::with_config(config, CTRL_PARSE_TEMPLATE, CONTENT_JSON, b"{}", CONTENT_TEXT, b"").unwrap();
+        assert!(matches!(client.start(), Err(NeutralIpcError::TooManyConnections { limit: 1 })));
+        drop(stream);
     }
 }
@@ -0,0 +1,223 @@
+//! Page composition from a layout and named fragments.
+//!
+//! A `Page` renders a set of named fragment templates and hands their output
+//! to a layout template under `data.fragments.<name>`, so a page made of a
+//! layout plus navigation, sidebar and similar parts is rendered with a single
+//! call. Each fragment is rendered with its own schema overlay, and fragment
+//! failures are handled according to a `FragmentErrorPolicy`.
+
+use serde_json::{Map, Value};
+
+use crate::error::{NeutralIpcError, Result};
+use crate::template::NeutralIpcTemplate;
+
+/// How `Page::render` handles a fragment that fails to render.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum FragmentErrorPolicy {
+    /// Stop at the first failing fragment and return
+    /// `NeutralIpcError::FragmentRender` without rendering the layout (default)
+    #[default]
+    FailFast,
+    /// Render the layout with the given text in place of every failing
+    /// fragment; the errors are available from `Page::fragment_errors`
+    Placeholder(String),
+}
+
+/// A layout template composed with named fragment templates.
+///
+/// Fragments are rendered in registration order, each over its own
+/// connection, before the layout. The layout schema receives the output of
+/// every fragment as a string under `data.fragments.<name>`; neither the
+/// layout schema nor the fragment schemas are modified by rendering.
+///
+/// # Example
+///
+/// ```no_run
+/// use neutralipcrs::{NeutralIpcTemplate, Page};
+/// use serde_json::json;
+///
+/// let layout = NeutralIpcTemplate::from_file_value("layout.ntpl", json!({})).unwrap();
+/// let nav = NeutralIpcTemplate::from_file_value("nav.ntpl", json!({})).unwrap();
+/// let footer = NeutralIpcTemplate::from_file_value("footer.ntpl", json!({})).unwrap();
+///
+/// let mut page = Page::new(layout);
+/// page.fragment("nav", nav, json!({"data": {"active": "home"}}))
+///     .fragment("footer", footer, json!({}));
+///
+/// let html = page.render().unwrap();
+/// ```
+pub struct Page {
+    /// Layout template rendered last
+    layout: NeutralIpcTemplate,
+    /// Named fragments with their schema overlay, in registration order
+    fragments: Vec<(String, NeutralIpcTemplate, Value)>,
+    /// How failing fragments are handled
+    policy: FragmentErrorPolicy,
+    /// Fragment errors collected by the last render
+    errors: Vec<(String, NeutralIpcError)>,
+}
+
+impl Page {
+    /// Create a page with the given layout and no fragments.
+    ///
+    /// # Arguments
+    ///
+    /// * `layout` - The template receiving the fragments under `data.fragments`
+    pub fn new(layout: NeutralIpcTemplate) -> Self {
+        Self {
+            layout,
+            fragments: Vec::new(),
+            policy: FragmentErrorPolicy::default(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Register a named fragment.
+    ///
+    /// Registering a name again replaces the earlier fragment in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Key of the fragment output under `data.fragments`
+    /// * `template` - The fragment template
+    /// * `overlay` - Schema data merged into the fragment schema for each render
+    ///   (as `Value` or string, see `NeutralIpcTemplate::render_with_override`)
+    pub fn fragment(&mut self, name: &str, template: NeutralIpcTemplate, overlay: Value) -> &mut Self {
+        match self.fragments.iter_mut().find(|(existing, _, _)| existing == name) {
+            Some(fragment) => *fragment = (name.to_string(), template, overlay),
+            None => self.fragments.push((name.to_string(), template, overlay)),
+        }
+        self
+    }
+
+    /// Set how failing fragments are handled.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The policy used by the next renders
+    pub fn set_error_policy(&mut self, policy: FragmentErrorPolicy) -> &mut Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Render the fragments, then the layout.
+    ///
+    /// # Returns
+    ///
+    /// The rendered layout content.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::FragmentRender` for the first failing fragment
+    /// with `FragmentErrorPolicy::FailFast`, or an error if the layout fails
+    /// to render (see `NeutralIpcTemplate::render`).
+    pub fn render(&mut self) -> Result<String> {
+        self.errors.clear();
+
+        let mut outputs = Map::new();
+        for (name, template, overlay) in &mut self.fragments {
+            match template.render_with_override(overlay.clone()) {
+                Ok(content) => {
+                    outputs.insert(name.clone(), Value::String(content));
+                }
+                Err(err) => match &self.policy {
+                    FragmentErrorPolicy::FailFast => {
+                        return Err(NeutralIpcError::FragmentRender {
+                            fragment: name.clone(),
+                            source: Box::new(err),
+                        });
+                    }
+                    FragmentErrorPolicy::Placeholder(placeholder) => {
+                        outputs.insert(name.clone(), Value::String(placeholder.clone()));
+                        self.errors.push((name.clone(), err));
+                    }
+                },
+            }
+        }
+
+        let mut fragments = Map::new();
+        fragments.insert("fragments".to_string(), Value::Object(outputs));
+        let mut data = Map::new();
+        data.insert("data".to_string(), Value::Object(fragments));
+        self.layout.render_with_override(Value::Object(data))
+    }
+
+    /// Get the fragment errors collected by the last render.
+    ///
+    /// Only filled with `FragmentErrorPolicy::Placeholder`; each entry holds the
+    /// fragment name and its error, in registration order.
+    pub fn fragment_errors(&self) -> &[(String, NeutralIpcError)] {
+        &self.errors
+    }
+
+    /// Get the layout template, e.g. to inspect the result of the last render.
+    pub fn layout(&self) -> &NeutralIpcTemplate {
+        &self.layout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+    use crate::mock::{read_request, write_response, MockServer};
+    use serde_json::json;
+
+    /// Start a mock server that renders every template as its own schema.
+    fn schema_echo_server() -> MockServer {
+        MockServer::start(|mut stream| {
+            if let Some((_, content1, _)) = read_request(&mut stream) {
+                write_response(&mut stream, CTRL_STATUS_OK, b"{}", &content1);
+            }
+        })
+    }
+
+    fn template(server: &MockServer, source: &str, schema: Value) -> NeutralIpcTemplate {
+        NeutralIpcTemplate::from_src_value(source, schema).unwrap().with_config(server.config())
+    }
+
+    #[test]
+    fn test_render_composes_fragments_into_layout() {
+        let server = schema_echo_server();
+        let mut page = Page::new(template(&server, "layout", json!({"data": {"title": "Home"}})));
+        page.fragment("nav", template(&server, "nav", json!({"data": {"items": 3}})), json!({"data": {"active": "home"}}))
+            .fragment("footer", template(&server, "footer", json!({"data": {"year": 2024}})), json!({}));
+
+        let layout: Value = serde_json::from_str(&page.render().unwrap()).unwrap();
+
+        assert_eq!(layout["data"]["title"], "Home");
+        let nav: Value = serde_json::from_str(layout["data"]["fragments"]["nav"].as_str().unwrap()).unwrap();
+        assert_eq!(nav, json!({"data": {"items": 3, "active": "home"}}));
+        let footer: Value = serde_json::from_str(layout["data"]["fragments"]["footer"].as_str().unwrap()).unwrap();
+        assert_eq!(footer, json!({"data": {"year": 2024}}));
+        assert!(page.fragment_errors().is_empty());
+    }
+
+    #[test]
+    fn test_render_fragment_error_policies() {
+        let server = schema_echo_server();
+        let mut broken = template(&server, "broken", json!({"data": {}}));
+        broken.set_max_schema_size(Some(1));
+        let mut page = Page::new(template(&server, "layout", json!({})));
+        page.fragment("nav", template(&server, "nav", json!({})), json!({}))
+            .fragment("broken", broken, json!({}));
+
+        match page.render() {
+            Err(NeutralIpcError::FragmentRender { fragment, source }) => {
+                assert_eq!(fragment, "broken");
+                assert!(matches!(*source, NeutralIpcError::SchemaTooLarge { .. }));
+            }
+            other => panic!("expected FragmentRender, got {:?}", other),
+        }
+
+        page.set_error_policy(FragmentErrorPolicy::Placeholder("<!-- unavailable -->".to_string()));
+        let layout: Value = serde_json::from_str(&page.render().unwrap()).unwrap();
+
+        assert_eq!(layout["data"]["fragments"]["broken"], "<!-- unavailable -->");
+        assert_eq!(layout["data"]["fragments"]["nav"], "{}");
+        let errors = page.fragment_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "broken");
+        assert!(matches!(errors[0].1, NeutralIpcError::SchemaTooLarge { .. }));
+    }
+}
@@ -0,0 +1,157 @@
+//! Compression helpers for IPC content blocks.
+//!
+//! Available with the `compression` feature. JSON and text blocks are sent
+//! with the compressed format identifiers (`CONTENT_JSON_GZ`, `CONTENT_TEXT_ZLIB`,
+//! ...), which the Neutral server must support. Responses are decompressed
+//! according to their format, and `CONTENT_BIN` blocks by their leading bytes.
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+use std::io::{Read, Write};
+
+use crate::config::CompressionType;
+use crate::constants::*;
+use crate::error::Result;
+use crate::record::ContentFormat;
+
+/// Compress a content block with the given compression type.
+///
+/// # Arguments
+///
+/// * `compression` - The compression algorithm to use
+/// * `content` - The content bytes to compress
+///
+/// # Returns
+///
+/// The compressed bytes.
+pub(crate) fn compress(compression: CompressionType, content: &[u8]) -> Result<Vec<u8>> {
+    match compression {
+        CompressionType::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(content)?;
+            Ok(encoder.finish()?)
+        }
+        CompressionType::Zlib => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(content)?;
+            Ok(encoder.finish()?)
+        }
+    }
+}
+
+/// Get the format identifier of a block of `format` compressed with `compression`.
+///
+/// # Returns
+///
+/// The compressed format identifier, or `None` if blocks of `format` are
+/// sent uncompressed (only JSON and text blocks have compressed formats).
+pub(crate) fn compressed_format(format: u8, compression: CompressionType) -> Option<u8> {
+    match (format, compression) {
+        (CONTENT_JSON, CompressionType::Gzip) => Some(CONTENT_JSON_GZ),
+        (CONTENT_JSON, CompressionType::Zlib) => Some(CONTENT_JSON_ZLIB),
+        (CONTENT_TEXT, CompressionType::Gzip) => Some(CONTENT_TEXT_GZ),
+        (CONTENT_TEXT, CompressionType::Zlib) => Some(CONTENT_TEXT_ZLIB),
+        _ => None,
+    }
+}
+
+/// Get the compression of a block from its format identifier.
+///
+/// # Returns
+///
+/// The compression type, or `None` if the format is not a compressed format.
+/// `CONTENT_BIN` blocks are detected from their content instead.
+pub(crate) fn format_compression(format: ContentFormat) -> Option<CompressionType> {
+    match format {
+        ContentFormat::JsonGzip | ContentFormat::TextGzip => Some(CompressionType::Gzip),
+        ContentFormat::JsonZlib | ContentFormat::TextZlib => Some(CompressionType::Zlib),
+        _ => None,
+    }
+}
+
+/// Detect the compression type of a content block from its leading bytes.
+///
+/// # Returns
+///
+/// The detected compression type, or `None` if the content does not start
+/// with a gzip or zlib header.
+pub(crate) fn detect(content: &[u8]) -> Option<CompressionType> {
+    match content {
+        [0x1f, 0x8b, ..] => Some(CompressionType::Gzip),
+        [cmf, flg, ..] if cmf & 0x0f == 8 && (u16::from(*cmf) * 256 + u16::from(*flg)) % 31 == 0 => {
+            Some(CompressionType::Zlib)
+        }
+        _ => None,
+    }
+}
+
+/// Decompress a content block if it carries a gzip or zlib header.
+///
+/// Content without a recognizable header is returned unchanged.
+///
+/// # Arguments
+///
+/// * `content` - The possibly compressed content bytes
+///
+/// # Errors
+///
+/// Returns an error if the content has a compression header but the
+/// compressed stream is corrupt.
+pub(crate) fn decompress(content: &[u8]) -> Result<Vec<u8>> {
+    match detect(content) {
+        Some(compression) => decompress_as(compression, content),
+        None => Ok(content.to_vec()),
+    }
+}
+
+/// Decompress a content block compressed with the given compression type.
+///
+/// # Errors
+///
+/// Returns an error if the content is not a valid compressed stream.
+pub(crate) fn decompress_as(compression: CompressionType, content: &[u8]) -> Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    match compression {
+        CompressionType::Gzip => GzDecoder::new(content).read_to_end(&mut decompressed)?,
+        CompressionType::Zlib => ZlibDecoder::new(content).read_to_end(&mut decompressed)?,
+    };
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_decompress_round_trip() {
+        let content = br#"{"data":{"text":"Hello!","number":123}}"#.repeat(20);
+
+        for compression in [CompressionType::Gzip, CompressionType::Zlib] {
+            let compressed = compress(compression, &content).unwrap();
+            assert!(compressed.len() < content.len());
+            assert_eq!(detect(&compressed), Some(compression));
+            assert_eq!(decompress(&compressed).unwrap(), content);
+        }
+    }
+
+    #[test]
+    fn test_compressed_formats_round_trip() {
+        for compression in [CompressionType::Gzip, CompressionType::Zlib] {
+            for format in [CONTENT_JSON, CONTENT_TEXT] {
+                let compressed = compressed_format(format, compression).unwrap();
+                assert_eq!(format_compression(ContentFormat::from(compressed)), Some(compression));
+            }
+            assert_eq!(compressed_format(CONTENT_PATH, compression), None);
+            assert_eq!(compressed_format(CONTENT_MSGPACK, compression), None);
+        }
+        assert!(decompress_as(CompressionType::Gzip, b"{}").is_err());
+    }
+
+    #[test]
+    fn test_decompress_leaves_plain_content_unchanged() {
+        assert_eq!(detect(b"{}"), None);
+        assert_eq!(decompress(b"{}").unwrap(), b"{}");
+        assert_eq!(decompress(b"").unwrap(), b"");
+    }
+}
@@ -1,10 +1,161 @@
 //! Configuration module for Neutral IPC client.
 //! Reads configuration from /etc/neutral-ipc-cfg.json or uses default values.
 //! neutral-ipc-cfg.json is the configuration file used by the IPC server.
+//! The `NEUTRAL_CONFIG_FILE` environment variable selects another file.
+//! Setting `NEUTRAL_IPC_NO_SYSTEM_CONFIG=1`, or calling
+//! `NeutralIpcConfig::disable_system_config()`, stops `NeutralIpcConfig::new()`
+//! from reading any file, for sandboxed deployments that must not open `/etc`.
+//! `NEUTRAL_IPC_TIMEOUT` (seconds) and `NEUTRAL_IPC_TIMEOUT_MS` override the
+//! timeout of the file, the milliseconds winning when both are set.
+//!
+//! With the `yaml-config` feature, files ending in `.yml` or `.yaml` are read
+//! as YAML. The YAML document is a mapping with the same keys as the JSON
+//! file; every key is optional:
+//!
+//! ```yaml
+//! host: 127.0.0.1          # server address
+//! port: 4273               # server port
+//! timeout: 10              # socket timeout in seconds
+//! timeout_ms: 150          # socket timeout in milliseconds, overrides timeout
+//! total_timeout: 30        # cap on the whole request in seconds
+//! respect_retry_after: false # wait and retry rate-limited renders
+//! max_retry_delay_ms: 5000 # cap on the total wait for rate-limited renders
+//! max_concurrent_connections: 64 # process-wide cap on open render connections
+//! queue_connections: true  # wait for a free connection instead of failing
+//! buffer_size: 8192        # read and write buffer size in bytes
+//! read_buffer_size: 65536  # overrides buffer_size for reads
+//! write_buffer_size: 8192  # overrides buffer_size for writes
+//! checksum: false          # send v1 records with a CRC32 checksum
+//! lossy_utf8: false        # replace invalid UTF-8 in rendered content
+//! template_cache_negotiation: false # send source templates by hash first
+//! max_schema_size: 1048576 # schema size limit checked before sending
+//! schema_size_policy: fail # fail or warn when over max_schema_size
+//! template_root: /srv/tpl  # base directory for relative template paths
+//! enforce_root: false      # also confine absolute paths to template_root
+//! proxy_host: 127.0.0.1    # SOCKS5 proxy (`proxy` feature)
+//! proxy_port: 1080
+//! compression: gzip        # gzip, zlib or none (`compression` feature)
+//! schema_fetch_timeout_secs: 5 # schema URL fetch timeout (`http-schema` feature)
+//! schema_fetch_cache: false # revalidate fetched schemas by ETag (`http-schema` feature)
+//! ```
+//!
+//! With the `toml-config` feature, files ending in `.toml` are read as TOML
+//! with the same keys. `NeutralIpcConfig::save_to_file` writes a configuration
+//! back as JSON or TOML.
 
-use serde_json::Value;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::env;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::error::{NeutralIpcError, Result};
+
+/// Compression algorithm applied to the JSON and text content blocks.
+///
+/// Requires the `compression` feature and a Neutral server that supports
+/// the compressed content formats.
+#[cfg(feature = "compression")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionType {
+    /// Gzip compression
+    Gzip,
+    /// Zlib compression
+    Zlib,
+}
+
+#[cfg(feature = "compression")]
+impl CompressionType {
+    /// Parse a compression type from its configuration name ("gzip" or "zlib").
+    ///
+    /// Any other name, i.e. "none" once the settings are checked, disables compression.
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "gzip" => Some(CompressionType::Gzip),
+            "zlib" => Some(CompressionType::Zlib),
+            _ => None,
+        }
+    }
+
+    /// Get the configuration name of this compression type.
+    fn name(&self) -> &'static str {
+        match self {
+            CompressionType::Gzip => "gzip",
+            CompressionType::Zlib => "zlib",
+        }
+    }
+}
+
+/// What happens to a request whose schema is over the `max_schema_size` limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SchemaSizePolicy {
+    /// Fail with `NeutralIpcError::SchemaTooLarge` before connecting (default)
+    #[default]
+    Fail,
+    /// Send the request anyway after a warning, logged with the `logging`
+    /// feature and written to stderr otherwise
+    Warn,
+}
+
+impl SchemaSizePolicy {
+    /// Parse a policy from its configuration name ("warn" or "fail").
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "warn" => Some(SchemaSizePolicy::Warn),
+            "fail" => Some(SchemaSizePolicy::Fail),
+            _ => None,
+        }
+    }
+
+    /// Get the configuration name of this policy.
+    fn name(&self) -> &'static str {
+        match self {
+            SchemaSizePolicy::Warn => "warn",
+            SchemaSizePolicy::Fail => "fail",
+        }
+    }
+}
+
+/// Type of the value of a configuration key, see `NeutralIpcConfig::setting_keys`.
+#[derive(Debug, Clone, Copy)]
+enum SettingKind {
+    /// Unsigned integer up to the given maximum
+    Number(u64),
+    /// `true` or `false`
+    Bool,
+    /// Any string
+    Text,
+    /// One of the given names, in any case
+    Name(&'static [&'static str]),
+}
+
+impl SettingKind {
+    /// Check that a value of a settings object has this type.
+    ///
+    /// `null` is accepted for text, which some keys use to unset a value.
+    fn accepts(&self, value: &Value) -> bool {
+        match self {
+            SettingKind::Number(max) => value.as_u64().is_some_and(|number| number <= *max),
+            SettingKind::Bool => value.is_boolean(),
+            SettingKind::Text => value.is_string() || value.is_null(),
+            SettingKind::Name(names) => value
+                .as_str()
+                .is_some_and(|name| names.iter().any(|known| known.eq_ignore_ascii_case(name))),
+        }
+    }
+
+    /// Parse a value of this type from a string.
+    fn parse(&self, value: &str) -> Option<Value> {
+        let parsed = match self {
+            SettingKind::Number(_) => json!(value.trim().parse::<u64>().ok()?),
+            SettingKind::Bool => json!(value.trim().parse::<bool>().ok()?),
+            SettingKind::Text | SettingKind::Name(_) => json!(value),
+        };
+        self.accepts(&parsed).then_some(parsed)
+    }
+}
 
 /// Configuration class for Neutral IPC client.
 ///
@@ -17,12 +168,57 @@ pub struct NeutralIpcConfig {
     host: String,
     /// Default port number (4273)
     port: u16,
-    /// Default timeout in seconds (10)
-    timeout: u16,
-    /// Default buffer size in bytes (8192)
-    buffer_size: usize,
+    /// Per-phase timeout (10 seconds)
+    timeout: Duration,
+    /// Default read buffer size in bytes (8192)
+    read_buffer_size: usize,
+    /// Default write buffer size in bytes (8192)
+    write_buffer_size: usize,
     /// The IPC server configuration file
     config_file: String,
+    /// Whether records carry a CRC32 checksum (default: false)
+    checksum: bool,
+    /// Whether invalid UTF-8 in rendered content is replaced instead of failing (default: false)
+    lossy_utf8: bool,
+    /// Whether source templates are first sent as a hash of the source (default: false)
+    template_cache_negotiation: bool,
+    /// Base directory for relative template paths (default: none)
+    template_root: Option<PathBuf>,
+    /// Whether absolute template paths must also be inside the template root (default: false)
+    enforce_root: bool,
+    /// Maximum schema size in bytes accepted by `render()` (default: unlimited)
+    max_schema_size: Option<usize>,
+    /// What happens to requests over `max_schema_size` (default: fail)
+    schema_size_policy: SchemaSizePolicy,
+    /// Time budget in seconds for a whole request, across all phases (default: none)
+    total_timeout: Option<u16>,
+    /// Whether rate-limited renders are retried after the delay sent by the server (default: false)
+    respect_retry_after: bool,
+    /// Maximum total wait in milliseconds for the retries of a rate-limited render (default: 5000)
+    max_retry_delay_ms: u64,
+    /// Maximum number of render connections open at once in the process (default: unlimited)
+    max_concurrent_connections: Option<usize>,
+    /// Whether renders over the connection limit wait instead of failing (default: true)
+    queue_connections: bool,
+    /// Per-phase timeout replacing `timeout`, set by template overrides (default: none)
+    timeout_override: Option<Duration>,
+    /// Timeout of the connect phase replacing the per-phase timeout (default: none)
+    connect_timeout: Option<Duration>,
+    /// SOCKS5 proxy host (default: none)
+    #[cfg(feature = "proxy")]
+    proxy_host: Option<String>,
+    /// SOCKS5 proxy port (default: none)
+    #[cfg(feature = "proxy")]
+    proxy_port: Option<u16>,
+    /// Compression applied to the schema payload (default: none)
+    #[cfg(feature = "compression")]
+    compression: Option<CompressionType>,
+    /// Timeout in seconds of a schema fetched from a URL (default: 5)
+    #[cfg(feature = "http-schema")]
+    schema_fetch_timeout_secs: u16,
+    /// Whether schemas fetched from a URL are cached and revalidated by ETag (default: false)
+    #[cfg(feature = "http-schema")]
+    schema_fetch_cache: bool,
 }
 
 impl Default for NeutralIpcConfig {
@@ -30,24 +226,629 @@ impl Default for NeutralIpcConfig {
         Self {
             host: "127.0.0.1".to_string(),
             port: 4273,
-            timeout: 10,
-            buffer_size: 8192,
+            timeout: Duration::from_secs(10),
+            read_buffer_size: 8192,
+            write_buffer_size: 8192,
             config_file: "/etc/neutral-ipc-cfg.json".to_string(),
+            checksum: false,
+            lossy_utf8: false,
+            template_cache_negotiation: false,
+            template_root: None,
+            enforce_root: false,
+            max_schema_size: None,
+            schema_size_policy: SchemaSizePolicy::Fail,
+            total_timeout: None,
+            respect_retry_after: false,
+            max_retry_delay_ms: 5000,
+            max_concurrent_connections: None,
+            queue_connections: true,
+            timeout_override: None,
+            connect_timeout: None,
+            #[cfg(feature = "proxy")]
+            proxy_host: None,
+            #[cfg(feature = "proxy")]
+            proxy_port: None,
+            #[cfg(feature = "compression")]
+            compression: None,
+            #[cfg(feature = "http-schema")]
+            schema_fetch_timeout_secs: 5,
+            #[cfg(feature = "http-schema")]
+            schema_fetch_cache: false,
         }
     }
 }
 
+/// Environment variable overriding the configuration file read by `NeutralIpcConfig::new()`.
+const CONFIG_FILE_ENV: &str = "NEUTRAL_CONFIG_FILE";
+
+/// Environment variable that stops `NeutralIpcConfig::new()` from reading any file when set to `1`.
+const NO_SYSTEM_CONFIG_ENV: &str = "NEUTRAL_IPC_NO_SYSTEM_CONFIG";
+
+/// Environment variable overriding the timeout of `NeutralIpcConfig::new()`, in seconds.
+const TIMEOUT_ENV: &str = "NEUTRAL_IPC_TIMEOUT";
+
+/// Environment variable overriding the timeout of `NeutralIpcConfig::new()`, in milliseconds.
+const TIMEOUT_MS_ENV: &str = "NEUTRAL_IPC_TIMEOUT_MS";
+
+/// Set by `NeutralIpcConfig::disable_system_config()`.
+static SYSTEM_CONFIG_DISABLED: AtomicBool = AtomicBool::new(false);
+
 impl NeutralIpcConfig {
     /// Create a new configuration with default values and load from config file if it exists
+    ///
+    /// The file is `/etc/neutral-ipc-cfg.json` unless the `NEUTRAL_CONFIG_FILE`
+    /// environment variable names another one. No file is read at all, and
+    /// the result is the same as `without_file()`, if the system configuration
+    /// is disabled with `NEUTRAL_IPC_NO_SYSTEM_CONFIG=1` or
+    /// `disable_system_config()`. This also covers the configurations loaded
+    /// by `NeutralIpcClient::new()`, `is_server_available()` and templates
+    /// without a configuration of their own.
+    ///
+    /// The `NEUTRAL_IPC_TIMEOUT` (seconds) and `NEUTRAL_IPC_TIMEOUT_MS`
+    /// environment variables override the timeout of the file, like the
+    /// `timeout` and `timeout_ms` keys.
     pub fn new() -> Self {
-        let mut config = Self::default();
-        config.load_from_config_file();
+        let mut config = if Self::is_system_config_disabled() {
+            Self::without_file()
+        } else {
+            let mut config = Self::default();
+            if let Some(config_file) = env::var(CONFIG_FILE_ENV).ok().filter(|file| !file.is_empty()) {
+                config.config_file = config_file;
+            }
+            config.load_from_config_file();
+            config
+        };
+
+        let mut timeouts = Map::new();
+        for (key, var) in [("timeout", TIMEOUT_ENV), ("timeout_ms", TIMEOUT_MS_ENV)] {
+            if let Some(value) = env::var(var).ok().and_then(|value| value.trim().parse::<u64>().ok()) {
+                timeouts.insert(key.to_string(), json!(value));
+            }
+        }
+        config.apply_timeout(&Value::Object(timeouts));
         config
     }
 
+    /// Create a configuration with default values and no configuration file
+    ///
+    /// Nothing is read from the filesystem, neither now nor when the settings
+    /// are reloaded, until a file is set with `set_config_file`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcConfig;
+    ///
+    /// let config = NeutralIpcConfig::without_file();
+    ///
+    /// assert_eq!(config.get_config_file(), "");
+    /// assert_eq!(config.get_port(), 4273);
+    /// ```
+    pub fn without_file() -> Self {
+        Self { config_file: String::new(), ..Self::default() }
+    }
+
+    /// Stop `new()` from reading any configuration file for the rest of the process
+    ///
+    /// Same as setting `NEUTRAL_IPC_NO_SYSTEM_CONFIG=1` before the first
+    /// configuration is loaded. Files given explicitly, e.g. with
+    /// `set_config_file` or `NeutralIpcConfigBuilder::file`, are still read.
+    pub fn disable_system_config() {
+        SYSTEM_CONFIG_DISABLED.store(true, Ordering::Relaxed);
+    }
+
+    /// Check whether `new()` skips the configuration file
+    fn is_system_config_disabled() -> bool {
+        SYSTEM_CONFIG_DISABLED.load(Ordering::Relaxed)
+            || env::var(NO_SYSTEM_CONFIG_ENV).is_ok_and(|value| value == "1")
+    }
+
+    /// Create a configuration from a YAML document
+    ///
+    /// Values missing from the document keep their defaults. The configuration
+    /// has no configuration file path, so later reloads do not touch the
+    /// filesystem. See the module documentation for the supported keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::InvalidConfig` if the document is not valid
+    /// YAML, is not a mapping, or names an unknown `compression` or
+    /// `schema_size_policy`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcConfig;
+    ///
+    /// let config = NeutralIpcConfig::from_yaml_str("host: 10.0.0.2\nport: 4300\n").unwrap();
+    ///
+    /// assert_eq!(config.get_host(), "10.0.0.2");
+    /// assert_eq!(config.get_port(), 4300);
+    /// ```
+    #[cfg(feature = "yaml-config")]
+    pub fn from_yaml_str(s: &str) -> Result<Self> {
+        let settings = Self::parse_yaml(s)?;
+        Self::check_names(&settings)?;
+        let mut config = Self { config_file: String::new(), ..Self::default() };
+        config.apply_file_config(&settings);
+        Ok(config)
+    }
+
+    /// Create a configuration from a YAML file
+    ///
+    /// Same as `from_yaml_str`, but the file becomes the configuration file,
+    /// so it is read again when the settings are reloaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::Io` if the file cannot be read, or
+    /// `NeutralIpcError::InvalidConfig` if it is not a valid YAML mapping.
+    #[cfg(feature = "yaml-config")]
+    pub fn from_yaml_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let mut config = Self::from_yaml_str(&content).map_err(|err| match err {
+            NeutralIpcError::InvalidConfig(reason) => {
+                NeutralIpcError::InvalidConfig(format!("{}: {}", path.display(), reason))
+            }
+            err => err,
+        })?;
+        config.config_file = path.to_string_lossy().to_string();
+        Ok(config)
+    }
+
+    /// Parse a YAML configuration document into a JSON object
+    #[cfg(feature = "yaml-config")]
+    fn parse_yaml(input: &str) -> Result<Value> {
+        let invalid = |err: &dyn std::fmt::Display| NeutralIpcError::InvalidConfig(format!("invalid YAML: {}", err));
+
+        let mut yaml: serde_yaml::Value = serde_yaml::from_str(input).map_err(|err| invalid(&err))?;
+        yaml.apply_merge().map_err(|err| invalid(&err))?;
+        match serde_json::to_value(yaml).map_err(|err| invalid(&err))? {
+            Value::Null => Ok(Value::Object(Map::new())),
+            settings @ Value::Object(_) => Ok(settings),
+            _ => Err(NeutralIpcError::InvalidConfig("YAML configuration must be a mapping".to_string())),
+        }
+    }
+
+    /// Parse a TOML configuration document into a JSON object
+    #[cfg(feature = "toml-config")]
+    fn parse_toml(input: &str) -> Result<Value> {
+        let table: toml::Table = input
+            .parse()
+            .map_err(|err| NeutralIpcError::InvalidConfig(format!("invalid TOML: {}", err)))?;
+        serde_json::to_value(table).map_err(|err| NeutralIpcError::InvalidConfig(format!("invalid TOML: {}", err)))
+    }
+
+    /// Serialize the configuration as a TOML document
+    ///
+    /// The document holds the same keys as the configuration file, so it can
+    /// be saved and read back with the `toml-config` feature. Unset optional
+    /// values are left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::InvalidConfig` if a value cannot be written
+    /// as TOML (e.g. an integer above `i64::MAX`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcConfig;
+    ///
+    /// let config = NeutralIpcConfig::builder().port(4300).no_file().build().unwrap();
+    ///
+    /// assert!(config.to_toml_string().unwrap().contains("port = 4300"));
+    /// ```
+    #[cfg(feature = "toml-config")]
+    pub fn to_toml_string(&self) -> Result<String> {
+        toml::to_string(&self.to_file_value())
+            .map_err(|err| NeutralIpcError::InvalidConfig(format!("cannot write TOML: {}", err)))
+    }
+
+    /// Get the configuration as a map of strings
+    ///
+    /// The map holds the same keys as the configuration file, for frameworks
+    /// that keep every setting as a string. Numbers and booleans are written
+    /// in their JSON form and unset optional values are left out.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcConfig;
+    ///
+    /// let config = NeutralIpcConfig::builder().port(4300).no_file().build().unwrap();
+    /// let map = config.to_string_map();
+    ///
+    /// assert_eq!(map["port"], "4300");
+    /// assert_eq!(map["checksum"], "false");
+    /// assert!(!map.contains_key("total_timeout"));
+    /// ```
+    pub fn to_string_map(&self) -> HashMap<&'static str, String> {
+        let settings = self.to_file_value();
+        Self::setting_keys()
+            .into_iter()
+            .filter_map(|(key, _)| {
+                let value = match settings.get(key)? {
+                    Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                };
+                Some((key, value))
+            })
+            .collect()
+    }
+
+    /// Create a configuration from a map of strings
+    ///
+    /// The reverse of `to_string_map`: each value is parsed to the type of
+    /// its key and applied like a key of the configuration file, over the
+    /// default values. No configuration file is read.
+    ///
+    /// # Arguments
+    ///
+    /// * `map` - Configuration keys and their values as strings
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::InvalidConfig` if a key is unknown or its
+    /// value cannot be parsed to the type of the key, e.g. a `port` above
+    /// 65535, an unknown `compression` or an unknown `schema_size_policy`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcConfig;
+    /// use std::collections::HashMap;
+    ///
+    /// let map = HashMap::from([
+    ///     ("host".to_string(), "10.0.0.5".to_string()),
+    ///     ("port".to_string(), "4300".to_string()),
+    ///     ("checksum".to_string(), "true".to_string()),
+    /// ]);
+    /// let config = NeutralIpcConfig::from_string_map(&map).unwrap();
+    ///
+    /// assert_eq!(config.get_port(), 4300);
+    /// assert!(config.get_checksum());
+    /// ```
+    pub fn from_string_map(map: &HashMap<String, String>) -> Result<NeutralIpcConfig> {
+        let mut settings = Map::new();
+        for (key, value) in map {
+            let kind = Self::setting_kind(key)
+                .ok_or_else(|| NeutralIpcError::InvalidConfig(format!("unknown configuration key '{}'", key)))?;
+            let parsed = kind
+                .parse(value)
+                .ok_or_else(|| NeutralIpcError::InvalidConfig(format!("invalid value for '{}': {}", key, value)))?;
+            settings.insert(key.clone(), parsed);
+        }
+
+        let mut config = Self::without_file();
+        config.apply_file_config(&Value::Object(settings));
+        Ok(config)
+    }
+
+    /// Get the keys of the configuration file and the type of their values.
+    ///
+    /// The single list of keys: the string map, `save_to_file` and the
+    /// checks of `apply_file_config` and `update_settings` all use it, and
+    /// `to_file_value` only writes keys listed here.
+    fn setting_keys() -> Vec<(&'static str, SettingKind)> {
+        use SettingKind::*;
+        const U16: u64 = u16::MAX as u64;
+        const USIZE: u64 = usize::MAX as u64;
+
+        #[allow(unused_mut)]
+        let mut keys = vec![
+            ("host", Text),
+            ("port", Number(U16)),
+            ("timeout", Number(u64::MAX)),
+            ("timeout_ms", Number(u64::MAX)),
+            ("buffer_size", Number(USIZE)),
+            ("read_buffer_size", Number(USIZE)),
+            ("write_buffer_size", Number(USIZE)),
+            ("checksum", Bool),
+            ("lossy_utf8", Bool),
+            ("template_cache_negotiation", Bool),
+            ("template_root", Text),
+            ("enforce_root", Bool),
+            ("max_schema_size", Number(USIZE)),
+            ("schema_size_policy", Name(&["warn", "fail"])),
+            ("total_timeout", Number(U16)),
+            ("respect_retry_after", Bool),
+            ("max_retry_delay_ms", Number(u64::MAX)),
+            ("max_concurrent_connections", Number(USIZE)),
+            ("queue_connections", Bool),
+        ];
+        #[cfg(feature = "proxy")]
+        keys.extend([("proxy_host", Text), ("proxy_port", Number(U16))]);
+        #[cfg(feature = "compression")]
+        keys.push(("compression", Name(&["gzip", "zlib", "none"])));
+        #[cfg(feature = "http-schema")]
+        keys.extend([("schema_fetch_timeout_secs", Number(U16)), ("schema_fetch_cache", Bool)]);
+        keys
+    }
+
+    /// Get the type of the value of a configuration key, or `None` for an unknown key.
+    fn setting_kind(key: &str) -> Option<SettingKind> {
+        Self::setting_keys().into_iter().find(|(name, _)| *name == key).map(|(_, kind)| kind)
+    }
+
+    /// Check that the keys taking one of a set of names, e.g. `compression`, name a known one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::InvalidConfig` naming the first unknown name.
+    #[cfg(feature = "yaml-config")]
+    fn check_names(settings: &Value) -> Result<()> {
+        let Value::Object(map) = settings else {
+            return Ok(());
+        };
+        for (key, value) in map {
+            if let Some(kind @ SettingKind::Name(_)) = Self::setting_kind(key) {
+                if !kind.accepts(value) {
+                    return Err(NeutralIpcError::InvalidConfig(format!("invalid value for '{}': {}", key, value)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the known keys whose value does not have the type of the key.
+    ///
+    /// Out-of-range numbers, e.g. a `port` above 65535, are dropped instead of
+    /// being truncated, leaving the current value. Unknown keys are kept.
+    fn checked_settings(settings: &Value) -> Value {
+        match settings {
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .filter(|(key, value)| Self::setting_kind(key).is_none_or(|kind| kind.accepts(value)))
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect(),
+            ),
+            settings => settings.clone(),
+        }
+    }
+
+    /// Save the configuration to a file
+    ///
+    /// The extension selects the format: `.json` files are written as JSON,
+    /// `.toml` files as TOML (`toml-config` feature) and `.yml` or `.yaml`
+    /// files as YAML (`yaml-config` feature). An existing file is updated rather than
+    /// replaced: the keys of this configuration are written over it and any
+    /// other key, e.g. a setting of the IPC server itself, is kept.
+    ///
+    /// The document is first written to a temporary file in the same
+    /// directory, which is read back and compared with this configuration, so
+    /// a value that does not survive the round trip is reported instead of
+    /// silently lost. Only then is it renamed over `path`, which is left
+    /// untouched on any error.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to write
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::Io` if the file cannot be read or written, or
+    /// `NeutralIpcError::InvalidConfig` if the extension is not supported or
+    /// its format is not available, the existing file is not a configuration
+    /// document, or the saved file does not load back to the same configuration.
+    pub fn save_to_file(&self, path: &Path) -> Result<()> {
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        match extension {
+            Some("json") => {}
+            #[cfg(feature = "toml-config")]
+            Some("toml") => {}
+            #[cfg(feature = "yaml-config")]
+            Some("yml" | "yaml") => {}
+            #[cfg(not(feature = "toml-config"))]
+            Some("toml") => {
+                return Err(NeutralIpcError::InvalidConfig(
+                    "saving TOML configuration files requires the `toml-config` feature".to_string(),
+                ));
+            }
+            #[cfg(not(feature = "yaml-config"))]
+            Some("yml" | "yaml") => {
+                return Err(NeutralIpcError::InvalidConfig(
+                    "saving YAML configuration files requires the `yaml-config` feature".to_string(),
+                ));
+            }
+            _ => {
+                return Err(NeutralIpcError::InvalidConfig(format!(
+                    "{}: unsupported configuration file extension, expected .json, .toml, .yml or .yaml",
+                    path.display()
+                )));
+            }
+        }
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| NeutralIpcError::InvalidConfig(format!("{}: not a file path", path.display())))?;
+
+        let mut document = Self::read_document(path)?;
+        for (key, _) in Self::setting_keys() {
+            document.remove(key);
+        }
+        if let Value::Object(settings) = self.to_file_value() {
+            document.extend(settings);
+        }
+        let document = Value::Object(document);
+        let content = match extension {
+            #[cfg(feature = "toml-config")]
+            Some("toml") => toml::to_string(&document)
+                .map_err(|err| NeutralIpcError::InvalidConfig(format!("cannot write TOML: {}", err)))?,
+            #[cfg(feature = "yaml-config")]
+            Some("yml" | "yaml") => serde_yaml::to_string(&document)
+                .map_err(|err| NeutralIpcError::InvalidConfig(format!("cannot write YAML: {}", err)))?,
+            _ => serde_json::to_string_pretty(&document)?,
+        };
+
+        // Keeps the extension, which selects the format the file is read back with.
+        let temp = path.with_file_name(format!(".{}-{}", std::process::id(), file_name.to_string_lossy()));
+        fs::write(&temp, content)?;
+        match self.check_saved(&temp, path) {
+            Ok(()) => fs::rename(&temp, path).map_err(|err| {
+                let _ = fs::remove_file(&temp);
+                err.into()
+            }),
+            Err(err) => {
+                let _ = fs::remove_file(&temp);
+                Err(err)
+            }
+        }
+    }
+
+    /// Read the document of a configuration file to update, or an empty one if the file does not exist.
+    fn read_document(path: &Path) -> Result<Map<String, Value>> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Map::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let invalid = |reason: String| NeutralIpcError::InvalidConfig(format!("{}: {}", path.display(), reason));
+        let document = match path.extension().and_then(|ext| ext.to_str()) {
+            #[cfg(feature = "toml-config")]
+            Some("toml") => Self::parse_toml(&content),
+            #[cfg(feature = "yaml-config")]
+            Some("yml" | "yaml") => Self::parse_yaml(&content),
+            _ => serde_json::from_str(&content)
+                .map_err(|err| NeutralIpcError::InvalidConfig(format!("invalid JSON: {}", err))),
+        };
+        match document {
+            Ok(Value::Object(document)) => Ok(document),
+            Ok(_) => Err(invalid("not a configuration object".to_string())),
+            Err(NeutralIpcError::InvalidConfig(reason)) => Err(invalid(reason)),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Check that the file `saved`, to be renamed to `path`, loads back to this configuration.
+    fn check_saved(&self, saved: &Path, path: &Path) -> Result<()> {
+        let mut loaded = Self { config_file: saved.to_string_lossy().to_string(), ..Self::default() };
+        loaded.apply_file_config(&loaded.load_config());
+        let changed = match self.diff(&loaded) {
+            Value::Object(mut diff) => match diff.remove("changed") {
+                Some(Value::Object(mut changed)) => {
+                    changed.remove("config_file");
+                    changed
+                }
+                _ => Map::new(),
+            },
+            _ => Map::new(),
+        };
+        if !changed.is_empty() {
+            let keys: Vec<&str> = changed.keys().map(String::as_str).collect();
+            return Err(NeutralIpcError::InvalidConfig(format!(
+                "{}: saved configuration differs in {}",
+                path.display(),
+                keys.join(", ")
+            )));
+        }
+        Ok(())
+    }
+
+    /// Save the configuration to its configuration file
+    ///
+    /// Same as `save_to_file` with the path returned by `get_config_file()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::InvalidConfig` if the configuration has no
+    /// configuration file, and the errors of `save_to_file` otherwise.
+    pub fn save_to_default_file(&self) -> Result<()> {
+        if self.config_file.is_empty() {
+            return Err(NeutralIpcError::InvalidConfig("no configuration file is set".to_string()));
+        }
+        self.save_to_file(Path::new(&self.config_file))
+    }
+
+    /// Build the configuration file object holding the current values.
+    ///
+    /// Unset optional values are left out, as TOML has no null.
+    fn to_file_value(&self) -> Value {
+        let mut settings = Map::new();
+        settings.insert("host".to_string(), json!(self.host));
+        settings.insert("port".to_string(), json!(self.port));
+        if self.timeout.subsec_nanos() == 0 {
+            settings.insert("timeout".to_string(), json!(self.timeout.as_secs()));
+        } else {
+            settings.insert("timeout_ms".to_string(), json!(self.timeout.as_millis() as u64));
+        }
+        settings.insert("read_buffer_size".to_string(), json!(self.read_buffer_size));
+        settings.insert("write_buffer_size".to_string(), json!(self.write_buffer_size));
+        settings.insert("checksum".to_string(), json!(self.checksum));
+        settings.insert("lossy_utf8".to_string(), json!(self.lossy_utf8));
+        settings.insert("template_cache_negotiation".to_string(), json!(self.template_cache_negotiation));
+        if let Some(template_root) = &self.template_root {
+            settings.insert("template_root".to_string(), json!(template_root));
+        }
+        settings.insert("enforce_root".to_string(), json!(self.enforce_root));
+        if let Some(max_schema_size) = self.max_schema_size {
+            settings.insert("max_schema_size".to_string(), json!(max_schema_size));
+        }
+        settings.insert("schema_size_policy".to_string(), json!(self.schema_size_policy.name()));
+        if let Some(total_timeout) = self.total_timeout {
+            settings.insert("total_timeout".to_string(), json!(total_timeout));
+        }
+        settings.insert("respect_retry_after".to_string(), json!(self.respect_retry_after));
+        settings.insert("max_retry_delay_ms".to_string(), json!(self.max_retry_delay_ms));
+        if let Some(max_connections) = self.max_concurrent_connections {
+            settings.insert("max_concurrent_connections".to_string(), json!(max_connections));
+        }
+        settings.insert("queue_connections".to_string(), json!(self.queue_connections));
+        #[cfg(feature = "proxy")]
+        if let Some(proxy_host) = &self.proxy_host {
+            settings.insert("proxy_host".to_string(), json!(proxy_host));
+        }
+        #[cfg(feature = "proxy")]
+        if let Some(proxy_port) = self.proxy_port {
+            settings.insert("proxy_port".to_string(), json!(proxy_port));
+        }
+        #[cfg(feature = "compression")]
+        if let Some(compression) = self.compression {
+            settings.insert("compression".to_string(), json!(compression.name()));
+        }
+        #[cfg(feature = "http-schema")]
+        settings.insert("schema_fetch_timeout_secs".to_string(), json!(self.schema_fetch_timeout_secs));
+        #[cfg(feature = "http-schema")]
+        settings.insert("schema_fetch_cache".to_string(), json!(self.schema_fetch_cache));
+        debug_assert!(settings.keys().all(|key| Self::setting_kind(key).is_some()));
+        Value::Object(settings)
+    }
+
+    /// Create a builder for an in-memory configuration
+    ///
+    /// Unlike `new()`, the built configuration never reads a configuration file
+    /// unless one is explicitly given with `NeutralIpcConfigBuilder::file`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcConfig;
+    ///
+    /// let config = NeutralIpcConfig::builder()
+    ///     .host("127.0.0.1")
+    ///     .port(4273)
+    ///     .timeout(5)
+    ///     .no_file()
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(config.get_timeout(), 5);
+    /// ```
+    pub fn builder() -> NeutralIpcConfigBuilder {
+        NeutralIpcConfigBuilder::default()
+    }
+
     /// Load configuration from the config file and update current values
     fn load_from_config_file(&mut self) {
+        if self.config_file.is_empty() {
+            return;
+        }
         let file_config = self.load_config();
+        self.apply_file_config(&file_config);
+    }
+
+    /// Apply the values of a parsed configuration file
+    fn apply_file_config(&mut self, file_config: &Value) {
+        let file_config = &Self::checked_settings(file_config);
         if let Value::Object(_) = file_config {
             // Override with values from config file if they exist
             if let Some(host) = file_config.get("host").and_then(|v| v.as_str()) {
@@ -56,12 +857,127 @@ impl NeutralIpcConfig {
             if let Some(port) = file_config.get("port").and_then(|v| v.as_u64()) {
                 self.port = port as u16;
             }
-            if let Some(timeout) = file_config.get("timeout").and_then(|v| v.as_u64()) {
-                self.timeout = timeout as u16;
+            self.apply_timeout(file_config);
+            self.apply_buffer_sizes(file_config);
+            if let Some(checksum) = file_config.get("checksum").and_then(|v| v.as_bool()) {
+                self.checksum = checksum;
+            }
+            if let Some(lossy_utf8) = file_config.get("lossy_utf8").and_then(|v| v.as_bool()) {
+                self.lossy_utf8 = lossy_utf8;
+            }
+            if let Some(negotiation) = file_config.get("template_cache_negotiation").and_then(|v| v.as_bool()) {
+                self.template_cache_negotiation = negotiation;
+            }
+            if let Some(max_schema_size) = file_config.get("max_schema_size").and_then(|v| v.as_u64()) {
+                self.set_max_schema_size(Some(max_schema_size as usize));
+            }
+            self.apply_schema_size_policy(file_config);
+            if let Some(total_timeout) = file_config.get("total_timeout").and_then(|v| v.as_u64()) {
+                self.set_total_timeout(Some(total_timeout as u16));
+            }
+            if let Some(respect) = file_config.get("respect_retry_after").and_then(|v| v.as_bool()) {
+                self.respect_retry_after = respect;
+            }
+            if let Some(max_delay) = file_config.get("max_retry_delay_ms").and_then(|v| v.as_u64()) {
+                self.max_retry_delay_ms = max_delay;
+            }
+            if let Some(max_connections) = file_config.get("max_concurrent_connections").and_then(|v| v.as_u64()) {
+                self.set_max_concurrent_connections(Some(max_connections as usize));
             }
-            if let Some(buffer_size) = file_config.get("buffer_size").and_then(|v| v.as_u64()) {
-                self.buffer_size = buffer_size as usize;
+            if let Some(queue) = file_config.get("queue_connections").and_then(|v| v.as_bool()) {
+                self.queue_connections = queue;
             }
+            self.apply_template_root(file_config);
+            #[cfg(feature = "proxy")]
+            self.apply_proxy(file_config);
+            #[cfg(feature = "http-schema")]
+            self.apply_schema_fetch(file_config);
+            #[cfg(feature = "compression")]
+            if let Some(compression) = file_config.get("compression").and_then(|v| v.as_str()) {
+                self.compression = CompressionType::from_name(compression);
+            }
+        }
+    }
+
+    /// Apply the `template_root` and `enforce_root` keys from a settings object.
+    ///
+    /// An empty or `null` `template_root` removes the template root.
+    fn apply_template_root(&mut self, settings: &Value) {
+        match settings.get("template_root") {
+            Some(Value::String(root)) if !root.is_empty() => self.template_root = Some(PathBuf::from(root)),
+            Some(Value::String(_)) | Some(Value::Null) => self.template_root = None,
+            _ => {}
+        }
+        if let Some(enforce_root) = settings.get("enforce_root").and_then(|v| v.as_bool()) {
+            self.enforce_root = enforce_root;
+        }
+    }
+
+    /// Apply the `schema_size_policy` key from a settings object.
+    ///
+    /// Unknown policy names leave the current policy unchanged.
+    fn apply_schema_size_policy(&mut self, settings: &Value) {
+        if let Some(policy) = settings.get("schema_size_policy").and_then(|v| v.as_str()).and_then(SchemaSizePolicy::from_name) {
+            self.schema_size_policy = policy;
+        }
+    }
+
+    /// Apply the buffer size keys from a settings object.
+    ///
+    /// The legacy `buffer_size` key sets both directions, then `read_buffer_size`
+    /// and `write_buffer_size` take precedence for their own direction.
+    /// Zero values are rejected and leave the current size unchanged.
+    fn apply_buffer_sizes(&mut self, settings: &Value) {
+        let get_size = |key: &str| {
+            settings.get(key)
+                .and_then(|v| v.as_u64())
+                .filter(|size| *size > 0)
+                .map(|size| size as usize)
+        };
+
+        if let Some(buffer_size) = get_size("buffer_size") {
+            self.read_buffer_size = buffer_size;
+            self.write_buffer_size = buffer_size;
+        }
+        if let Some(read_buffer_size) = get_size("read_buffer_size") {
+            self.read_buffer_size = read_buffer_size;
+        }
+        if let Some(write_buffer_size) = get_size("write_buffer_size") {
+            self.write_buffer_size = write_buffer_size;
+        }
+    }
+
+    /// Apply the `timeout` (seconds) and `timeout_ms` keys from a settings object.
+    ///
+    /// `timeout_ms` wins when both are set. Zero values are ignored.
+    fn apply_timeout(&mut self, settings: &Value) {
+        if let Some(timeout) = settings.get("timeout").and_then(|v| v.as_u64()).filter(|timeout| *timeout > 0) {
+            self.timeout = Duration::from_secs(timeout);
+        }
+        if let Some(timeout_ms) = settings.get("timeout_ms").and_then(|v| v.as_u64()).filter(|timeout| *timeout > 0) {
+            self.timeout = Duration::from_millis(timeout_ms);
+        }
+    }
+
+    /// Apply the `schema_fetch_timeout_secs` and `schema_fetch_cache` keys from a settings object.
+    #[cfg(feature = "http-schema")]
+    fn apply_schema_fetch(&mut self, settings: &Value) {
+        if let Some(timeout) = settings.get("schema_fetch_timeout_secs").and_then(|v| v.as_u64()) {
+            self.schema_fetch_timeout_secs = timeout as u16;
+        }
+        if let Some(cache) = settings.get("schema_fetch_cache").and_then(|v| v.as_bool()) {
+            self.schema_fetch_cache = cache;
+        }
+    }
+
+    /// Apply the `proxy_host` and `proxy_port` keys from a settings object.
+    #[cfg(feature = "proxy")]
+    fn apply_proxy(&mut self, settings: &Value) {
+        if let Some(proxy_host) = settings.get("proxy_host").and_then(|v| v.as_str()) {
+            self.proxy_host = Some(proxy_host.to_string());
+        }
+        if let Some(proxy_port) = settings.get("proxy_port").and_then(|v| v.as_u64()) {
+            self.proxy_port = Some(proxy_port as u16);
         }
     }
 
@@ -69,13 +985,24 @@ impl NeutralIpcConfig {
     ///
     /// This method attempts to read and parse the configuration file specified
     /// in `self.config_file`. If the file doesn't exist or cannot be parsed,
-    /// it returns `Value::Null`.
+    /// it returns `Value::Null`. With the `yaml-config` feature, `.yml` and
+    /// `.yaml` files are parsed as YAML, and with the `toml-config` feature,
+    /// `.toml` files are parsed as TOML.
     fn load_config(&self) -> Value {
-        if !Path::new(&self.config_file).exists() {
+        let path = Path::new(&self.config_file);
+        if !path.exists() {
             return Value::Null;
         }
 
-        match fs::read_to_string(&self.config_file) {
+        match fs::read_to_string(path) {
+            #[cfg(feature = "yaml-config")]
+            Ok(content) if matches!(path.extension().and_then(|ext| ext.to_str()), Some("yml" | "yaml")) => {
+                Self::parse_yaml(&content).unwrap_or(Value::Null)
+            }
+            #[cfg(feature = "toml-config")]
+            Ok(content) if path.extension().and_then(|ext| ext.to_str()) == Some("toml") => {
+                Self::parse_toml(&content).unwrap_or(Value::Null)
+            }
             Ok(content) => {
                 match serde_json::from_str(&content) {
                     Ok(config) => config,
@@ -107,31 +1034,224 @@ impl NeutralIpcConfig {
 
     /// Get the configured timeout value
     ///
+    /// Legacy getter kept for compatibility; sub-second timeouts are rounded
+    /// up to whole seconds, see `get_timeout_duration`.
+    ///
     /// # Returns
     ///
     /// The timeout in seconds (default: 10)
     pub fn get_timeout(&self) -> u16 {
-        self.timeout
+        let secs = self.timeout.as_secs() + u64::from(self.timeout.subsec_nanos() > 0);
+        secs.min(u16::MAX as u64) as u16
+    }
+
+    /// Get the per-phase timeout
+    ///
+    /// The timeout set with `NeutralIpcTemplate::set_timeout` replaces the
+    /// configured one in the configuration of a render.
+    ///
+    /// # Returns
+    ///
+    /// The timeout of each phase of a request (default: 10 seconds)
+    pub fn get_timeout_duration(&self) -> Duration {
+        self.timeout_override.unwrap_or(self.timeout)
+    }
+
+    /// Get the timeout of the connect phase.
+    pub(crate) fn get_connect_timeout(&self) -> Duration {
+        self.connect_timeout.unwrap_or_else(|| self.get_timeout_duration())
     }
 
     /// Get the configured buffer size
     ///
+    /// Legacy getter kept for compatibility; equivalent to `get_read_buffer_size`.
+    ///
     /// # Returns
     ///
-    /// The buffer size in bytes (default: 8192)
+    /// The read buffer size in bytes (default: 8192)
     pub fn get_buffer_size(&self) -> usize {
-        self.buffer_size
+        self.read_buffer_size
     }
 
-    /// Get the configuration file path
+    /// Get the configured read buffer size
     ///
     /// # Returns
     ///
-    /// The path to the configuration file (default: "/etc/neutral-ipc-cfg.json")
-    pub fn get_config_file(&self) -> String {
-        self.config_file.clone()
+    /// The size in bytes of each chunk read from the server (default: 8192)
+    pub fn get_read_buffer_size(&self) -> usize {
+        self.read_buffer_size
     }
-    /// Set the host address
+
+    /// Get the configured write buffer size
+    ///
+    /// # Returns
+    ///
+    /// The size in bytes of each chunk written to the server (default: 8192)
+    pub fn get_write_buffer_size(&self) -> usize {
+        self.write_buffer_size
+    }
+
+    /// Get the configuration file path
+    ///
+    /// # Returns
+    ///
+    /// The path to the configuration file (default: "/etc/neutral-ipc-cfg.json")
+    pub fn get_config_file(&self) -> String {
+        self.config_file.clone()
+    }
+
+    /// Get whether records carry a CRC32 checksum
+    ///
+    /// # Returns
+    ///
+    /// `true` if the v1 protocol with checksums is used (default: false)
+    pub fn get_checksum(&self) -> bool {
+        self.checksum
+    }
+
+    /// Get whether invalid UTF-8 in rendered content is replaced
+    ///
+    /// # Returns
+    ///
+    /// `true` if invalid UTF-8 in content-2 is replaced with U+FFFD (default: false)
+    pub fn get_lossy_utf8(&self) -> bool {
+        self.lossy_utf8
+    }
+
+    /// Get whether source templates are first sent as a hash of the source
+    ///
+    /// # Returns
+    ///
+    /// `true` if template cache negotiation is enabled (default: false)
+    pub fn get_template_cache_negotiation(&self) -> bool {
+        self.template_cache_negotiation
+    }
+
+    /// Get the base directory for relative template paths
+    ///
+    /// # Returns
+    ///
+    /// The template root, or `None` if template paths are sent unchanged (default)
+    pub fn get_template_root(&self) -> Option<&Path> {
+        self.template_root.as_deref()
+    }
+
+    /// Get whether absolute template paths must be inside the template root
+    ///
+    /// # Returns
+    ///
+    /// `true` if absolute paths are checked against the template root (default: false)
+    pub fn get_enforce_root(&self) -> bool {
+        self.enforce_root
+    }
+
+    /// Get the time budget for a whole request
+    ///
+    /// # Returns
+    ///
+    /// The budget in seconds, or `None` if only the per-phase timeout applies (default)
+    pub fn get_total_timeout(&self) -> Option<u16> {
+        self.total_timeout
+    }
+
+    /// Get whether rate-limited renders are retried
+    ///
+    /// # Returns
+    ///
+    /// `true` if the retry delay sent by the server is respected (default: false)
+    pub fn get_respect_retry_after(&self) -> bool {
+        self.respect_retry_after
+    }
+
+    /// Get the maximum total wait for the retries of a rate-limited render
+    ///
+    /// # Returns
+    ///
+    /// The wait in milliseconds (default: 5000)
+    pub fn get_max_retry_delay_ms(&self) -> u64 {
+        self.max_retry_delay_ms
+    }
+
+    /// Get the maximum number of render connections open at once
+    ///
+    /// # Returns
+    ///
+    /// The limit, or `None` if connections are not limited (default)
+    pub fn get_max_concurrent_connections(&self) -> Option<usize> {
+        self.max_concurrent_connections
+    }
+
+    /// Get whether renders over the connection limit wait for a free connection
+    ///
+    /// # Returns
+    ///
+    /// `true` if they wait, `false` if they fail (default: true)
+    pub fn get_queue_connections(&self) -> bool {
+        self.queue_connections
+    }
+
+    /// Get the maximum schema size accepted by `render()`
+    ///
+    /// # Returns
+    ///
+    /// The limit in bytes, or `None` if schemas of any size are sent (default)
+    pub fn get_max_schema_size(&self) -> Option<usize> {
+        self.max_schema_size
+    }
+
+    /// Get what happens to requests over the maximum schema size
+    ///
+    /// # Returns
+    ///
+    /// The policy (default: `SchemaSizePolicy::Fail`)
+    pub fn get_schema_size_policy(&self) -> SchemaSizePolicy {
+        self.schema_size_policy
+    }
+
+    /// Get the timeout of schemas fetched from a URL
+    ///
+    /// # Returns
+    ///
+    /// The timeout in seconds (default: 5)
+    #[cfg(feature = "http-schema")]
+    pub fn get_schema_fetch_timeout_secs(&self) -> u16 {
+        self.schema_fetch_timeout_secs
+    }
+
+    /// Check whether schemas fetched from a URL are cached
+    ///
+    /// # Returns
+    ///
+    /// `true` if fetched schemas are cached and revalidated by ETag (default: false)
+    #[cfg(feature = "http-schema")]
+    pub fn get_schema_fetch_cache(&self) -> bool {
+        self.schema_fetch_cache
+    }
+
+    /// Get the configured SOCKS5 proxy
+    ///
+    /// # Returns
+    ///
+    /// The proxy host and port, or `None` if connections are direct (default)
+    #[cfg(feature = "proxy")]
+    pub fn get_proxy(&self) -> Option<(String, u16)> {
+        match (&self.proxy_host, self.proxy_port) {
+            (Some(host), Some(port)) => Some((host.clone(), port)),
+            _ => None,
+        }
+    }
+
+    /// Get the configured schema compression
+    ///
+    /// # Returns
+    ///
+    /// The compression type, or `None` if the schema is sent uncompressed (default)
+    #[cfg(feature = "compression")]
+    pub fn get_compression(&self) -> Option<CompressionType> {
+        self.compression
+    }
+
+    /// Set the host address
     ///
     /// # Arguments
     ///
@@ -153,18 +1273,430 @@ impl NeutralIpcConfig {
     ///
     /// # Arguments
     ///
-    /// * `timeout` - The timeout in seconds to set
+    /// * `timeout` - The timeout in seconds to set; zero is ignored
     pub fn set_timeout(&mut self, timeout: u16) {
-        self.timeout = timeout;
+        if timeout > 0 {
+            self.timeout = Duration::from_secs(timeout as u64);
+        }
     }
 
-    /// Set the buffer size
+    /// Set the timeout value in milliseconds
+    ///
+    /// For budgets below a second, e.g. within a datacenter.
     ///
     /// # Arguments
     ///
-    /// * `buffer_size` - The buffer size in bytes to set
+    /// * `timeout_ms` - The timeout in milliseconds to set; zero is ignored
+    pub fn set_timeout_ms(&mut self, timeout_ms: u64) {
+        if timeout_ms > 0 {
+            self.timeout = Duration::from_millis(timeout_ms);
+        }
+    }
+
+    /// Set the buffer size for both directions
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer_size` - The buffer size in bytes to set; zero is rejected and ignored
     pub fn set_buffer_size(&mut self, buffer_size: usize) {
-        self.buffer_size = buffer_size;
+        self.set_read_buffer_size(buffer_size);
+        self.set_write_buffer_size(buffer_size);
+    }
+
+    /// Set the read buffer size
+    ///
+    /// # Arguments
+    ///
+    /// * `read_buffer_size` - The read chunk size in bytes to set; zero is rejected and ignored
+    pub fn set_read_buffer_size(&mut self, read_buffer_size: usize) {
+        if read_buffer_size > 0 {
+            self.read_buffer_size = read_buffer_size;
+        }
+    }
+
+    /// Set the write buffer size
+    ///
+    /// # Arguments
+    ///
+    /// * `write_buffer_size` - The write chunk size in bytes to set; zero is rejected and ignored
+    pub fn set_write_buffer_size(&mut self, write_buffer_size: usize) {
+        if write_buffer_size > 0 {
+            self.write_buffer_size = write_buffer_size;
+        }
+    }
+
+    /// Enable or disable CRC32 checksums on records
+    ///
+    /// # Arguments
+    ///
+    /// * `checksum` - `true` to use the v1 protocol with checksums
+    ///
+    /// # Note
+    ///
+    /// Checksums require the server to also support the v1 protocol.
+    pub fn set_checksum(&mut self, checksum: bool) {
+        self.checksum = checksum;
+    }
+
+    /// Enable or disable lossy UTF-8 decoding of rendered content
+    ///
+    /// When enabled, invalid UTF-8 sequences in the rendered content (content-2)
+    /// are replaced with U+FFFD so a single bad byte does not fail the whole
+    /// render. The result metadata (content-1, JSON) is always decoded strictly.
+    ///
+    /// # Arguments
+    ///
+    /// * `lossy_utf8` - `true` to replace invalid UTF-8 in rendered content
+    pub fn set_lossy_utf8(&mut self, lossy_utf8: bool) {
+        self.lossy_utf8 = lossy_utf8;
+    }
+
+    /// Set whether source templates are first sent as a hash of the source
+    ///
+    /// When enabled, blocking renders of source templates send the SHA-256 of
+    /// the source with `CTRL_PARSE_TEMPLATE_HASH` instead of the source. If
+    /// the server answers `CTRL_STATUS_KO`, because it does not have the
+    /// template cached or does not support the control code, the template
+    /// is sent again in full with `CTRL_PARSE_TEMPLATE`. Requires server
+    /// support to save anything; templates with request headers are always
+    /// sent in full.
+    ///
+    /// # Arguments
+    ///
+    /// * `negotiation` - `true` to send source templates by hash first
+    pub fn set_template_cache_negotiation(&mut self, negotiation: bool) {
+        self.template_cache_negotiation = negotiation;
+    }
+
+    /// Set the base directory for relative template paths
+    ///
+    /// When set, relative file-path templates are resolved against the root
+    /// into canonical absolute paths before they are sent to the server, and
+    /// paths escaping the root are rejected with `NeutralIpcError::PathTraversal`.
+    /// The template files must be reachable by the client at the same paths
+    /// the server uses.
+    ///
+    /// # Arguments
+    ///
+    /// * `template_root` - The base directory, or `None` to send paths unchanged
+    pub fn set_template_root(&mut self, template_root: Option<PathBuf>) {
+        self.template_root = template_root;
+    }
+
+    /// Set whether absolute template paths must be inside the template root
+    ///
+    /// Without this, absolute paths bypass the template root. Has no effect
+    /// if no template root is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `enforce_root` - `true` to reject absolute paths outside the template root
+    pub fn set_enforce_root(&mut self, enforce_root: bool) {
+        self.enforce_root = enforce_root;
+    }
+
+    /// Set the time budget for a whole request
+    ///
+    /// The `timeout` applies to each phase of a request (connect, every write
+    /// and every read), so a slow server can take several times longer than
+    /// it. The total budget caps connect, write and read together: each socket
+    /// timeout is shrunk to the time left, and the stricter of both wins.
+    ///
+    /// # Arguments
+    ///
+    /// * `total_timeout` - The budget in seconds; `None` or zero means no budget
+    pub fn set_total_timeout(&mut self, total_timeout: Option<u16>) {
+        self.total_timeout = total_timeout.filter(|timeout| *timeout > 0);
+    }
+
+    /// Enable or disable the retry of rate-limited renders
+    ///
+    /// A render is rate-limited when the server answers with a `retry_after_ms`
+    /// field in `content-1`, either with `CTRL_STATUS_KO` or with the status
+    /// code `429`. When enabled, blocking renders wait that long and send the
+    /// request again, until the server accepts it or the waits would exceed
+    /// `max_retry_delay_ms`; the render then fails with
+    /// `NeutralIpcError::RateLimited`.
+    ///
+    /// # Arguments
+    ///
+    /// * `respect` - `true` to retry rate-limited renders
+    pub fn set_respect_retry_after(&mut self, respect: bool) {
+        self.respect_retry_after = respect;
+    }
+
+    /// Set the maximum total wait for the retries of a rate-limited render
+    ///
+    /// Also caps each single wait.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_delay` - The wait in milliseconds
+    pub fn set_max_retry_delay_ms(&mut self, max_delay: u64) {
+        self.max_retry_delay_ms = max_delay;
+    }
+
+    /// Set the maximum number of render connections open at once
+    ///
+    /// The connections are counted across the whole process, so the limit
+    /// protects the file descriptors of the process under bursts of renders.
+    /// A blocking render over the limit waits for a connection to close, up to
+    /// the `total_timeout` budget, or fails with
+    /// `NeutralIpcError::TooManyConnections` if `queue_connections` is disabled.
+    /// Each render applies the limit of its own configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_connections` - The limit; `None` or zero means unlimited
+    pub fn set_max_concurrent_connections(&mut self, max_connections: Option<usize>) {
+        self.max_concurrent_connections = max_connections.filter(|limit| *limit > 0);
+    }
+
+    /// Set whether renders over the connection limit wait for a free connection
+    ///
+    /// # Arguments
+    ///
+    /// * `queue` - `true` to wait, `false` to fail with `NeutralIpcError::TooManyConnections`
+    pub fn set_queue_connections(&mut self, queue: bool) {
+        self.queue_connections = queue;
+    }
+
+    /// Replace the per-phase timeout, see `NeutralIpcTemplate::set_timeout`.
+    pub(crate) fn set_timeout_override(&mut self, timeout: Duration) {
+        self.timeout_override = Some(timeout);
+    }
+
+    /// Set the timeout of the connect phase, see `NeutralIpcTemplate::set_connect_timeout`.
+    pub(crate) fn set_connect_timeout(&mut self, timeout: Duration) {
+        self.connect_timeout = Some(timeout);
+    }
+
+    /// Set the maximum schema size accepted by `render()`
+    ///
+    /// Renders whose serialized schema is larger than the limit fail with
+    /// `NeutralIpcError::SchemaTooLarge` before connecting to the server, or
+    /// only warn with `SchemaSizePolicy::Warn` (see `set_schema_size_policy`).
+    ///
+    /// # Arguments
+    ///
+    /// * `max_schema_size` - The limit in bytes; `None` or zero means unlimited
+    pub fn set_max_schema_size(&mut self, max_schema_size: Option<usize>) {
+        self.max_schema_size = max_schema_size.filter(|limit| *limit > 0);
+    }
+
+    /// Set what happens to requests over the maximum schema size
+    ///
+    /// The limit is `max_schema_size`, or the one set on the template with
+    /// `NeutralIpcTemplate::set_max_schema_size`.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - `SchemaSizePolicy::Fail` to reject (default), `SchemaSizePolicy::Warn` to warn and send
+    pub fn set_schema_size_policy(&mut self, policy: SchemaSizePolicy) {
+        self.schema_size_policy = policy;
+    }
+
+    /// Set the timeout of schemas fetched from a URL
+    ///
+    /// Bounds the whole fetch of `NeutralIpcTemplate::merge_schema_from_url`,
+    /// connection and response body included.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The timeout in seconds; zero means no timeout
+    #[cfg(feature = "http-schema")]
+    pub fn set_schema_fetch_timeout_secs(&mut self, timeout: u16) {
+        self.schema_fetch_timeout_secs = timeout;
+    }
+
+    /// Enable or disable the cache of schemas fetched from a URL
+    ///
+    /// When enabled, responses carrying an `ETag` are kept in a process-wide
+    /// cache; later fetches of the same URL send `If-None-Match` and reuse the
+    /// cached schema when the server answers `304 Not Modified`.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache` - `true` to cache fetched schemas
+    #[cfg(feature = "http-schema")]
+    pub fn set_schema_fetch_cache(&mut self, cache: bool) {
+        self.schema_fetch_cache = cache;
+    }
+
+    /// Route connections through a SOCKS5 proxy
+    ///
+    /// The proxy tunnel is transparent to the IPC protocol; the proxy connects
+    /// to the configured host and port on behalf of the client.
+    ///
+    /// # Arguments
+    ///
+    /// * `proxy_host` - The proxy host address
+    /// * `proxy_port` - The proxy port number
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcConfig;
+    ///
+    /// let config = NeutralIpcConfig::new().with_proxy("proxy.internal", 1080);
+    /// ```
+    #[cfg(feature = "proxy")]
+    pub fn with_proxy(mut self, proxy_host: &str, proxy_port: u16) -> Self {
+        self.proxy_host = Some(proxy_host.to_string());
+        self.proxy_port = Some(proxy_port);
+        self
+    }
+
+    /// Set the host address, see `set_host`
+    pub fn with_host(mut self, host: &str) -> Self {
+        self.set_host(host.to_string());
+        self
+    }
+
+    /// Set the port number, see `set_port`
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.set_port(port);
+        self
+    }
+
+    /// Set the timeout in seconds, see `set_timeout`
+    pub fn with_timeout(mut self, timeout: u16) -> Self {
+        self.set_timeout(timeout);
+        self
+    }
+
+    /// Set the timeout in milliseconds, see `set_timeout_ms`
+    pub fn with_timeout_ms(mut self, timeout_ms: u64) -> Self {
+        self.set_timeout_ms(timeout_ms);
+        self
+    }
+
+    /// Set the buffer size in bytes for both directions, see `set_buffer_size`
+    pub fn with_buffer_size(mut self, buffer_size: usize) -> Self {
+        self.set_buffer_size(buffer_size);
+        self
+    }
+
+    /// Set the read buffer size in bytes, see `set_read_buffer_size`
+    pub fn with_read_buffer_size(mut self, read_buffer_size: usize) -> Self {
+        self.set_read_buffer_size(read_buffer_size);
+        self
+    }
+
+    /// Set the write buffer size in bytes, see `set_write_buffer_size`
+    pub fn with_write_buffer_size(mut self, write_buffer_size: usize) -> Self {
+        self.set_write_buffer_size(write_buffer_size);
+        self
+    }
+
+    /// Enable or disable record checksums, see `set_checksum`
+    pub fn with_checksum(mut self, checksum: bool) -> Self {
+        self.set_checksum(checksum);
+        self
+    }
+
+    /// Enable or disable lossy UTF-8 decoding of rendered content, see `set_lossy_utf8`
+    pub fn with_lossy_utf8(mut self, lossy_utf8: bool) -> Self {
+        self.set_lossy_utf8(lossy_utf8);
+        self
+    }
+
+    /// Enable or disable template cache negotiation, see `set_template_cache_negotiation`
+    pub fn with_template_cache_negotiation(mut self, negotiation: bool) -> Self {
+        self.set_template_cache_negotiation(negotiation);
+        self
+    }
+
+    /// Set the base directory for relative template paths, see `set_template_root`
+    pub fn with_template_root(mut self, template_root: impl Into<PathBuf>) -> Self {
+        self.set_template_root(Some(template_root.into()));
+        self
+    }
+
+    /// Confine absolute template paths to the template root, see `set_enforce_root`
+    pub fn with_enforce_root(mut self, enforce_root: bool) -> Self {
+        self.set_enforce_root(enforce_root);
+        self
+    }
+
+    /// Set the time budget in seconds for a whole request, see `set_total_timeout`
+    pub fn with_total_timeout(mut self, total_timeout: u16) -> Self {
+        self.set_total_timeout(Some(total_timeout));
+        self
+    }
+
+    /// Wait and retry rate-limited renders, see `set_respect_retry_after`
+    pub fn with_respect_retry_after(mut self, respect: bool) -> Self {
+        self.set_respect_retry_after(respect);
+        self
+    }
+
+    /// Set the maximum total wait for rate-limited renders, see `set_max_retry_delay_ms`
+    pub fn with_max_retry_delay_ms(mut self, max_delay: u64) -> Self {
+        self.set_max_retry_delay_ms(max_delay);
+        self
+    }
+
+    /// Set the process-wide cap on open render connections, see `set_max_concurrent_connections`
+    pub fn with_max_concurrent_connections(mut self, max_connections: usize) -> Self {
+        self.set_max_concurrent_connections(Some(max_connections));
+        self
+    }
+
+    /// Wait for a free connection instead of failing, see `set_queue_connections`
+    pub fn with_queue_connections(mut self, queue: bool) -> Self {
+        self.set_queue_connections(queue);
+        self
+    }
+
+    /// Set the maximum schema size in bytes, see `set_max_schema_size`
+    pub fn with_max_schema_size(mut self, max_schema_size: usize) -> Self {
+        self.set_max_schema_size(Some(max_schema_size));
+        self
+    }
+
+    /// Set what happens to requests over the maximum schema size, see `set_schema_size_policy`
+    pub fn with_schema_size_policy(mut self, policy: SchemaSizePolicy) -> Self {
+        self.set_schema_size_policy(policy);
+        self
+    }
+
+    /// Set the timeout in seconds of schema fetches, see `set_schema_fetch_timeout_secs`
+    #[cfg(feature = "http-schema")]
+    pub fn with_schema_fetch_timeout_secs(mut self, timeout: u16) -> Self {
+        self.set_schema_fetch_timeout_secs(timeout);
+        self
+    }
+
+    /// Enable or disable the cache of fetched schemas, see `set_schema_fetch_cache`
+    #[cfg(feature = "http-schema")]
+    pub fn with_schema_fetch_cache(mut self, cache: bool) -> Self {
+        self.set_schema_fetch_cache(cache);
+        self
+    }
+
+    /// Set the schema compression, see `set_compression`
+    #[cfg(feature = "compression")]
+    pub fn with_compression(mut self, compression: CompressionType) -> Self {
+        self.set_compression(Some(compression));
+        self
+    }
+
+    /// Set the schema compression
+    ///
+    /// # Arguments
+    ///
+    /// * `compression` - The compression type to use, or `None` to disable compression
+    ///
+    /// # Note
+    ///
+    /// JSON schemas and text templates are sent with compressed formats
+    /// (`CONTENT_JSON_GZ`, `CONTENT_TEXT_GZ`, or their zlib counterparts),
+    /// which the server must support. If it answers with an unsupported
+    /// format diagnostic, the request is sent again uncompressed.
+    #[cfg(feature = "compression")]
+    pub fn set_compression(&mut self, compression: Option<CompressionType>) {
+        self.compression = compression;
     }
 
     /// Set the configuration file path
@@ -182,6 +1714,149 @@ impl NeutralIpcConfig {
         self.load_from_config_file();
     }
 
+    /// Compare this configuration with another one
+    ///
+    /// Companion to `NeutralIpcTemplate::schema_diff`. Since both configurations
+    /// always have the same fields, only the `changed` member is reported.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The configuration compared against this one
+    ///
+    /// # Returns
+    ///
+    /// A JSON object `{"changed": {...}}` where each differing field maps to
+    /// `{"from": .., "to": ..}`
+    pub fn diff(&self, other: &NeutralIpcConfig) -> Value {
+        #[allow(unused_mut)]
+        let mut fields = vec![
+            ("host", json!(self.host), json!(other.host)),
+            ("port", json!(self.port), json!(other.port)),
+            ("timeout_ms", json!(self.timeout.as_millis() as u64), json!(other.timeout.as_millis() as u64)),
+            ("read_buffer_size", json!(self.read_buffer_size), json!(other.read_buffer_size)),
+            ("write_buffer_size", json!(self.write_buffer_size), json!(other.write_buffer_size)),
+            ("config_file", json!(self.config_file), json!(other.config_file)),
+            ("checksum", json!(self.checksum), json!(other.checksum)),
+            ("lossy_utf8", json!(self.lossy_utf8), json!(other.lossy_utf8)),
+            (
+                "template_cache_negotiation",
+                json!(self.template_cache_negotiation),
+                json!(other.template_cache_negotiation),
+            ),
+            ("template_root", json!(self.template_root), json!(other.template_root)),
+            ("enforce_root", json!(self.enforce_root), json!(other.enforce_root)),
+            ("max_schema_size", json!(self.max_schema_size), json!(other.max_schema_size)),
+            (
+                "schema_size_policy",
+                json!(self.schema_size_policy.name()),
+                json!(other.schema_size_policy.name()),
+            ),
+            ("total_timeout", json!(self.total_timeout), json!(other.total_timeout)),
+            ("respect_retry_after", json!(self.respect_retry_after), json!(other.respect_retry_after)),
+            ("max_retry_delay_ms", json!(self.max_retry_delay_ms), json!(other.max_retry_delay_ms)),
+            (
+                "max_concurrent_connections",
+                json!(self.max_concurrent_connections),
+                json!(other.max_concurrent_connections),
+            ),
+            ("queue_connections", json!(self.queue_connections), json!(other.queue_connections)),
+        ];
+        #[cfg(feature = "proxy")]
+        fields.push(("proxy_host", json!(self.proxy_host), json!(other.proxy_host)));
+        #[cfg(feature = "proxy")]
+        fields.push(("proxy_port", json!(self.proxy_port), json!(other.proxy_port)));
+        #[cfg(feature = "compression")]
+        fields.push((
+            "compression",
+            json!(self.compression.map(|c| c.name())),
+            json!(other.compression.map(|c| c.name())),
+        ));
+        #[cfg(feature = "http-schema")]
+        fields.push((
+            "schema_fetch_timeout_secs",
+            json!(self.schema_fetch_timeout_secs),
+            json!(other.schema_fetch_timeout_secs),
+        ));
+        #[cfg(feature = "http-schema")]
+        fields.push(("schema_fetch_cache", json!(self.schema_fetch_cache), json!(other.schema_fetch_cache)));
+
+        let mut changed = Map::new();
+        for (name, from, to) in fields {
+            if from != to {
+                changed.insert(name.to_string(), json!({"from": from, "to": to}));
+            }
+        }
+
+        json!({"changed": changed})
+    }
+
+    /// Merge another configuration over this one
+    ///
+    /// Every field of `other` that differs from `NeutralIpcConfig::default()`
+    /// overrides the same field of this configuration, e.g. to apply a
+    /// per-request override to a base configuration. A field of `other` that
+    /// was set back to its default value does not override anything. The
+    /// configuration file of this configuration is kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The configuration whose non-default values take precedence
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcConfig;
+    ///
+    /// let base = NeutralIpcConfig::without_file().with_host("10.0.0.2").with_timeout(30);
+    /// let merged = base.merge(&NeutralIpcConfig::without_file().with_port(4300));
+    ///
+    /// assert_eq!(merged.get_host(), "10.0.0.2");
+    /// assert_eq!(merged.get_port(), 4300);
+    /// assert_eq!(merged.get_timeout(), 30);
+    /// ```
+    pub fn merge(&self, other: &NeutralIpcConfig) -> NeutralIpcConfig {
+        let default = Self::default();
+        let mut merged = self.clone();
+        macro_rules! merge_fields {
+            ($($field:ident),* $(,)?) => {
+                $(
+                    if other.$field != default.$field {
+                        merged.$field = other.$field.clone();
+                    }
+                )*
+            };
+        }
+
+        merge_fields!(
+            host,
+            port,
+            timeout,
+            read_buffer_size,
+            write_buffer_size,
+            checksum,
+            lossy_utf8,
+            template_cache_negotiation,
+            template_root,
+            enforce_root,
+            max_schema_size,
+            schema_size_policy,
+            total_timeout,
+            respect_retry_after,
+            max_retry_delay_ms,
+            max_concurrent_connections,
+            queue_connections,
+            timeout_override,
+            connect_timeout,
+        );
+        #[cfg(feature = "proxy")]
+        merge_fields!(proxy_host, proxy_port);
+        #[cfg(feature = "compression")]
+        merge_fields!(compression);
+        #[cfg(feature = "http-schema")]
+        merge_fields!(schema_fetch_timeout_secs, schema_fetch_cache);
+        merged
+    }
+
     /// Update multiple configuration settings at once
     ///
     /// This method applies the provided settings and then automatically reloads from the config file
@@ -207,6 +1882,7 @@ impl NeutralIpcConfig {
     /// config.update_settings(settings);
     /// ```
     pub fn update_settings(&mut self, settings: Value) {
+        let settings = Self::checked_settings(&settings);
         // Check if config_file is being updated
         let should_reload = if let Value::Object(settings_map) = &settings {
             settings_map.get("config_file").and_then(|v| v.as_str()).is_some()
@@ -222,15 +1898,48 @@ impl NeutralIpcConfig {
             if let Some(port) = settings_map.get("port").and_then(|v| v.as_u64()) {
                 self.port = port as u16;
             }
-            if let Some(timeout) = settings_map.get("timeout").and_then(|v| v.as_u64()) {
-                self.timeout = timeout as u16;
+            self.apply_timeout(&Value::Object(settings_map.clone()));
+            self.apply_buffer_sizes(&Value::Object(settings_map.clone()));
+            if let Some(checksum) = settings_map.get("checksum").and_then(|v| v.as_bool()) {
+                self.checksum = checksum;
+            }
+            if let Some(lossy_utf8) = settings_map.get("lossy_utf8").and_then(|v| v.as_bool()) {
+                self.lossy_utf8 = lossy_utf8;
             }
-            if let Some(buffer_size) = settings_map.get("buffer_size").and_then(|v| v.as_u64()) {
-                self.buffer_size = buffer_size as usize;
+            if let Some(negotiation) = settings_map.get("template_cache_negotiation").and_then(|v| v.as_bool()) {
+                self.template_cache_negotiation = negotiation;
             }
+            if let Some(max_schema_size) = settings_map.get("max_schema_size").and_then(|v| v.as_u64()) {
+                self.set_max_schema_size(Some(max_schema_size as usize));
+            }
+            self.apply_schema_size_policy(&Value::Object(settings_map.clone()));
+            if let Some(total_timeout) = settings_map.get("total_timeout").and_then(|v| v.as_u64()) {
+                self.set_total_timeout(Some(total_timeout as u16));
+            }
+            if let Some(respect) = settings_map.get("respect_retry_after").and_then(|v| v.as_bool()) {
+                self.respect_retry_after = respect;
+            }
+            if let Some(max_delay) = settings_map.get("max_retry_delay_ms").and_then(|v| v.as_u64()) {
+                self.max_retry_delay_ms = max_delay;
+            }
+            if let Some(max_connections) = settings_map.get("max_concurrent_connections").and_then(|v| v.as_u64()) {
+                self.set_max_concurrent_connections(Some(max_connections as usize));
+            }
+            if let Some(queue) = settings_map.get("queue_connections").and_then(|v| v.as_bool()) {
+                self.queue_connections = queue;
+            }
+            self.apply_template_root(&Value::Object(settings_map.clone()));
+            #[cfg(feature = "proxy")]
+            self.apply_proxy(&Value::Object(settings_map.clone()));
+            #[cfg(feature = "http-schema")]
+            self.apply_schema_fetch(&Value::Object(settings_map.clone()));
             if let Some(config_file) = settings_map.get("config_file").and_then(|v| v.as_str()) {
                 self.config_file = config_file.to_string();
             }
+            #[cfg(feature = "compression")]
+            if let Some(compression) = settings_map.get("compression").and_then(|v| v.as_str()) {
+                self.compression = CompressionType::from_name(compression);
+            }
         }
 
         // Reload from config file if config_file was updated
@@ -239,3 +1948,514 @@ impl NeutralIpcConfig {
         }
     }
 }
+
+/// Maximum timeout in seconds accepted by `NeutralIpcConfigBuilder::build`.
+const MAX_BUILDER_TIMEOUT: u16 = 3600;
+
+/// Builder for `NeutralIpcConfig`.
+///
+/// Created with `NeutralIpcConfig::builder()`. Values not set on the builder
+/// keep their defaults. Explicitly set values take precedence over values read
+/// from a configuration file given with `file()`.
+#[derive(Debug, Clone, Default)]
+pub struct NeutralIpcConfigBuilder {
+    /// Host address override
+    host: Option<String>,
+    /// Port number override
+    port: Option<u16>,
+    /// Timeout override in seconds
+    timeout: Option<u16>,
+    /// Buffer size override in bytes (both directions)
+    buffer_size: Option<usize>,
+    /// Configuration file to read, if any
+    file: Option<String>,
+}
+
+impl NeutralIpcConfigBuilder {
+    /// Set the host address
+    pub fn host(mut self, host: &str) -> Self {
+        self.host = Some(host.to_string());
+        self
+    }
+
+    /// Set the port number
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Set the timeout in seconds
+    pub fn timeout(mut self, timeout: u16) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the buffer size in bytes for both directions
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = Some(buffer_size);
+        self
+    }
+
+    /// Read values from the given configuration file when building
+    pub fn file(mut self, path: &str) -> Self {
+        self.file = Some(path.to_string());
+        self
+    }
+
+    /// Never read a configuration file, even if `file()` was called before
+    ///
+    /// The built configuration also has no configuration file path, so later
+    /// reloads do not touch the filesystem either.
+    pub fn no_file(mut self) -> Self {
+        self.file = None;
+        self
+    }
+
+    /// Build the configuration
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::InvalidConfig` if the port is zero, the timeout
+    /// is zero or greater than one hour, or the buffer size is zero.
+    pub fn build(self) -> Result<NeutralIpcConfig> {
+        let mut config = NeutralIpcConfig::default();
+        match self.file {
+            Some(file) => {
+                config.config_file = file;
+                config.load_from_config_file();
+            }
+            None => config.config_file = String::new(),
+        }
+
+        if let Some(host) = self.host {
+            config.host = host;
+        }
+        if let Some(port) = self.port {
+            config.port = port;
+        }
+        // Not `set_timeout`, which ignores zero: the builder rejects it below.
+        if let Some(timeout) = self.timeout {
+            config.timeout = Duration::from_secs(timeout as u64);
+        }
+        if let Some(buffer_size) = self.buffer_size {
+            if buffer_size == 0 {
+                return Err(NeutralIpcError::InvalidConfig("buffer size must be greater than zero".to_string()));
+            }
+            config.read_buffer_size = buffer_size;
+            config.write_buffer_size = buffer_size;
+        }
+
+        if config.port == 0 {
+            return Err(NeutralIpcError::InvalidConfig("port must be greater than zero".to_string()));
+        }
+        if config.timeout.is_zero() || config.timeout > Duration::from_secs(MAX_BUILDER_TIMEOUT as u64) {
+            return Err(NeutralIpcError::InvalidConfig(format!(
+                "timeout must be nonzero and at most {} seconds, got {:?}",
+                MAX_BUILDER_TIMEOUT, config.timeout
+            )));
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_buffer_size_sets_both_directions() {
+        let mut config = NeutralIpcConfig::default();
+        config.update_settings(json!({"buffer_size": 1024}));
+
+        assert_eq!(config.get_buffer_size(), 1024);
+        assert_eq!(config.get_read_buffer_size(), 1024);
+        assert_eq!(config.get_write_buffer_size(), 1024);
+    }
+
+    #[test]
+    fn test_directional_buffer_sizes_take_precedence() {
+        let mut config = NeutralIpcConfig::default();
+        config.update_settings(json!({
+            "read_buffer_size": 65536,
+            "buffer_size": 1024,
+            "write_buffer_size": 512
+        }));
+
+        assert_eq!(config.get_read_buffer_size(), 65536);
+        assert_eq!(config.get_write_buffer_size(), 512);
+    }
+
+    #[test]
+    fn test_buffer_sizes_from_config_file() {
+        let path = std::env::temp_dir().join(format!("neutral-ipc-cfg-buffers-{}.json", std::process::id()));
+        fs::write(&path, r#"{"buffer_size": 2048, "read_buffer_size": 4096}"#).unwrap();
+
+        let mut config = NeutralIpcConfig::default();
+        config.set_config_file(path.to_string_lossy().to_string());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.get_read_buffer_size(), 4096);
+        assert_eq!(config.get_write_buffer_size(), 2048);
+    }
+
+    #[test]
+    fn test_zero_buffer_sizes_are_rejected() {
+        let mut config = NeutralIpcConfig::default();
+        config.update_settings(json!({"buffer_size": 0, "read_buffer_size": 0}));
+        config.set_write_buffer_size(0);
+
+        assert_eq!(config.get_read_buffer_size(), 8192);
+        assert_eq!(config.get_write_buffer_size(), 8192);
+    }
+
+    #[test]
+    fn test_builder_no_file_ignores_config_files() {
+        let path = std::env::temp_dir().join(format!("neutral-ipc-cfg-builder-{}.json", std::process::id()));
+        fs::write(&path, r#"{"host": "10.0.0.1", "port": 9999}"#).unwrap();
+        let path = path.to_string_lossy().to_string();
+
+        let config = NeutralIpcConfig::builder().file(&path).no_file().build().unwrap();
+        let from_file = NeutralIpcConfig::builder().file(&path).port(8080).build().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.get_host(), "127.0.0.1");
+        assert_eq!(config.get_port(), 4273);
+        assert_eq!(config.get_config_file(), "");
+        assert_eq!(from_file.get_host(), "10.0.0.1");
+        assert_eq!(from_file.get_port(), 8080);
+    }
+
+    #[cfg(feature = "yaml-config")]
+    #[test]
+    fn test_from_yaml_str() {
+        let config = NeutralIpcConfig::from_yaml_str(
+            "defaults: &defaults\n  timeout: 30\nhost: 10.0.0.2\nport: 4300\nbuffer_size: 1024\n<<: *defaults\n"
+        ).unwrap();
+
+        assert_eq!(config.get_host(), "10.0.0.2");
+        assert_eq!(config.get_port(), 4300);
+        assert_eq!(config.get_timeout(), 30);
+        assert_eq!(config.get_read_buffer_size(), 1024);
+        assert_eq!(config.get_config_file(), "");
+        assert_eq!(NeutralIpcConfig::from_yaml_str("").unwrap().get_port(), 4273);
+
+        assert!(matches!(NeutralIpcConfig::from_yaml_str("host: [unclosed"), Err(NeutralIpcError::InvalidConfig(_))));
+        assert!(matches!(NeutralIpcConfig::from_yaml_str("- host"), Err(NeutralIpcError::InvalidConfig(_))));
+    }
+
+    #[cfg(feature = "yaml-config")]
+    #[test]
+    fn test_yaml_config_file_by_extension() {
+        let path = std::env::temp_dir().join(format!("neutral-ipc-cfg-{}.yaml", std::process::id()));
+        fs::write(&path, "port: 4301\nchecksum: true\n").unwrap();
+
+        let from_file = NeutralIpcConfig::from_yaml_file(&path).unwrap();
+        let mut loaded = NeutralIpcConfig::default();
+        loaded.set_config_file(path.to_string_lossy().to_string());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(from_file.get_port(), 4301);
+        assert_eq!(from_file.get_config_file(), path.to_string_lossy());
+        assert_eq!(loaded.get_port(), 4301);
+        assert!(loaded.get_checksum());
+        assert!(matches!(NeutralIpcConfig::from_yaml_file(&path), Err(NeutralIpcError::Io(_))));
+    }
+
+    #[test]
+    fn test_save_to_file_round_trips_json() {
+        let path = std::env::temp_dir().join(format!("neutral-ipc-cfg-save-{}.json", std::process::id()));
+        let mut config = NeutralIpcConfig::builder().host("10.0.0.3").port(4302).no_file().build().unwrap();
+        config.set_checksum(true);
+        config.set_total_timeout(Some(20));
+        config.set_max_schema_size(Some(2048));
+        config.set_schema_size_policy(SchemaSizePolicy::Warn);
+
+        config.save_to_file(&path).unwrap();
+        let mut loaded = NeutralIpcConfig::default();
+        loaded.set_config_file(path.to_string_lossy().to_string());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.get_host(), "10.0.0.3");
+        assert_eq!(loaded.get_port(), 4302);
+        assert!(loaded.get_checksum());
+        assert_eq!(loaded.get_total_timeout(), Some(20));
+        assert_eq!(loaded.get_max_schema_size(), Some(2048));
+        assert_eq!(loaded.get_schema_size_policy(), SchemaSizePolicy::Warn);
+        assert!(matches!(config.save_to_default_file(), Err(NeutralIpcError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_save_to_file_keeps_unknown_keys() {
+        let path = std::env::temp_dir().join(format!("neutral-ipc-cfg-update-{}.json", std::process::id()));
+        fs::write(&path, r#"{"port": 4000, "total_timeout": 9, "server_workers": 8, "templates": {"dir": "/srv"}}"#).unwrap();
+        let config = NeutralIpcConfig::builder().port(4305).no_file().build().unwrap();
+
+        config.save_to_file(&path).unwrap();
+        let saved: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let temp = path.with_file_name(format!(".{}-{}", std::process::id(), path.file_name().unwrap().to_string_lossy()));
+        assert!(!temp.exists());
+
+        fs::write(&path, "[1, 2]").unwrap();
+        assert!(matches!(config.save_to_file(&path), Err(NeutralIpcError::InvalidConfig(_))));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[1, 2]");
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(saved["port"], 4305);
+        assert_eq!(saved["server_workers"], 8);
+        assert_eq!(saved["templates"], json!({"dir": "/srv"}));
+        assert!(saved.get("total_timeout").is_none());
+    }
+
+    #[test]
+    fn test_save_to_file_rejects_unsupported_extensions() {
+        let config = NeutralIpcConfig::without_file();
+        for name in ["neutral-ipc-cfg.ini", "neutral-ipc-cfg"] {
+            let path = std::env::temp_dir().join(name);
+            assert!(matches!(config.save_to_file(&path), Err(NeutralIpcError::InvalidConfig(_))));
+            assert!(!path.exists());
+        }
+
+        #[cfg(not(feature = "yaml-config"))]
+        {
+            let path = std::env::temp_dir().join(format!("neutral-ipc-cfg-save-{}.yml", std::process::id()));
+            assert!(matches!(config.save_to_file(&path), Err(NeutralIpcError::InvalidConfig(_))));
+            assert!(!path.exists());
+        }
+    }
+
+    #[cfg(feature = "yaml-config")]
+    #[test]
+    fn test_save_to_file_round_trips_yaml() {
+        let path = std::env::temp_dir().join(format!("neutral-ipc-cfg-save-{}.yaml", std::process::id()));
+        let mut config = NeutralIpcConfig::builder().host("10.0.0.5").port(4306).no_file().build().unwrap();
+        config.set_max_schema_size(Some(1024));
+
+        config.save_to_file(&path).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        let loaded = NeutralIpcConfig::from_yaml_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(content.contains("port: 4306"));
+        assert_eq!(loaded.get_host(), "10.0.0.5");
+        assert_eq!(loaded.get_port(), 4306);
+        assert_eq!(loaded.get_max_schema_size(), Some(1024));
+    }
+
+    #[test]
+    fn test_string_map_round_trip() {
+        let mut config = NeutralIpcConfig::builder().host("10.0.0.4").port(4304).no_file().build().unwrap();
+        config.set_timeout_ms(1500);
+        config.set_template_root(Some(PathBuf::from("/srv/tpl")));
+        config.set_schema_size_policy(SchemaSizePolicy::Warn);
+
+        let map = config.to_string_map();
+        assert_eq!(map["timeout_ms"], "1500");
+        assert_eq!(map["template_root"], "/srv/tpl");
+        assert_eq!(map["schema_size_policy"], "warn");
+        assert!(!map.contains_key("max_schema_size"));
+
+        let map: HashMap<String, String> = map.into_iter().map(|(key, value)| (key.to_string(), value)).collect();
+        let parsed = NeutralIpcConfig::from_string_map(&map).unwrap();
+        assert_eq!(config.diff(&parsed)["changed"], json!({}));
+
+        let invalid = |key: &str, value: &str| {
+            let map = HashMap::from([(key.to_string(), value.to_string())]);
+            matches!(NeutralIpcConfig::from_string_map(&map), Err(NeutralIpcError::InvalidConfig(_)))
+        };
+        assert!(invalid("port", "http"));
+        assert!(invalid("port", "70000"));
+        assert!(invalid("total_timeout", "65536"));
+        assert!(invalid("schema_size_policy", "ignore"));
+        assert!(invalid("checksum", "yes"));
+        assert!(invalid("hostname", "localhost"));
+    }
+
+    #[test]
+    fn test_out_of_range_settings_are_not_truncated() {
+        let mut config = NeutralIpcConfig::without_file();
+        config.apply_file_config(&json!({"port": 70000, "total_timeout": 65536, "checksum": "yes", "host": "10.0.0.6"}));
+        assert_eq!(config.get_port(), 4273);
+        assert_eq!(config.get_total_timeout(), None);
+        assert!(!config.get_checksum());
+        assert_eq!(config.get_host(), "10.0.0.6");
+
+        config.update_settings(json!({"port": 65536, "timeout": 5}));
+        assert_eq!(config.get_port(), 4273);
+        assert_eq!(config.get_timeout(), 5);
+    }
+
+    #[cfg(feature = "toml-config")]
+    #[test]
+    fn test_save_to_file_round_trips_toml() {
+        let path = std::env::temp_dir().join(format!("neutral-ipc-cfg-save-{}.toml", std::process::id()));
+        let mut config = NeutralIpcConfig::builder().port(4303).buffer_size(1024).no_file().build().unwrap();
+        config.set_config_file(path.to_string_lossy().to_string());
+        config.set_max_schema_size(Some(4096));
+
+        let toml = config.to_toml_string().unwrap();
+        assert!(toml.contains("port = 4303"));
+        assert!(!toml.contains("total_timeout"));
+
+        config.save_to_default_file().unwrap();
+        let mut loaded = NeutralIpcConfig::default();
+        loaded.set_config_file(path.to_string_lossy().to_string());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.get_port(), 4303);
+        assert_eq!(loaded.get_read_buffer_size(), 1024);
+        assert_eq!(loaded.get_max_schema_size(), Some(4096));
+    }
+
+    /// Serializes the tests that change how `new()` finds its configuration file.
+    static NEW_CONFIG_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    #[test]
+    fn test_config_file_from_env() {
+        let _lock = NEW_CONFIG_LOCK.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let path = std::env::temp_dir().join(format!("neutral-ipc-cfg-env-{}.json", std::process::id()));
+        // Only a large schema limit, so concurrent tests calling `new()` are unaffected.
+        fs::write(&path, r#"{"max_schema_size": 1073741824}"#).unwrap();
+
+        env::set_var(CONFIG_FILE_ENV, &path);
+        let config = NeutralIpcConfig::new();
+        env::remove_var(CONFIG_FILE_ENV);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.get_config_file(), path.to_string_lossy());
+        assert_eq!(config.get_max_schema_size(), Some(1073741824));
+    }
+
+    #[test]
+    fn test_system_config_disabled_reads_no_file() {
+        let _lock = NEW_CONFIG_LOCK.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let path = std::env::temp_dir().join(format!("neutral-ipc-cfg-disabled-{}.json", std::process::id()));
+        fs::write(&path, r#"{"max_schema_size": 1073741824}"#).unwrap();
+        env::set_var(CONFIG_FILE_ENV, &path);
+
+        env::set_var(NO_SYSTEM_CONFIG_ENV, "1");
+        let from_env = NeutralIpcConfig::new();
+        env::remove_var(NO_SYSTEM_CONFIG_ENV);
+
+        NeutralIpcConfig::disable_system_config();
+        let from_switch = NeutralIpcConfig::new();
+        SYSTEM_CONFIG_DISABLED.store(false, Ordering::Relaxed);
+
+        env::remove_var(CONFIG_FILE_ENV);
+        fs::remove_file(&path).unwrap();
+
+        for config in [from_env, from_switch, NeutralIpcConfig::without_file()] {
+            assert_eq!(config.get_config_file(), "");
+            assert_eq!(config.get_max_schema_size(), None);
+        }
+    }
+
+    #[test]
+    fn test_timeout_ms_takes_precedence() {
+        let mut config = NeutralIpcConfig::without_file();
+        config.update_settings(json!({"timeout": 5, "timeout_ms": 150}));
+        assert_eq!(config.get_timeout_duration(), Duration::from_millis(150));
+        assert_eq!(config.get_timeout(), 1);
+
+        config.update_settings(json!({"timeout": 5}));
+        assert_eq!(config.get_timeout_duration(), Duration::from_secs(5));
+
+        config.apply_file_config(&json!({"timeout_ms": 2500, "timeout": 1}));
+        assert_eq!(config.get_timeout_duration(), Duration::from_millis(2500));
+        assert_eq!(config.get_timeout(), 3);
+        assert_eq!(config.to_file_value()["timeout_ms"], 2500);
+        assert!(config.to_file_value().get("timeout").is_none());
+
+        let _lock = NEW_CONFIG_LOCK.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        env::set_var(TIMEOUT_ENV, "20");
+        let from_env = NeutralIpcConfig::new();
+        env::set_var(TIMEOUT_MS_ENV, "1500");
+        let from_env_ms = NeutralIpcConfig::new();
+        env::remove_var(TIMEOUT_ENV);
+        env::remove_var(TIMEOUT_MS_ENV);
+
+        assert_eq!(from_env.get_timeout_duration(), Duration::from_secs(20));
+        assert_eq!(from_env_ms.get_timeout_duration(), Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn test_zero_timeout_is_ignored() {
+        let mut config = NeutralIpcConfig::without_file().with_timeout_ms(150);
+        config.set_timeout_ms(0);
+        assert_eq!(config.get_timeout_duration(), Duration::from_millis(150));
+        config.set_timeout(0);
+        assert_eq!(config.get_timeout_duration(), Duration::from_millis(150));
+
+        config.update_settings(json!({"timeout": 0, "timeout_ms": 0}));
+        assert_eq!(config.get_timeout_duration(), Duration::from_millis(150));
+
+        let _lock = NEW_CONFIG_LOCK.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        env::set_var(TIMEOUT_MS_ENV, "0");
+        let from_env = NeutralIpcConfig::new();
+        env::remove_var(TIMEOUT_MS_ENV);
+        assert!(!from_env.get_timeout_duration().is_zero());
+    }
+
+    #[test]
+    fn test_merge_overrides_non_default_fields() {
+        let base = NeutralIpcConfig::without_file()
+            .with_host("10.0.0.2")
+            .with_timeout(30)
+            .with_checksum(true)
+            .with_max_schema_size(4096);
+        let other = NeutralIpcConfig::without_file()
+            .with_port(4300)
+            .with_timeout(10)
+            .with_template_root("/srv/tpl")
+            .with_queue_connections(false);
+
+        let merged = base.merge(&other);
+        assert_eq!(merged.get_host(), "10.0.0.2");
+        assert_eq!(merged.get_port(), 4300);
+        // A default value in `other` does not override the base.
+        assert_eq!(merged.get_timeout(), 30);
+        assert!(merged.get_checksum());
+        assert_eq!(merged.get_max_schema_size(), Some(4096));
+        assert_eq!(merged.get_template_root(), Some(Path::new("/srv/tpl")));
+        assert!(!merged.get_queue_connections());
+        assert_eq!(merged.get_config_file(), "");
+
+        assert_eq!(base.merge(&NeutralIpcConfig::default()).diff(&base), json!({"changed": {}}));
+    }
+
+    #[test]
+    fn test_builder_validates_values() {
+        let config = NeutralIpcConfig::builder().host("localhost").port(1234).timeout(30).buffer_size(1024).build().unwrap();
+        assert_eq!(config.get_host(), "localhost");
+        assert_eq!(config.get_port(), 1234);
+        assert_eq!(config.get_timeout(), 30);
+        assert_eq!(config.get_read_buffer_size(), 1024);
+        assert_eq!(config.get_write_buffer_size(), 1024);
+
+        assert!(matches!(NeutralIpcConfig::builder().port(0).build(), Err(NeutralIpcError::InvalidConfig(_))));
+        assert!(matches!(NeutralIpcConfig::builder().timeout(0).build(), Err(NeutralIpcError::InvalidConfig(_))));
+        assert!(matches!(NeutralIpcConfig::builder().timeout(7200).build(), Err(NeutralIpcError::InvalidConfig(_))));
+        assert!(matches!(NeutralIpcConfig::builder().buffer_size(0).build(), Err(NeutralIpcError::InvalidConfig(_))));
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_unknown_compression_name_is_rejected() {
+        let map = HashMap::from([("compression".to_string(), "brotli".to_string())]);
+        assert!(matches!(NeutralIpcConfig::from_string_map(&map), Err(NeutralIpcError::InvalidConfig(_))));
+        let map = HashMap::from([("compression".to_string(), "ZLIB".to_string())]);
+        assert_eq!(NeutralIpcConfig::from_string_map(&map).unwrap().get_compression(), Some(CompressionType::Zlib));
+
+        let mut config = NeutralIpcConfig::without_file().with_compression(CompressionType::Gzip);
+        config.update_settings(json!({"compression": "brotli"}));
+        assert_eq!(config.get_compression(), Some(CompressionType::Gzip));
+        config.update_settings(json!({"compression": "none"}));
+        assert_eq!(config.get_compression(), None);
+
+        #[cfg(feature = "yaml-config")]
+        assert!(matches!(
+            NeutralIpcConfig::from_yaml_str("compression: brotli\n"),
+            Err(NeutralIpcError::InvalidConfig(_))
+        ));
+    }
+}
@@ -14,6 +14,20 @@ pub const RESERVED: u8 = 0;
 /// This constant defines the total size of the header structure.
 pub const HEADER_LEN: usize = 12;
 
+/// Length of the CRC32 checksum appended to the header in protocol v1.
+pub const CHECKSUM_LEN: usize = 4;
+
+/// Length of the protocol v1 header in bytes.
+///
+/// The v1 header is the v0 header followed by a big endian CRC32 checksum of
+/// both content blocks. It is only used when checksums are enabled.
+pub const HEADER_LEN_V1: usize = HEADER_LEN + CHECKSUM_LEN;
+
+/// Protocol version marker for v1 records.
+///
+/// Sent in the reserved byte of the header when the record carries a checksum.
+pub const PROTOCOL_V1: u8 = 1;
+
 /// Control code for template parsing operations.
 ///
 /// This control code is used when the client wants to parse and process
@@ -37,6 +51,18 @@ pub const CTRL_STATUS_KO: u8 = 1;
 /// This constant indicates that the payload contains JSON-formatted data.
 pub const CONTENT_JSON: u8 = 10;
 
+/// Content type identifier for gzip-compressed JSON data.
+///
+/// Sent instead of `CONTENT_JSON` when gzip compression is configured
+/// (`compression` feature); the server must support compressed formats.
+pub const CONTENT_JSON_GZ: u8 = 11;
+
+/// Content type identifier for zlib-compressed JSON data.
+///
+/// Sent instead of `CONTENT_JSON` when zlib compression is configured
+/// (`compression` feature); the server must support compressed formats.
+pub const CONTENT_JSON_ZLIB: u8 = 12;
+
 /// Content type identifier for file path data.
 ///
 /// This constant indicates that the payload contains file path information.
@@ -47,6 +73,18 @@ pub const CONTENT_PATH: u8 = 20;
 /// This constant indicates that the payload contains plain text data.
 pub const CONTENT_TEXT: u8 = 30;
 
+/// Content type identifier for gzip-compressed text data.
+///
+/// Sent instead of `CONTENT_TEXT` when gzip compression is configured
+/// (`compression` feature); the server must support compressed formats.
+pub const CONTENT_TEXT_GZ: u8 = 31;
+
+/// Content type identifier for zlib-compressed text data.
+///
+/// Sent instead of `CONTENT_TEXT` when zlib compression is configured
+/// (`compression` feature); the server must support compressed formats.
+pub const CONTENT_TEXT_ZLIB: u8 = 32;
+
 /// Content type identifier for binary data.
 ///
 /// This constant indicates that the payload contains binary data.
@@ -56,3 +94,66 @@ pub const CONTENT_BIN: u8 = 40;
 ///
 /// This constant indicates that the payload contains MessagePack-encoded data.
 pub const CONTENT_MSGPACK: u8 = 50;
+
+/// Content type identifier for base64 data.
+///
+/// This constant indicates that the payload contains base64-encoded text,
+/// for tools that only handle ASCII-safe payloads.
+pub const CONTENT_BASE64: u8 = 60;
+
+/// Control code for template listing operations.
+///
+/// This control code is used when the client wants the server to enumerate
+/// the template files it knows about.
+pub const CTRL_LIST_TEMPLATES: u8 = 13;
+
+/// Control code for template parsing with request headers.
+///
+/// Same as `CTRL_PARSE_TEMPLATE`, but `content-1` starts with a list of
+/// `key: value\n` header lines terminated by a zero byte, followed by the
+/// schema. The response `content-1` may carry response headers the same way.
+pub const CTRL_PARSE_TEMPLATE_WITH_HEADERS: u8 = 14;
+
+/// Control code for template validation operations.
+///
+/// The server parses the template in `content-2` without rendering it and
+/// replies with a JSON object holding a `token` that identifies the parsed
+/// template in later `CTRL_RENDER_COMPILED` requests.
+pub const CTRL_VALIDATE_TEMPLATE: u8 = 16;
+
+/// Control code for rendering a template validated before.
+///
+/// Same as `CTRL_PARSE_TEMPLATE`, but `content-2` is the token returned for
+/// `CTRL_VALIDATE_TEMPLATE` instead of the template.
+pub const CTRL_RENDER_COMPILED: u8 = 17;
+
+/// Control code for rendering a template cached by the server.
+///
+/// Same as `CTRL_PARSE_TEMPLATE`, but `content-2` is the SHA-256 of a template
+/// source sent before, as lowercase hex, instead of the source. Servers that
+/// do not have the source cached reply with `CTRL_STATUS_KO` and the error in
+/// `content-1`.
+pub const CTRL_PARSE_TEMPLATE_HASH: u8 = 18;
+
+/// Control code asking the server to reload its configuration.
+///
+/// The server answers with `CTRL_STATUS_OK` once the new configuration is in
+/// use, or with `CTRL_STATUS_KO` and diagnostics if it refuses or fails.
+pub const CTRL_RELOAD_CONFIG: u8 = 15;
+
+/// Get the version of this crate.
+///
+/// # Returns
+///
+/// The crate version, e.g. `"1.4.2"`.
+pub fn version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
+
+/// Get the highest IPC protocol version this client speaks.
+///
+/// Version 0 records are always supported; version 1 (`PROTOCOL_V1`) adds a
+/// checksum and is used when enabled in the configuration.
+pub fn protocol_version() -> u8 {
+    PROTOCOL_V1
+}
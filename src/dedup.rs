@@ -0,0 +1,258 @@
+//! Coalescing of identical concurrent render requests.
+//!
+//! Available with the `dedup` feature. A `NeutralIpcDeduplicator` shared between
+//! templates (usually across threads) makes sure that only one IPC round-trip is
+//! in flight for a given template and schema: the first caller performs the
+//! request and every concurrent caller with the same request waits for and
+//! receives the same result.
+//!
+//! Renders are blocking IPC round-trips, so callers wait on a `Condvar` rather
+//! than on a shared future, and the in-flight map is a plain `Mutex`, held
+//! only to look up or insert an entry. This needs no async runtime and no
+//! additional dependencies. As with a shared future, the outcome must be
+//! cloned for every caller: errors, which are not `Clone`, are shared in an
+//! `Arc`.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Condvar, Mutex};
+
+use crate::error::{NeutralIpcError, Result};
+
+/// Rendering outcome shared between coalesced callers: the result map and the content.
+pub(crate) type Rendered = (HashMap<String, Value>, String);
+
+/// Outcome shared between coalesced callers.
+type SharedOutcome = std::result::Result<Rendered, Arc<NeutralIpcError>>;
+
+/// A request currently being performed by a leading caller.
+#[derive(Default)]
+struct InFlight {
+    /// Outcome of the request, set once by the leading caller
+    outcome: Mutex<Option<SharedOutcome>>,
+    /// Signaled when the outcome is set
+    done: Condvar,
+}
+
+impl InFlight {
+    /// Store the outcome and wake every waiting caller.
+    fn complete(&self, outcome: SharedOutcome) {
+        let mut slot = self.outcome.lock().unwrap_or_else(|e| e.into_inner());
+        if slot.is_none() {
+            *slot = Some(outcome);
+        }
+        self.done.notify_all();
+    }
+
+    /// Block until the outcome is available and return a copy of it.
+    fn wait(&self) -> SharedOutcome {
+        let mut slot = self.outcome.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if let Some(outcome) = slot.as_ref() {
+                return outcome.clone();
+            }
+            slot = self.done.wait(slot).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+}
+
+/// Completes the in-flight entry even if the leading caller panics.
+struct LeaderGuard<'a> {
+    /// The deduplicator owning the entry
+    dedup: &'a NeutralIpcDeduplicator,
+    /// Request key of the entry
+    key: u64,
+    /// The in-flight entry
+    entry: Arc<InFlight>,
+}
+
+impl Drop for LeaderGuard<'_> {
+    fn drop(&mut self) {
+        let incomplete = io::Error::other("coalesced render did not complete");
+        self.entry.complete(Err(Arc::new(NeutralIpcError::Io(incomplete))));
+        self.dedup.remove(self.key, &self.entry);
+    }
+}
+
+/// Coalesces identical concurrent render requests into a single IPC round-trip.
+///
+/// Share one instance between templates with `Arc` and attach it with
+/// `NeutralIpcTemplate::with_deduplicator`. Requests are identified by a hash
+/// of the template and schema. Results are not cached: once a request has
+/// completed, the next identical render performs a new round-trip.
+///
+/// # Example
+///
+/// ```no_run
+/// use neutralipcrs::{NeutralIpcDeduplicator, NeutralIpcTemplate};
+/// use serde_json::json;
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let dedup = Arc::new(NeutralIpcDeduplicator::new());
+///
+/// let handles: Vec<_> = (0..4).map(|_| {
+///     let dedup = Arc::clone(&dedup);
+///     thread::spawn(move || {
+///         let mut template = NeutralIpcTemplate::from_src_value("{:;text:}", json!({"data": {"text": "Hi"}}))
+///             .unwrap()
+///             .with_deduplicator(dedup);
+///         template.render()
+///     })
+/// }).collect();
+///
+/// for handle in handles {
+///     println!("{}", handle.join().unwrap().unwrap());
+/// }
+/// ```
+#[derive(Default)]
+pub struct NeutralIpcDeduplicator {
+    /// Requests currently in flight, by request key
+    in_flight: Mutex<HashMap<u64, Arc<InFlight>>>,
+}
+
+impl NeutralIpcDeduplicator {
+    /// Create a new deduplicator with no requests in flight.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the number of distinct requests currently in flight.
+    pub fn in_flight(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Run `render` for `key`, or wait for the identical request already in flight.
+    ///
+    /// Every caller receives a copy of a successful result. When the request
+    /// fails, every caller receives `NeutralIpcError::CoalescedRender` sharing
+    /// the same error, unless no other caller waited for it: the leading
+    /// caller then receives its error unchanged.
+    pub(crate) fn run<F>(&self, key: u64, render: F) -> Result<Rendered>
+    where
+        F: FnOnce() -> Result<Rendered>,
+    {
+        let (entry, leader) = {
+            let mut in_flight = self.lock();
+            match in_flight.get(&key) {
+                Some(entry) => (Arc::clone(entry), false),
+                None => {
+                    let entry = Arc::new(InFlight::default());
+                    in_flight.insert(key, Arc::clone(&entry));
+                    (entry, true)
+                }
+            }
+        };
+
+        if !leader {
+            return entry.wait().map_err(NeutralIpcError::CoalescedRender);
+        }
+
+        let guard = LeaderGuard { dedup: self, key, entry };
+        let result = render();
+        // Once the entry is removed no caller can join, so any other holder is a waiter.
+        self.remove(key, &guard.entry);
+        let waited = Arc::strong_count(&guard.entry) > 1;
+        match result {
+            Ok(rendered) => {
+                guard.entry.complete(Ok(rendered.clone()));
+                Ok(rendered)
+            }
+            Err(err) if !waited => Err(err),
+            Err(err) => {
+                let err = Arc::new(err);
+                guard.entry.complete(Err(Arc::clone(&err)));
+                Err(NeutralIpcError::CoalescedRender(err))
+            }
+        }
+    }
+
+    /// Remove `entry` from the requests in flight, unless `key` was taken by a newer request.
+    fn remove(&self, key: u64, entry: &Arc<InFlight>) {
+        let mut in_flight = self.lock();
+        if in_flight.get(&key).is_some_and(|current| Arc::ptr_eq(current, entry)) {
+            in_flight.remove(&key);
+        }
+    }
+
+    /// Lock the in-flight map, recovering from a poisoned lock.
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<u64, Arc<InFlight>>> {
+        self.in_flight.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Barrier;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_run_coalesces_concurrent_requests() {
+        let dedup = Arc::new(NeutralIpcDeduplicator::new());
+        let calls = Arc::new(AtomicUsize::new(0));
+        let barrier = Arc::new(Barrier::new(4));
+
+        let handles: Vec<_> = (0..4).map(|_| {
+            let dedup = Arc::clone(&dedup);
+            let calls = Arc::clone(&calls);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                barrier.wait();
+                dedup.run(42, || {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(200));
+                    Ok((HashMap::new(), "Hello!".to_string()))
+                })
+            })
+        }).collect();
+
+        for handle in handles {
+            assert_eq!(handle.join().unwrap().unwrap().1, "Hello!");
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(dedup.in_flight(), 0);
+    }
+
+    #[test]
+    fn test_run_shares_errors_and_does_not_cache() {
+        let dedup = Arc::new(NeutralIpcDeduplicator::new());
+
+        let leader = {
+            let dedup = Arc::clone(&dedup);
+            thread::spawn(move || {
+                dedup.run(7, || {
+                    thread::sleep(Duration::from_millis(200));
+                    Err(NeutralIpcError::ConnectionClosed)
+                })
+            })
+        };
+        while dedup.in_flight() == 0 {
+            thread::yield_now();
+        }
+
+        let follower = dedup.run(7, || panic!("follower must not render"));
+        let (leader, follower) = match (leader.join().unwrap(), follower) {
+            (Err(NeutralIpcError::CoalescedRender(leader)), Err(NeutralIpcError::CoalescedRender(follower))) => {
+                (leader, follower)
+            }
+            other => panic!("expected CoalescedRender, got {:?}", other),
+        };
+        assert!(Arc::ptr_eq(&leader, &follower));
+        assert!(matches!(*follower, NeutralIpcError::ConnectionClosed));
+
+        let again = dedup.run(7, || Ok((HashMap::new(), "fresh".to_string()))).unwrap();
+        assert_eq!(again.1, "fresh");
+    }
+
+    #[test]
+    fn test_run_returns_errors_unchanged_without_waiters() {
+        let dedup = NeutralIpcDeduplicator::new();
+        let result = dedup.run(7, || Err(NeutralIpcError::ConnectionClosed));
+        assert!(matches!(result, Err(NeutralIpcError::ConnectionClosed)));
+        assert_eq!(dedup.in_flight(), 0);
+    }
+}
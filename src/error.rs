@@ -5,6 +5,9 @@
 
 use std::fmt;
 use std::io;
+use std::string::FromUtf8Error;
+use std::path::PathBuf;
+use std::sync::Arc;
 
 /// Result type alias for Neutral IPC operations.
 ///
@@ -28,13 +31,147 @@ pub enum NeutralIpcError {
     /// Connection was closed unexpectedly during communication.
     ConnectionClosed,
     /// Invalid UTF-8 encoding in response content.
-    InvalidUtf8,
+    /// The underlying error gives the offset of the invalid byte and the original bytes.
+    InvalidUtf8(FromUtf8Error),
     /// JSON parsing or serialization error.
     Json(serde_json::Error),
+    /// Invalid base64 encoding in template content.
+    Base64(base64::DecodeError),
+    /// The JSON content of a server response cannot be parsed.
+    InvalidResultJson {
+        /// Underlying JSON error, including line and column
+        error: serde_json::Error,
+        /// Start of the offending payload, with control characters escaped
+        snippet: String,
+    },
+    /// A schema given as a JSON string cannot be parsed.
+    InvalidSchemaJson {
+        /// Underlying JSON error, including line and column
+        error: serde_json::Error,
+        /// Start of the offending schema string, with control characters escaped
+        snippet: String,
+    },
     /// MessagePack serialization error.
     MsgPackEncode(rmp_serde::encode::Error),
     /// MessagePack deserialization error.
     MsgPackDecode(rmp_serde::decode::Error),
+    /// The server does not support the requested operation.
+    NotSupported,
+    /// The server responded with `CTRL_STATUS_KO`; carries its diagnostics.
+    ServerError(String),
+    /// The server kept rate-limiting the render; carries the last delay it asked for.
+    RateLimited {
+        /// Delay in milliseconds the server asked to wait before retrying
+        retry_after_ms: u64,
+    },
+    /// The deadline of a render passed before the render completed.
+    DeadlineExceeded,
+    /// The process already has the configured maximum of render connections open.
+    TooManyConnections {
+        /// Configured `max_concurrent_connections`
+        limit: usize,
+    },
+    /// The server refused a command because the client lacks the privileges it requires.
+    PermissionDenied(String),
+    /// A render coalesced with identical concurrent renders failed; carries the
+    /// error of the shared request, as received by every coalesced caller.
+    CoalescedRender(Arc<NeutralIpcError>),
+    /// A configuration value is invalid.
+    InvalidConfig(String),
+    /// The named environment variable is not set.
+    EnvVarNotFound(String),
+    /// A schema update conflicts with the existing schema at the given path.
+    SchemaMergeConflict(String),
+    /// The schema is not valid, e.g. it is not a JSON object.
+    InvalidSchema(String),
+    /// The schema has top-level keys outside the allowed sections; lists the unexpected keys.
+    SchemaLayout(Vec<String>),
+    /// The schema is sealed and cannot be modified.
+    SchemaSealedError,
+    /// A schema file could not be read.
+    SchemaFileIo {
+        /// Path of the schema file
+        path: PathBuf,
+        /// Underlying IO error
+        source: io::Error,
+    },
+    /// A schema file does not contain valid JSON.
+    SchemaFileJson {
+        /// Path of the schema file
+        path: PathBuf,
+        /// Underlying JSON error, including line and column
+        source: serde_json::Error,
+    },
+    /// A schema in a non-JSON format (YAML, TOML) could not be parsed.
+    SchemaParse {
+        /// Name of the schema format
+        format: &'static str,
+        /// Underlying parser message
+        message: String,
+    },
+    /// The template file cannot be watched for changes.
+    Watch(String),
+    /// A schema could not be fetched from a URL, or the response is not JSON.
+    SchemaFetchError(String),
+    /// The connection through the proxy failed, e.g. the handshake or authentication was rejected.
+    ProxyError(String),
+    /// The CRC32 checksum of a received record does not match its content.
+    ChecksumMismatch {
+        /// Checksum declared in the record header
+        expected: u32,
+        /// Checksum computed from the received content
+        actual: u32,
+    },
+    /// A received record does not follow the protocol, e.g. its content does not match the header.
+    ProtocolViolation(String),
+    /// The serialized schema exceeds the configured maximum schema size.
+    SchemaTooLarge {
+        /// Size of the serialized schema in bytes
+        size: usize,
+        /// Configured limit in bytes
+        limit: usize,
+    },
+    /// The template source does not fit in a record, whose content lengths are 32-bit.
+    TemplateTooLarge {
+        /// Size of the template source in bytes
+        size: u64,
+        /// Largest content a record can carry, in bytes
+        limit: u64,
+    },
+    /// A template source reader yielded more or fewer bytes than declared.
+    ContentLengthMismatch {
+        /// Length declared with `set_source_reader`
+        expected: u64,
+        /// Bytes read before the mismatch was detected
+        actual: u64,
+    },
+    /// A template source reader cannot be used, e.g. an earlier render already consumed it.
+    SourceReaderUnavailable(String),
+    /// The schema does not match the validation schema; lists every violation
+    /// as (JSON pointer to the offending value, message).
+    SchemaValidation(Vec<(String, String)>),
+    /// A page fragment failed to render.
+    FragmentRender {
+        /// Name of the fragment
+        fragment: String,
+        /// Error of the fragment render
+        source: Box<NeutralIpcError>,
+    },
+    /// Sending a template to the server or reading its rendering failed.
+    Render {
+        /// Template path, or `source <hash>` for a template given as source code
+        template: String,
+        /// Size of the serialized schema sent, in bytes
+        schema_size: usize,
+        /// Error of the render request
+        source: Box<NeutralIpcError>,
+    },
+    /// A request header name or value cannot be encoded in a record.
+    InvalidRequestHeader(String),
+    /// A template path resolves outside the configured template root.
+    PathTraversal(String),
+    /// A record header declares a protocol version (reserved byte) this client does not know.
+    UnsupportedProtocolVersion(u8),
 }
 
 impl fmt::Display for NeutralIpcError {
@@ -44,10 +181,85 @@ impl fmt::Display for NeutralIpcError {
             NeutralIpcError::InvalidHeaderLength => write!(f, "Invalid header length received"),
             NeutralIpcError::InvalidResponse => write!(f, "Invalid response from server"),
             NeutralIpcError::ConnectionClosed => write!(f, "Connection closed unexpectedly"),
-            NeutralIpcError::InvalidUtf8 => write!(f, "Invalid UTF-8 encoding in response"),
+            NeutralIpcError::InvalidUtf8(err) => write!(f, "Invalid UTF-8 encoding in response: {}", err),
             NeutralIpcError::Json(err) => write!(f, "JSON error: {}", err),
+            NeutralIpcError::Base64(err) => write!(f, "Base64 decode error: {}", err),
+            NeutralIpcError::InvalidResultJson { error, snippet } => {
+                write!(f, "Invalid JSON in server response: {} (payload: \"{}\")", error, snippet)
+            }
+            NeutralIpcError::InvalidSchemaJson { error, snippet } => {
+                write!(f, "Invalid JSON in schema: {} (schema: \"{}\")", error, snippet)
+            }
             NeutralIpcError::MsgPackEncode(err) => write!(f, "MsgPack encode error: {}", err),
             NeutralIpcError::MsgPackDecode(err) => write!(f, "MsgPack decode error: {}", err),
+            NeutralIpcError::NotSupported => write!(f, "Operation not supported by server"),
+            NeutralIpcError::ServerError(diagnostics) => write!(f, "Server error: {}", diagnostics),
+            NeutralIpcError::RateLimited { retry_after_ms } => {
+                write!(f, "Rate limited by the server, retry after {} ms", retry_after_ms)
+            }
+            NeutralIpcError::DeadlineExceeded => write!(f, "Deadline exceeded before the render completed"),
+            NeutralIpcError::TooManyConnections { limit } => {
+                write!(f, "Too many connections: the limit of {} concurrent connections is reached", limit)
+            }
+            NeutralIpcError::PermissionDenied(reason) => write!(f, "Permission denied: {}", reason),
+            NeutralIpcError::CoalescedRender(reason) => write!(f, "Coalesced render failed: {}", reason),
+            NeutralIpcError::InvalidConfig(reason) => write!(f, "Invalid configuration: {}", reason),
+            NeutralIpcError::EnvVarNotFound(name) => write!(f, "Environment variable '{}' not found", name),
+            NeutralIpcError::SchemaMergeConflict(path) => write!(f, "Schema merge conflict at '{}'", path),
+            NeutralIpcError::SchemaLayout(keys) => {
+                write!(f, "Unexpected top-level schema keys: {}", keys.join(", "))
+            }
+            NeutralIpcError::SchemaSealedError => write!(f, "Schema is sealed and cannot be modified"),
+            NeutralIpcError::InvalidSchema(reason) => write!(f, "Invalid schema: {}", reason),
+            NeutralIpcError::SchemaFileIo { path, source } => {
+                write!(f, "Cannot read schema file '{}': {}", path.display(), source)
+            }
+            NeutralIpcError::SchemaFileJson { path, source } => {
+                write!(f, "Invalid JSON in schema file '{}': {}", path.display(), source)
+            }
+            NeutralIpcError::SchemaParse { format, message } => {
+                write!(f, "{} schema parse error: {}", format, message)
+            }
+            NeutralIpcError::Watch(reason) => write!(f, "Cannot watch template file: {}", reason),
+            NeutralIpcError::SchemaFetchError(reason) => write!(f, "Cannot fetch schema: {}", reason),
+            NeutralIpcError::ProxyError(reason) => write!(f, "Proxy error: {}", reason),
+            NeutralIpcError::ChecksumMismatch { expected, actual } => {
+                write!(f, "Checksum mismatch: expected {:08x}, got {:08x}", expected, actual)
+            }
+            NeutralIpcError::ProtocolViolation(reason) => write!(f, "Protocol violation: {}", reason),
+            NeutralIpcError::SchemaTooLarge { size, limit } => {
+                write!(f, "Schema too large: {} bytes exceeds the limit of {} bytes", size, limit)
+            }
+            NeutralIpcError::TemplateTooLarge { size, limit } => {
+                write!(f, "Template too large: {} bytes exceeds the limit of {} bytes", size, limit)
+            }
+            NeutralIpcError::ContentLengthMismatch { expected, actual } => {
+                write!(f, "Template source length mismatch: declared {} bytes, read {}", expected, actual)
+            }
+            NeutralIpcError::SourceReaderUnavailable(reason) => {
+                write!(f, "Template source reader unavailable: {}", reason)
+            }
+            NeutralIpcError::SchemaValidation(violations) => {
+                write!(f, "Schema validation failed:")?;
+                for (path, message) in violations {
+                    let path = if path.is_empty() { "/" } else { path };
+                    write!(f, " {}: {};", path, message)?;
+                }
+                Ok(())
+            }
+            NeutralIpcError::FragmentRender { fragment, source } => {
+                write!(f, "Fragment '{}' failed to render: {}", fragment, source)
+            }
+            NeutralIpcError::Render { template, source, .. } => {
+                write!(f, "render of '{}' failed: {}", template, source)
+            }
+            NeutralIpcError::InvalidRequestHeader(reason) => write!(f, "Invalid request header: {}", reason),
+            NeutralIpcError::PathTraversal(path) => {
+                write!(f, "Template path '{}' resolves outside the template root", path)
+            }
+            NeutralIpcError::UnsupportedProtocolVersion(version) => {
+                write!(f, "Unsupported protocol version in record header: {}", version)
+            }
         }
     }
 }
@@ -57,13 +269,92 @@ impl std::error::Error for NeutralIpcError {
         match self {
             NeutralIpcError::Io(err) => Some(err),
             NeutralIpcError::Json(err) => Some(err),
+            NeutralIpcError::Base64(err) => Some(err),
+            NeutralIpcError::InvalidResultJson { error, .. } => Some(error),
+            NeutralIpcError::InvalidSchemaJson { error, .. } => Some(error),
             NeutralIpcError::MsgPackEncode(err) => Some(err),
             NeutralIpcError::MsgPackDecode(err) => Some(err),
+            NeutralIpcError::InvalidUtf8(err) => Some(err),
+            NeutralIpcError::SchemaFileIo { source, .. } => Some(source),
+            NeutralIpcError::SchemaFileJson { source, .. } => Some(source),
+            NeutralIpcError::FragmentRender { source, .. } => Some(source.as_ref()),
+            NeutralIpcError::Render { source, .. } => Some(source.as_ref()),
+            NeutralIpcError::CoalescedRender(err) => Some(err.as_ref()),
             _ => None,
         }
     }
 }
 
+/// Maximum number of payload characters kept in an error snippet.
+const SNIPPET_CHARS: usize = 256;
+
+impl NeutralIpcError {
+    /// Build an `InvalidResultJson` error keeping the start of the response payload.
+    pub(crate) fn invalid_result_json(error: serde_json::Error, payload: &str) -> Self {
+        NeutralIpcError::InvalidResultJson { error, snippet: snippet(payload) }
+    }
+
+    /// Build an `InvalidSchemaJson` error keeping the start of the schema string.
+    pub(crate) fn invalid_schema_json(error: serde_json::Error, schema: &str) -> Self {
+        NeutralIpcError::InvalidSchemaJson { error, snippet: snippet(schema) }
+    }
+
+    /// Build a timeout error: `Io` with `io::ErrorKind::TimedOut` and the given message.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcError;
+    ///
+    /// assert!(NeutralIpcError::timeout("render took too long").is_timeout());
+    /// ```
+    pub fn timeout(message: impl Into<String>) -> Self {
+        NeutralIpcError::Io(io::Error::new(io::ErrorKind::TimedOut, message.into()))
+    }
+
+    /// Whether the error is a network timeout, usually worth retrying.
+    ///
+    /// True for `Io` errors of kind `TimedOut` or `WouldBlock` (what a socket
+    /// timeout reports on some platforms), also when wrapped in a `Render` or
+    /// `FragmentRender` error.
+    pub fn is_timeout(&self) -> bool {
+        matches!(self.io_kind(), Some(io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock))
+    }
+
+    /// Whether the server refused the connection, e.g. because it is not running.
+    ///
+    /// Looks through `Render` and `FragmentRender` errors like `is_timeout`.
+    pub fn is_connection_refused(&self) -> bool {
+        self.io_kind() == Some(io::ErrorKind::ConnectionRefused)
+    }
+
+    /// Whether the connection was closed by the server while writing the request.
+    ///
+    /// Looks through `Render` and `FragmentRender` errors like `is_timeout`.
+    pub fn is_broken_pipe(&self) -> bool {
+        self.io_kind() == Some(io::ErrorKind::BrokenPipe)
+    }
+
+    /// Get the kind of a network `Io` error, looking through render wrappers.
+    fn io_kind(&self) -> Option<io::ErrorKind> {
+        match self {
+            NeutralIpcError::Io(err) => Some(err.kind()),
+            NeutralIpcError::Render { source, .. } | NeutralIpcError::FragmentRender { source, .. } => source.io_kind(),
+            _ => None,
+        }
+    }
+}
+
+/// Take the first `SNIPPET_CHARS` characters of a payload, escaping quotes,
+/// backslashes and control characters so the snippet stays on one line.
+fn snippet(payload: &str) -> String {
+    let mut snippet: String = payload.chars().take(SNIPPET_CHARS).flat_map(char::escape_debug).collect();
+    if payload.chars().nth(SNIPPET_CHARS).is_some() {
+        snippet.push_str("...");
+    }
+    snippet
+}
+
 /// Convert from `io::Error` to `NeutralIpcError`.
 ///
 /// This implementation allows IO errors to be automatically converted
@@ -84,6 +375,12 @@ impl From<serde_json::Error> for NeutralIpcError {
     }
 }
 
+impl From<base64::DecodeError> for NeutralIpcError {
+    fn from(err: base64::DecodeError) -> Self {
+        NeutralIpcError::Base64(err)
+    }
+}
+
 impl From<rmp_serde::encode::Error> for NeutralIpcError {
     fn from(err: rmp_serde::encode::Error) -> Self {
         NeutralIpcError::MsgPackEncode(err)
@@ -0,0 +1,154 @@
+//! Conversion of YAML and TOML schema sources into JSON values.
+//!
+//! Available with the `yaml` and `toml` features. Schemas written in these
+//! formats are converted to `serde_json::Value` so they can be deep-merged like
+//! any other schema.
+
+use serde_json::Value;
+
+use crate::error::{NeutralIpcError, Result};
+
+/// Parse a YAML document into a JSON value.
+///
+/// Anchors and aliases are resolved, and `<<` merge keys are applied.
+///
+/// # Errors
+///
+/// Returns `NeutralIpcError::SchemaParse` if the document is not valid YAML or
+/// cannot be represented as JSON (e.g. non-string mapping keys).
+#[cfg(feature = "yaml")]
+pub(crate) fn from_yaml_str(input: &str) -> Result<Value> {
+    let parse_error = |err: serde_yaml::Error| NeutralIpcError::SchemaParse {
+        format: "YAML",
+        message: err.to_string(),
+    };
+
+    let mut yaml: serde_yaml::Value = serde_yaml::from_str(input).map_err(parse_error)?;
+    yaml.apply_merge().map_err(parse_error)?;
+
+    serde_json::to_value(yaml).map_err(|err| NeutralIpcError::SchemaParse {
+        format: "YAML",
+        message: err.to_string(),
+    })
+}
+
+/// Parse a TOML document into a JSON value.
+///
+/// Datetimes are converted to their RFC 3339 string representation.
+///
+/// # Errors
+///
+/// Returns `NeutralIpcError::SchemaParse` if the document is not valid TOML or
+/// contains floats that JSON cannot represent (NaN or infinity).
+#[cfg(feature = "toml")]
+pub(crate) fn from_toml_str(input: &str) -> Result<Value> {
+    let table: toml::Table = input.parse().map_err(|err: toml::de::Error| NeutralIpcError::SchemaParse {
+        format: "TOML",
+        message: err.to_string(),
+    })?;
+
+    toml_to_json(toml::Value::Table(table))
+}
+
+/// Recursively convert a TOML value into a JSON value.
+#[cfg(feature = "toml")]
+fn toml_to_json(value: toml::Value) -> Result<Value> {
+    let json = match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::Number(i.into()),
+        toml::Value::Float(f) => match serde_json::Number::from_f64(f) {
+            Some(n) => Value::Number(n),
+            None => {
+                return Err(NeutralIpcError::SchemaParse {
+                    format: "TOML",
+                    message: format!("float {} cannot be represented in JSON", f),
+                })
+            }
+        },
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(dt) => Value::String(dt.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect::<Result<_>>()?),
+        toml::Value::Table(table) => Value::Object(
+            table.into_iter()
+                .map(|(key, value)| Ok((key, toml_to_json(value)?)))
+                .collect::<Result<_>>()?,
+        ),
+    };
+    Ok(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_from_yaml_str_resolves_anchors_and_merge_keys() {
+        let yaml = r#"
+defaults: &defaults
+  lang: en
+  theme: dark
+data:
+  text: Hello!
+  number: 123
+  site:
+    <<: *defaults
+    theme: light
+  copy: *defaults
+"#;
+
+        let value = from_yaml_str(yaml).unwrap();
+        assert_eq!(value["data"], json!({
+            "text": "Hello!",
+            "number": 123,
+            "site": {"lang": "en", "theme": "light"},
+            "copy": {"lang": "en", "theme": "dark"}
+        }));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_from_yaml_str_reports_parse_errors() {
+        match from_yaml_str("data: [unclosed") {
+            Err(NeutralIpcError::SchemaParse { format, .. }) => assert_eq!(format, "YAML"),
+            other => panic!("expected SchemaParse, got {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_from_toml_str_stringifies_datetimes() {
+        let toml = r#"
+[data]
+text = "Hello!"
+number = 123
+ratio = 0.5
+enabled = true
+updated = 2024-05-01T10:30:00Z
+tags = ["a", "b"]
+"#;
+
+        let value = from_toml_str(toml).unwrap();
+        assert_eq!(value, json!({
+            "data": {
+                "text": "Hello!",
+                "number": 123,
+                "ratio": 0.5,
+                "enabled": true,
+                "updated": "2024-05-01T10:30:00Z",
+                "tags": ["a", "b"]
+            }
+        }));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_from_toml_str_reports_parse_errors() {
+        match from_toml_str("[data\ntext = 1") {
+            Err(NeutralIpcError::SchemaParse { format, .. }) => assert_eq!(format, "TOML"),
+            other => panic!("expected SchemaParse, got {:?}", other),
+        }
+        assert!(matches!(from_toml_str("value = nan"), Err(NeutralIpcError::SchemaParse { .. })));
+    }
+}
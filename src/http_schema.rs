@@ -0,0 +1,155 @@
+//! Fetching of JSON schemas over HTTP and HTTPS.
+//!
+//! Available with the `http-schema` feature. Schemas kept in a remote
+//! configuration service are fetched with a blocking request bounded by the
+//! `schema_fetch_timeout_secs` of the configuration. When the configuration
+//! enables `schema_fetch_cache`, responses with an `ETag` are kept in a
+//! process-wide cache and revalidated with `If-None-Match` on later fetches.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use crate::config::NeutralIpcConfig;
+use crate::error::{NeutralIpcError, Result};
+use crate::json;
+
+/// Schemas fetched with an `ETag`, by URL: the entity tag and the parsed schema.
+static CACHE: Mutex<Option<HashMap<String, (String, Value)>>> = Mutex::new(None);
+
+/// Fetch the JSON document at `url`.
+///
+/// # Errors
+///
+/// Returns `NeutralIpcError::SchemaFetchError` if the request fails, the
+/// server answers with a status other than 200 (or 304 for a cached URL), or
+/// the body is not valid JSON.
+pub(crate) fn fetch_schema(url: &str, config: &NeutralIpcConfig) -> Result<Value> {
+    let fetch_error = |reason: String| NeutralIpcError::SchemaFetchError(format!("{}: {}", url, reason));
+    let use_cache = config.get_schema_fetch_cache();
+    let cached = if use_cache { cached(url) } else { None };
+
+    let timeout = match config.get_schema_fetch_timeout_secs() {
+        0 => None,
+        secs => Some(Duration::from_secs(secs as u64)),
+    };
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(timeout)
+        .http_status_as_error(false)
+        .build()
+        .into();
+
+    let mut request = agent.get(url);
+    if let Some((etag, _)) = &cached {
+        request = request.header("If-None-Match", etag.as_str());
+    }
+    let mut response = request.call().map_err(|err| fetch_error(err.to_string()))?;
+
+    match (response.status().as_u16(), cached) {
+        (304, Some((_, schema))) => return Ok(schema),
+        (200, _) => {}
+        (status, _) => return Err(fetch_error(format!("HTTP status {}", status))),
+    }
+
+    let etag = response
+        .headers()
+        .get("etag")
+        .and_then(|etag| etag.to_str().ok())
+        .map(str::to_string);
+    let body = response.body_mut().read_to_string().map_err(|err| fetch_error(err.to_string()))?;
+    let schema = json::from_str(&body).map_err(|err| fetch_error(format!("invalid JSON: {}", err)))?;
+
+    if let (true, Some(etag)) = (use_cache, etag) {
+        let mut cache = CACHE.lock().unwrap_or_else(PoisonError::into_inner);
+        cache.get_or_insert_with(HashMap::new).insert(url.to_string(), (etag, schema.clone()));
+    }
+    Ok(schema)
+}
+
+/// Get the cached entity tag and schema of `url`, if any.
+fn cached(url: &str) -> Option<(String, Value)> {
+    let cache = CACHE.lock().unwrap_or_else(PoisonError::into_inner);
+    cache.as_ref()?.get(url).cloned()
+}
+
+/// Remove every schema from the cache.
+pub(crate) fn clear_cache() {
+    *CACHE.lock().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockServer;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpStream;
+
+    /// Read an HTTP request and return its `If-None-Match` header, if any.
+    fn read_http_request(stream: &TcpStream) -> Option<String> {
+        let mut if_none_match = None;
+        for line in BufReader::new(stream).lines() {
+            let line = line.ok()?;
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("if-none-match") {
+                    if_none_match = Some(value.trim().to_string());
+                }
+            }
+        }
+        if_none_match
+    }
+
+    fn write_http_response(mut stream: &TcpStream, status: &str, headers: &str, body: &str) {
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+            status,
+            body.len(),
+            headers,
+            body
+        );
+        stream.write_all(response.as_bytes()).unwrap();
+    }
+
+    fn url(server: &MockServer, path: &str) -> String {
+        format!("http://127.0.0.1:{}{}", server.config().get_port(), path)
+    }
+
+    #[test]
+    fn test_fetch_schema_revalidates_cached_etag() {
+        let server = MockServer::start(|stream| match read_http_request(&stream).as_deref() {
+            Some("\"v1\"") => write_http_response(&stream, "304 Not Modified", "ETag: \"v1\"\r\n", ""),
+            _ => write_http_response(&stream, "200 OK", "ETag: \"v1\"\r\n", r#"{"data": {"fetched": 1}}"#),
+        });
+        let url = url(&server, "/cached.json");
+        let mut config = NeutralIpcConfig::default();
+
+        assert_eq!(fetch_schema(&url, &config).unwrap()["data"]["fetched"], 1);
+        assert!(cached(&url).is_none());
+
+        config.set_schema_fetch_cache(true);
+        assert_eq!(fetch_schema(&url, &config).unwrap()["data"]["fetched"], 1);
+        assert_eq!(cached(&url).unwrap().0, "\"v1\"");
+        assert_eq!(fetch_schema(&url, &config).unwrap()["data"]["fetched"], 1);
+    }
+
+    #[test]
+    fn test_fetch_schema_errors() {
+        let server = MockServer::start(|stream| {
+            read_http_request(&stream);
+            write_http_response(&stream, "200 OK", "", "not json");
+        });
+        let config = NeutralIpcConfig::default();
+        let err = fetch_schema(&url(&server, "/text"), &config).unwrap_err();
+        assert!(matches!(&err, NeutralIpcError::SchemaFetchError(reason) if reason.contains("invalid JSON")));
+
+        let server = MockServer::start(|stream| {
+            read_http_request(&stream);
+            write_http_response(&stream, "404 Not Found", "", "");
+        });
+        let err = fetch_schema(&url(&server, "/missing.json"), &config).unwrap_err();
+        assert!(matches!(&err, NeutralIpcError::SchemaFetchError(reason) if reason.ends_with("HTTP status 404")));
+    }
+}
@@ -0,0 +1,59 @@
+//! JSON parsing backend used internally by the client.
+//!
+//! By default JSON is parsed with `serde_json`. When the `simd-json` feature is
+//! enabled, parsing goes through simd-json instead, which is considerably faster
+//! for large documents such as multi-megabyte render results. The public API
+//! always exposes `serde_json::Value`, and inputs rejected by simd-json fall
+//! back to `serde_json` so both backends accept exactly the same documents.
+
+use serde_json::Value;
+
+/// Parse a JSON string into a `Value` using the configured backend.
+pub(crate) fn from_str(input: &str) -> serde_json::Result<Value> {
+    from_slice(input.as_bytes())
+}
+
+/// Parse JSON bytes into a `Value` using the configured backend.
+#[cfg(feature = "simd-json")]
+pub(crate) fn from_slice(input: &[u8]) -> serde_json::Result<Value> {
+    // simd-json parses in place, so it needs its own mutable copy of the input.
+    let mut buffer = input.to_vec();
+    match simd_json::serde::from_slice::<Value>(&mut buffer) {
+        Ok(value) => Ok(value),
+        Err(_) => serde_json::from_slice(input),
+    }
+}
+
+/// Parse JSON bytes into a `Value` using the configured backend.
+#[cfg(not(feature = "simd-json"))]
+pub(crate) fn from_slice(input: &[u8]) -> serde_json::Result<Value> {
+    serde_json::from_slice(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str_matches_serde_json() {
+        let inputs = [
+            r#"{"has_error":false,"status_code":"200","status_text":"OK","status_param":""}"#,
+            r#"{"data":{"text":"Hello é\n","number":123,"float":1.5,"neg":-7,"list":[1,"two",null,true]}}"#,
+            r#"{"big":18446744073709551615,"exp":1e10,"nested":{"a":{"b":{"c":[]}}}}"#,
+            r#"[]"#,
+            r#""plain string""#,
+        ];
+
+        for input in inputs {
+            let expected: Value = serde_json::from_str(input).unwrap();
+            assert_eq!(from_str(input).unwrap(), expected, "input: {}", input);
+            assert_eq!(from_slice(input.as_bytes()).unwrap(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_from_str_rejects_invalid_json() {
+        assert!(from_str("{\"data\":").is_err());
+        assert!(from_str("").is_err());
+    }
+}
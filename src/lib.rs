@@ -44,16 +44,86 @@
 //!
 //! The client reads the server configuration from `/etc/neutral-ipc-cfg.json` to
 //! determine connection settings (host and port).
+//!
+//! # Features
+//!
+//! - `simd-json`: parse render results and JSON schemas with simd-json instead
+//!   of serde_json. The public API still uses `serde_json::Value`.
+//! - `compression`: compress the JSON schema and text template blocks with
+//!   gzip or zlib (see `NeutralIpcConfig::set_compression`). Compressed blocks
+//!   are sent with compressed formats such as `CONTENT_JSON_GZ`; requests are
+//!   sent again uncompressed if the server rejects them.
+//! - `proxy`: connect to the server through a SOCKS5 proxy (see
+//!   `NeutralIpcConfig::with_proxy`).
+//! - `yaml`, `toml`: merge schemas written in YAML or TOML (see
+//!   `NeutralIpcTemplate::merge_schema_yaml` and `merge_schema_toml`).
+//! - `yaml-config`: read the client configuration from YAML files (see
+//!   `NeutralIpcConfig::from_yaml_file`).
+//! - `toml-config`: read and write the client configuration as TOML (see
+//!   `NeutralIpcConfig::to_toml_string` and `save_to_file`).
+//! - `dedup`: coalesce identical concurrent renders into a single IPC
+//!   round-trip (see `NeutralIpcDeduplicator`).
+//! - `validate`: validate the schema against a JSON Schema before rendering
+//!   (see `NeutralIpcTemplate::set_validation_schema`).
+//! - `http-schema`: merge schemas fetched over HTTP or HTTPS (see
+//!   `NeutralIpcTemplate::merge_schema_from_url`).
+//! - `logging`: log warnings with the `log` crate, e.g. for schema keys
+//!   outside the sections the server reads (see
+//!   `NeutralIpcTemplate::set_strict_schema_layout`).
+//! - `schema-macro`: build schemas from named sections with the
+//!   `neutral_schema!` macro.
+//! - `watch`: reload file templates when they change on disk (see
+//!   `NeutralIpcTemplate::watch_template_file`).
+//! - `async-tokio`, `async-std`: render without blocking the executor with
+//!   `NeutralIpcTemplate::render_async`, on tokio or async-std (and smol).
+//! - `cli`: build the `neutral-render` binary, which renders a template given
+//!   on the command line and prints the result to stdout.
+//! - `serde`: serialize and deserialize `NeutralIpcTemplate` for caching or
+//!   passing between processes.
 
 
 pub mod config;
 pub mod constants;
 pub mod template;
 pub mod client;
+pub mod compose;
+#[cfg(any(feature = "async-tokio", feature = "async-std"))]
+pub(crate) mod async_client;
 pub(crate) mod error;
+#[cfg(feature = "compression")]
+pub(crate) mod compression;
+#[cfg(feature = "http-schema")]
+pub(crate) mod http_schema;
+pub(crate) mod json;
+pub mod pipeline;
+pub mod prepared;
+#[cfg(feature = "schema-macro")]
+mod schema_macro;
+#[cfg(feature = "dedup")]
+pub mod dedup;
+#[cfg(any(feature = "yaml", feature = "toml"))]
+pub(crate) mod formats;
+#[cfg(test)]
+pub(crate) mod mock;
 pub(crate) mod record;
+pub mod schema;
 
-pub use config::NeutralIpcConfig;
+pub use client::RenderStream;
+pub use compose::{FragmentErrorPolicy, Page};
+pub use config::{NeutralIpcConfig, NeutralIpcConfigBuilder, SchemaSizePolicy};
+#[cfg(feature = "compression")]
+pub use config::CompressionType;
 pub use constants::*;
+#[cfg(feature = "dedup")]
+pub use dedup::NeutralIpcDeduplicator;
 pub use error::NeutralIpcError;
-pub use template::NeutralIpcTemplate;
+pub use pipeline::{PipelinedClient, PipelinedResponses};
+pub use prepared::{DryRun, PreparedRequest, RenderResult};
+pub use record::{ContentFormat, NeutralIpcRawRecord, RecordHeader};
+pub use schema::MergeStrategy;
+#[cfg(feature = "schema-macro")]
+#[doc(hidden)]
+pub use serde_json as __serde_json;
+pub use template::{
+    CompiledTemplate, NeutralIpcTemplate, NewlineStyle, Redirect, ServerDiagnostics, TemplateKind, TemplateOptions,
+};
@@ -0,0 +1,93 @@
+//! Mock Neutral IPC server used by the unit tests.
+//!
+//! The mock server listens on an ephemeral local port and hands every accepted
+//! connection to a handler closure, so tests can exercise the client without a
+//! running Neutral server.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::config::NeutralIpcConfig;
+use crate::constants::*;
+use crate::record::NeutralIpcRecord;
+
+/// Held by the tests that set `max_concurrent_connections`, as the limit is
+/// shared by every connection of the test process.
+pub(crate) static CONNECTION_LIMIT: Mutex<()> = Mutex::new(());
+
+/// Mock server accepting connections on a local ephemeral port.
+pub(crate) struct MockServer {
+    /// Port the mock server is listening on
+    port: u16,
+}
+
+impl MockServer {
+    /// Start a mock server that calls `handler` for each accepted connection.
+    ///
+    /// The accept loop runs on a detached thread for the rest of the test process.
+    pub(crate) fn start<F>(handler: F) -> Self
+    where
+        F: Fn(TcpStream) + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                handler(stream);
+            }
+        });
+
+        Self { port }
+    }
+
+    /// Start a mock server that answers every request with the given response.
+    pub(crate) fn respond_with(control: u8, content1: &'static [u8], content2: &'static [u8]) -> Self {
+        Self::start(move |mut stream| {
+            if read_request(&mut stream).is_some() {
+                write_response(&mut stream, control, content1, content2);
+            }
+        })
+    }
+
+    /// Get a configuration pointing at the mock server.
+    pub(crate) fn config(&self) -> NeutralIpcConfig {
+        let mut config = NeutralIpcConfig::default();
+        config.set_host("127.0.0.1".to_string());
+        config.set_port(self.port);
+        config
+    }
+}
+
+/// Read a complete v0 request record from the stream.
+///
+/// Returns the header and both content blocks, or `None` if the client
+/// disconnects before sending a full record.
+pub(crate) fn read_request(stream: &mut TcpStream) -> Option<(Vec<u8>, Vec<u8>, Vec<u8>)> {
+    let mut header = vec![0u8; HEADER_LEN];
+    stream.read_exact(&mut header).ok()?;
+
+    let length1 = u32::from_be_bytes([header[3], header[4], header[5], header[6]]) as usize;
+    let length2 = u32::from_be_bytes([header[8], header[9], header[10], header[11]]) as usize;
+
+    let mut content1 = vec![0u8; length1];
+    stream.read_exact(&mut content1).ok()?;
+    let mut content2 = vec![0u8; length2];
+    stream.read_exact(&mut content2).ok()?;
+
+    Some((header, content1, content2))
+}
+
+/// Write a v0 response record to the stream.
+pub(crate) fn write_response(stream: &mut TcpStream, control: u8, content1: &[u8], content2: &[u8]) {
+    let response = NeutralIpcRecord::encode_record(
+        control,
+        CONTENT_JSON,
+        content1,
+        CONTENT_TEXT,
+        content2,
+    );
+    let _ = stream.write_all(&response);
+}
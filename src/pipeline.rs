@@ -0,0 +1,318 @@
+//! Request pipelining over a single connection.
+//!
+//! `PipelinedClient` sends a batch of render requests over one TCP connection.
+//! A writer thread sends every request as fast as the socket accepts it while
+//! the caller reads and decodes the responses in order, so the latency of one
+//! round-trip is hidden behind the transfer of the next requests. The server
+//! must keep the connection open and answer requests in the order received.
+
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::thread::{self, JoinHandle};
+
+use crate::client::{connect, ConnectionPermit};
+use crate::config::NeutralIpcConfig;
+use crate::error::{NeutralIpcError, Result};
+use crate::record::NeutralIpcRecord;
+use crate::template::NeutralIpcTemplate;
+
+/// Client sending queued render requests over a single pipelined connection.
+///
+/// Templates are queued with `push` and sent with `run`, which returns an
+/// iterator over the responses in queue order. The protocol has no request
+/// identifiers, so responses are matched to requests by position: a
+/// connection closed before every response was received, or a malformed
+/// response, aborts the iteration with `NeutralIpcError::ProtocolViolation`
+/// or the decoding error.
+///
+/// # Example
+///
+/// ```no_run
+/// use neutralipcrs::{NeutralIpcConfig, NeutralIpcTemplate, PipelinedClient};
+/// use serde_json::json;
+///
+/// let mut client = PipelinedClient::new(NeutralIpcConfig::new());
+/// for id in 0..100 {
+///     let template = NeutralIpcTemplate::from_src_value("{:;id:}", json!({"data": {"id": id}})).unwrap();
+///     client.push(template).unwrap();
+/// }
+///
+/// for response in client.run().unwrap() {
+///     let (_template, content) = response.unwrap();
+///     println!("{}", content);
+/// }
+/// ```
+pub struct PipelinedClient {
+    /// Connection settings for the pipelined connection
+    config: NeutralIpcConfig,
+    /// Queued templates with the control code and encoded record of their request
+    queue: Vec<(NeutralIpcTemplate, u8, Vec<u8>)>,
+}
+
+impl PipelinedClient {
+    /// Create a pipelined client with an empty queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Connection settings used for every queued request
+    pub fn new(config: NeutralIpcConfig) -> Self {
+        Self { config, queue: Vec::new() }
+    }
+
+    /// Queue a template for rendering.
+    ///
+    /// The request is encoded immediately with the client configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request cannot be encoded, e.g. invalid request
+    /// headers or a template path outside the template root.
+    pub fn push(&mut self, template: NeutralIpcTemplate) -> Result<()> {
+        let (control, request) = template.encode_render_request(&self.config)?;
+        self.queue.push((template, control, request));
+        Ok(())
+    }
+
+    /// Get the number of queued requests.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Check whether no request is queued.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Connect, send every queued request, and return the responses in order.
+    ///
+    /// Requests are written from a separate thread; responses are read as the
+    /// returned iterator advances. Each item is the template, holding the
+    /// result of its render, and the rendered content.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection cannot be established, or
+    /// `NeutralIpcError::TooManyConnections` if the `max_concurrent_connections`
+    /// limit is reached and `queue_connections` is disabled. Errors while
+    /// sending or receiving are yielded by the iterator, which then ends.
+    pub fn run(self) -> Result<PipelinedResponses> {
+        let host = self.config.get_host();
+        let port = self.config.get_port();
+        let timeout = Some(self.config.get_timeout_duration());
+
+        let permit = ConnectionPermit::acquire_for(&self.config)?;
+        let stream = connect(&self.config, &host, port, None)?;
+        stream.set_read_timeout(timeout)?;
+        stream.set_write_timeout(timeout)?;
+
+        let mut pending = VecDeque::with_capacity(self.queue.len());
+        let mut requests = Vec::with_capacity(self.queue.len());
+        for (template, control, request) in self.queue {
+            pending.push_back((template, control));
+            requests.push(request);
+        }
+
+        let mut writer_stream = stream.try_clone()?;
+        let write_buffer_size = self.config.get_write_buffer_size();
+        let writer = thread::spawn(move || -> io::Result<()> {
+            for request in requests {
+                for chunk in request.chunks(write_buffer_size) {
+                    writer_stream.write_all(chunk)?;
+                }
+            }
+            writer_stream.flush()
+        });
+
+        Ok(PipelinedResponses {
+            stream,
+            permit,
+            lossy_utf8: self.config.get_lossy_utf8(),
+            total: pending.len(),
+            pending,
+            writer: Some(writer),
+        })
+    }
+}
+
+/// Iterator over the responses of a `PipelinedClient` run, in request order.
+///
+/// Dropping the iterator closes the connection.
+pub struct PipelinedResponses {
+    /// Connection the responses are read from
+    stream: TcpStream,
+    /// Slot of the connection limit, released with the connection
+    permit: Option<ConnectionPermit>,
+    /// Whether invalid UTF-8 in rendered content is replaced
+    lossy_utf8: bool,
+    /// Number of requests sent
+    total: usize,
+    /// Templates still waiting for their response, with their request control code
+    pending: VecDeque<(NeutralIpcTemplate, u8)>,
+    /// Writer thread, joined once all responses are read or on error
+    writer: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl PipelinedResponses {
+    /// Read and decode the next response record.
+    fn read_response(&mut self) -> Result<HashMap<String, Value>> {
+        let received = self.total - self.pending.len();
+        let closed = |err: io::Error| match err.kind() {
+            io::ErrorKind::UnexpectedEof | io::ErrorKind::ConnectionReset => NeutralIpcError::ProtocolViolation(format!(
+                "connection closed after {} of {} responses",
+                received, self.total
+            )),
+            _ => NeutralIpcError::Io(err),
+        };
+
+        let header = NeutralIpcRecord::read_header(&mut self.stream).map_err(|err| match err {
+            NeutralIpcError::Io(err) => closed(err),
+            err => err,
+        })?;
+        let decoded = NeutralIpcRecord::decode_header(&header)?;
+
+        let mut content1 = vec![0u8; decoded.length1 as usize];
+        self.stream.read_exact(&mut content1).map_err(closed)?;
+        let mut content2 = vec![0u8; decoded.length2 as usize];
+        self.stream.read_exact(&mut content2).map_err(closed)?;
+
+        if self.lossy_utf8 {
+            NeutralIpcRecord::decode_record_lossy(&header, &content1, &content2)
+        } else {
+            NeutralIpcRecord::decode_record(&header, &content1, &content2)
+        }
+    }
+
+    /// Stop the iteration, closing the connection and reporting a writer failure if any.
+    fn finish(&mut self) -> Option<Result<(NeutralIpcTemplate, String)>> {
+        self.pending.clear();
+        let _ = self.stream.shutdown(Shutdown::Both);
+        self.permit = None;
+        match self.writer.take().map(|writer| writer.join()) {
+            Some(Ok(Err(err))) => Some(Err(NeutralIpcError::Io(err))),
+            _ => None,
+        }
+    }
+}
+
+impl Iterator for PipelinedResponses {
+    type Item = Result<(NeutralIpcTemplate, String)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pending.is_empty() {
+            return self.finish();
+        }
+
+        let record = match self.read_response() {
+            Ok(record) => record,
+            Err(err) => {
+                self.finish();
+                return Some(Err(err));
+            }
+        };
+
+        let (mut template, control) = self.pending.pop_front()?;
+        match template.store_render_response(control, &record) {
+            Ok(content) => Some(Ok((template, content))),
+            Err(err) => {
+                self.finish();
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl Drop for PipelinedResponses {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+    use crate::mock::{read_request, write_response, MockServer, CONNECTION_LIMIT};
+    use serde_json::json;
+    use std::sync::PoisonError;
+
+    /// Mock server answering every request on a connection with the template source,
+    /// stopping after `limit` responses.
+    fn pipelined_echo_server(limit: usize) -> MockServer {
+        MockServer::start(move |mut stream| {
+            let mut answered = 0;
+            while answered < limit {
+                match read_request(&mut stream) {
+                    Some((_, _, content2)) => write_response(&mut stream, CTRL_STATUS_OK, b"{}", &content2),
+                    None => return,
+                }
+                answered += 1;
+            }
+            // Close our side, then drain the remaining requests so the close is not a reset.
+            let _ = stream.shutdown(Shutdown::Write);
+            let _ = io::copy(&mut stream, &mut io::sink());
+        })
+    }
+
+    fn queue(server: &MockServer, count: usize) -> PipelinedClient {
+        let mut client = PipelinedClient::new(server.config());
+        for id in 0..count {
+            let template = NeutralIpcTemplate::from_src_value(&format!("render {}", id), json!({})).unwrap();
+            client.push(template).unwrap();
+        }
+        client
+    }
+
+    #[test]
+    fn test_pipelined_renders_in_order() {
+        let server = pipelined_echo_server(usize::MAX);
+        let client = queue(&server, 100);
+        assert_eq!(client.len(), 100);
+
+        let contents: Vec<String> = client.run().unwrap().map(|response| response.unwrap().1).collect();
+        let expected: Vec<String> = (0..100).map(|id| format!("render {}", id)).collect();
+        assert_eq!(contents, expected);
+    }
+
+    #[test]
+    fn test_pipelined_missing_responses_abort() {
+        let server = pipelined_echo_server(50);
+        let mut responses = queue(&server, 100).run().unwrap();
+
+        for id in 0..50 {
+            let (template, content) = responses.next().unwrap().unwrap();
+            assert_eq!(content, format!("render {}", id));
+            assert_eq!(template.source(), Some(content.as_str()));
+        }
+        match responses.next() {
+            Some(Err(NeutralIpcError::ProtocolViolation(reason))) => {
+                assert_eq!(reason, "connection closed after 50 of 100 responses")
+            }
+            other => panic!("expected ProtocolViolation, got {:?}", other.map(|r| r.map(|(_, c)| c))),
+        }
+        assert!(responses.next().is_none());
+    }
+
+    #[test]
+    fn test_pipelined_connection_takes_a_slot_of_the_limit() {
+        let _limit = CONNECTION_LIMIT.lock().unwrap_or_else(PoisonError::into_inner);
+        let server = pipelined_echo_server(usize::MAX);
+        let mut config = server.config();
+        config.set_max_concurrent_connections(Some(1));
+        config.set_queue_connections(false);
+
+        let mut held = PipelinedClient::new(config.clone());
+        held.push(NeutralIpcTemplate::from_src_value("held", json!({})).unwrap()).unwrap();
+        let responses = held.run().unwrap();
+
+        let mut client = PipelinedClient::new(config.clone());
+        client.push(NeutralIpcTemplate::from_src_value("render", json!({})).unwrap()).unwrap();
+        assert!(matches!(client.run(), Err(NeutralIpcError::TooManyConnections { limit: 1 })));
+
+        assert_eq!(responses.map(|response| response.unwrap().1).collect::<Vec<_>>(), ["held"]);
+        let mut client = PipelinedClient::new(config);
+        client.push(NeutralIpcTemplate::from_src_value("render", json!({})).unwrap()).unwrap();
+        assert!(client.run().is_ok());
+    }
+}
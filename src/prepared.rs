@@ -0,0 +1,248 @@
+//! Render requests encoded once and sent repeatedly.
+//!
+//! `NeutralIpcTemplate::prepare` encodes the request record of a template a
+//! single time. The resulting `PreparedRequest` shares the encoded record, so
+//! clones are cheap and every send writes the same bytes without touching the
+//! template again, e.g. for fragments rendered identically on every page.
+//!
+//! `NeutralIpcTemplate::dry_run` encodes the same record without sending it,
+//! and returns it as a `DryRun` split into its header and content blocks.
+
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::client::NeutralIpcClient;
+use crate::config::NeutralIpcConfig;
+use crate::constants::*;
+use crate::error::{NeutralIpcError, Result};
+use crate::record::{NeutralIpcRecord, RecordHeader};
+use crate::template::{NeutralIpcTemplate, ServerDiagnostics};
+
+/// A render request encoded once, sent any number of times.
+///
+/// Created with `NeutralIpcTemplate::prepare`. Cloning shares the encoded
+/// record instead of copying it.
+#[derive(Debug, Clone)]
+pub struct PreparedRequest {
+    /// Control code of the request
+    control: u8,
+    /// Encoded request record, header and contents
+    request: Arc<[u8]>,
+    /// Template the request was prepared from, as named in render errors
+    template: String,
+    /// Size of the serialized schema in bytes
+    schema_size: usize,
+}
+
+/// Result of sending a `PreparedRequest`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderResult {
+    /// Rendered content
+    pub content: String,
+    /// Result data sent by the server in `content-1`, e.g. the status code
+    pub result: Value,
+}
+
+impl RenderResult {
+    /// Get the status code of the render, or an empty string if the result has none.
+    pub fn status_code(&self) -> &str {
+        self.result.get("status_code").and_then(|v| v.as_str()).unwrap_or("")
+    }
+}
+
+/// A render request encoded without being sent.
+///
+/// Created with `NeutralIpcTemplate::dry_run`, for tests asserting the exact
+/// bytes a template sends to the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DryRun {
+    /// Decoded header of the record
+    pub header: RecordHeader,
+    /// Encoded header, with the checksum of v1 records
+    pub header_bytes: Vec<u8>,
+    /// First content block, the schema as sent
+    pub content1: Vec<u8>,
+    /// Second content block, the template as sent
+    pub content2: Vec<u8>,
+}
+
+impl DryRun {
+    /// Split an encoded request record into its header and content blocks.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::ProtocolViolation` if the contents do not
+    /// have the lengths declared in the header.
+    pub(crate) fn from_record(record: &[u8]) -> Result<Self> {
+        let mut contents = record;
+        let header = NeutralIpcRecord::decode_header_from_reader(&mut contents)?;
+        let header_bytes = record[..record.len() - contents.len()].to_vec();
+
+        let (length1, length2) = (header.length1 as usize, header.length2 as usize);
+        if contents.len() != length1 + length2 {
+            return Err(NeutralIpcError::ProtocolViolation(format!(
+                "record has {} content bytes but the header declares {}",
+                contents.len(),
+                length1 + length2
+            )));
+        }
+        let (content1, content2) = contents.split_at(length1);
+        Ok(Self { header, header_bytes, content1: content1.to_vec(), content2: content2.to_vec() })
+    }
+
+    /// Get the complete record, as it would be written to the connection.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.header_bytes.as_slice(), &self.content1, &self.content2].concat()
+    }
+}
+
+impl PreparedRequest {
+    /// Wrap an encoded request record.
+    pub(crate) fn new(control: u8, request: Vec<u8>, template: String, schema_size: usize) -> Self {
+        Self { control, request: request.into(), template, schema_size }
+    }
+
+    /// Get the size of the encoded request record in bytes.
+    pub fn len(&self) -> usize {
+        self.request.len()
+    }
+
+    /// Check whether the encoded request record is empty; never true for a prepared request.
+    pub fn is_empty(&self) -> bool {
+        self.request.is_empty()
+    }
+
+    /// Send the request and read the rendered result.
+    ///
+    /// Connects with the host, port, timeouts and buffer sizes of `config`.
+    /// Settings that shape the request itself (template root, compression,
+    /// checksum) were applied by `prepare` and are not read again. The output
+    /// options of the template are not applied, and renders are neither
+    /// retried when rate-limited nor negotiated by hash.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Connection settings of the server
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::Render`, naming the template, wrapping the
+    /// error of the exchange with the server, or `NeutralIpcError::ServerError`
+    /// if the server answers with `CTRL_STATUS_KO`.
+    pub fn send(&self, config: &NeutralIpcConfig) -> Result<RenderResult> {
+        let mut client = NeutralIpcClient::with_encoded_request(config.clone(), self.control, Arc::clone(&self.request));
+        let (mut result, content) = client
+            .start()
+            .and_then(|record| NeutralIpcTemplate::rendered_result(self.control, record))
+            .map_err(|err| self.render_error(err))?;
+
+        if result.get("status").and_then(|v| v.as_u64()) == Some(CTRL_STATUS_KO as u64) {
+            let raw = result.get("diagnostics").and_then(|v| v.as_str()).unwrap_or("");
+            let diagnostics = ServerDiagnostics::parse(raw);
+            let message = if diagnostics.message.is_empty() { "no diagnostics".to_string() } else { diagnostics.message };
+            return Err(self.render_error(NeutralIpcError::ServerError(message)));
+        }
+
+        Ok(RenderResult { content, result: result.remove("result").unwrap_or(Value::Null) })
+    }
+
+    /// Wrap an error of the exchange with the server with the template it was prepared from.
+    fn render_error(&self, err: NeutralIpcError) -> NeutralIpcError {
+        NeutralIpcError::Render { template: self.template.clone(), schema_size: self.schema_size, source: Box::new(err) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SchemaSizePolicy;
+    use crate::mock::{read_request, write_response, MockServer};
+    use crate::record::ContentFormat;
+    use serde_json::json;
+
+    #[test]
+    fn test_prepared_request_sends_identical_records() {
+        let (requests, received) = std::sync::mpsc::channel();
+        let server = MockServer::start(move |mut stream| {
+            if let Some((header, content1, content2)) = read_request(&mut stream) {
+                write_response(&mut stream, CTRL_STATUS_OK, br#"{"status_code":"200"}"#, &content2);
+                requests.send([header, content1, content2].concat()).unwrap();
+            }
+        });
+
+        let template = NeutralIpcTemplate::from_src_value("footer", json!({"data": {"year": 2026}}))
+            .unwrap()
+            .with_config(server.config());
+        let prepared = template.prepare().unwrap();
+        let expected = template.encode_render_request(&server.config()).unwrap().1;
+        drop(template);
+
+        let first = prepared.send(&server.config()).unwrap();
+        let second = prepared.clone().send(&server.config()).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first.content, "footer");
+        assert_eq!(first.status_code(), "200");
+        assert_eq!(received.recv().unwrap(), expected);
+        assert_eq!(received.recv().unwrap(), expected);
+        assert_eq!(prepared.len(), expected.len());
+    }
+
+    #[test]
+    fn test_dry_run_captures_the_request() {
+        let template = NeutralIpcTemplate::from_src_value("{:;title:}", json!({"data": {"title": "Home"}}))
+            .unwrap()
+            .with_config(NeutralIpcConfig::without_file());
+        let dry_run = template.dry_run().unwrap();
+
+        assert_eq!(dry_run.header.reserved, RESERVED);
+        assert_eq!(dry_run.header.control, CTRL_PARSE_TEMPLATE);
+        assert_eq!(dry_run.header.format1, ContentFormat::Json);
+        assert_eq!(dry_run.header.format2, ContentFormat::Text);
+        assert_eq!(dry_run.header.length1 as usize, dry_run.content1.len());
+        assert_eq!(dry_run.header_bytes, dry_run.header.encode());
+        assert_eq!(dry_run.content1, br#"{"data":{"title":"Home"}}"#);
+        assert_eq!(dry_run.content2, b"{:;title:}");
+        assert_eq!(dry_run.to_bytes(), template.encode_render_request(&NeutralIpcConfig::without_file()).unwrap().1);
+
+        let template = template.with_config(NeutralIpcConfig::without_file().with_checksum(true));
+        let dry_run = template.dry_run().unwrap();
+        assert_eq!(dry_run.header.reserved, PROTOCOL_V1);
+        assert_eq!(dry_run.header_bytes.len(), HEADER_LEN_V1);
+
+        let template = template.with_config(NeutralIpcConfig::without_file().with_max_schema_size(8));
+        assert!(matches!(template.dry_run(), Err(NeutralIpcError::SchemaTooLarge { limit: 8, .. })));
+    }
+
+    #[test]
+    fn test_requests_encoded_in_advance_apply_the_schema_budget() {
+        let template = NeutralIpcTemplate::from_src_value("{:;title:}", json!({"data": {"title": "Home"}}))
+            .unwrap()
+            .with_config(
+                NeutralIpcConfig::without_file().with_max_schema_size(8).with_schema_size_policy(SchemaSizePolicy::Warn),
+            );
+        assert!(template.dry_run().is_ok());
+        assert!(template.prepare().is_ok());
+
+        let template = template.with_config(NeutralIpcConfig::without_file().with_max_schema_size(8));
+        assert!(matches!(template.dry_run(), Err(NeutralIpcError::SchemaTooLarge { limit: 8, .. })));
+        assert!(matches!(template.prepare(), Err(NeutralIpcError::SchemaTooLarge { limit: 8, .. })));
+    }
+
+    #[test]
+    fn test_prepared_request_errors() {
+        let server = MockServer::respond_with(CTRL_STATUS_KO, br#"{"message":"unknown tag"}"#, b"");
+        let prepared = NeutralIpcTemplate::from_file_value("page.ntpl", json!({}))
+            .unwrap()
+            .with_config(server.config())
+            .prepare()
+            .unwrap();
+
+        match prepared.send(&server.config()) {
+            Err(NeutralIpcError::Render { template, source, .. }) => {
+                assert_eq!(template, "page.ntpl");
+                assert!(matches!(*source, NeutralIpcError::ServerError(ref message) if message == "unknown tag"));
+            }
+            other => panic!("expected Render, got {:?}", other),
+        }
+    }
+}
@@ -12,19 +12,253 @@
 //
 // Byte 0: \x00                  // reserved
 // Byte 1: \x00                  // control (action/status) (10 = parse template)
-// Byte 2: \x00                  // content-format 1 (10 = JSON, 20 = file path, 30 = plaintext, 40 = binary, 50 = MsgPack)
+// Byte 2: \x00                  // content-format 1 (10 = JSON, 20 = file path, 30 = plaintext, 40 = binary, 50 = MsgPack, 60 = base64)
 // Bytes 3-6: \x00\x00\x00\x00   // content-length 1 big endian byte order
-// Byte 7: \x00                  // content-format 2 (10 = JSON, 20 = file path, 30 = plaintext, 40 = binary, 50 = MsgPack)
+// Byte 7: \x00                  // content-format 2 (10 = JSON, 20 = file path, 30 = plaintext, 40 = binary, 50 = MsgPack, 60 = base64)
 // Bytes 8-11: \x00\x00\x00\x00  // content-length 2 big endian byte order (can be zero)
 //
 // All text content must be UTF-8 encoded.
+//
+// ============================================
+// Neutral IPC record version 1 (checksum)
+// ============================================
+//
+// Same as version 0 with the reserved byte set to 1 and four extra header bytes:
+//
+// Bytes 12-15: \x00\x00\x00\x00 // CRC32 of content 1 followed by content 2, big endian byte order
 
 use serde_json::Value;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::io::Read;
+
+#[cfg(any(feature = "async-tokio", feature = "async-std"))]
+use futures_io::AsyncRead;
+#[cfg(any(feature = "async-tokio", feature = "async-std"))]
+use futures_lite::io::AsyncReadExt;
 
 use crate::constants::*;
 use crate::error::{NeutralIpcError, Result};
 
+/// Format identifier of a content block in a record header.
+///
+/// Identifiers not defined by the protocol are kept as `Other` so that a
+/// header always encodes back to the bytes it was decoded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContentFormat {
+    /// JSON document (`CONTENT_JSON`)
+    Json,
+    /// Gzip-compressed JSON document (`CONTENT_JSON_GZ`)
+    JsonGzip,
+    /// Zlib-compressed JSON document (`CONTENT_JSON_ZLIB`)
+    JsonZlib,
+    /// File path (`CONTENT_PATH`)
+    Path,
+    /// Plain text (`CONTENT_TEXT`)
+    Text,
+    /// Gzip-compressed text (`CONTENT_TEXT_GZ`)
+    TextGzip,
+    /// Zlib-compressed text (`CONTENT_TEXT_ZLIB`)
+    TextZlib,
+    /// Binary data (`CONTENT_BIN`)
+    Bin,
+    /// MsgPack document (`CONTENT_MSGPACK`)
+    MsgPack,
+    /// Base64-encoded text (`CONTENT_BASE64`)
+    Base64,
+    /// Any other format identifier
+    Other(u8),
+}
+
+impl From<u8> for ContentFormat {
+    fn from(format: u8) -> Self {
+        match format {
+            CONTENT_JSON => ContentFormat::Json,
+            CONTENT_JSON_GZ => ContentFormat::JsonGzip,
+            CONTENT_JSON_ZLIB => ContentFormat::JsonZlib,
+            CONTENT_PATH => ContentFormat::Path,
+            CONTENT_TEXT => ContentFormat::Text,
+            CONTENT_TEXT_GZ => ContentFormat::TextGzip,
+            CONTENT_TEXT_ZLIB => ContentFormat::TextZlib,
+            CONTENT_BIN => ContentFormat::Bin,
+            CONTENT_MSGPACK => ContentFormat::MsgPack,
+            CONTENT_BASE64 => ContentFormat::Base64,
+            other => ContentFormat::Other(other),
+        }
+    }
+}
+
+impl From<ContentFormat> for u8 {
+    fn from(format: ContentFormat) -> Self {
+        match format {
+            ContentFormat::Json => CONTENT_JSON,
+            ContentFormat::JsonGzip => CONTENT_JSON_GZ,
+            ContentFormat::JsonZlib => CONTENT_JSON_ZLIB,
+            ContentFormat::Path => CONTENT_PATH,
+            ContentFormat::Text => CONTENT_TEXT,
+            ContentFormat::TextGzip => CONTENT_TEXT_GZ,
+            ContentFormat::TextZlib => CONTENT_TEXT_ZLIB,
+            ContentFormat::Bin => CONTENT_BIN,
+            ContentFormat::MsgPack => CONTENT_MSGPACK,
+            ContentFormat::Base64 => CONTENT_BASE64,
+            ContentFormat::Other(other) => other,
+        }
+    }
+}
+
+impl ContentFormat {
+    /// Check whether blocks of this format are read in full and decompressed
+    /// when decoded (`compression` feature), instead of being streamed.
+    pub(crate) fn is_compressed(self) -> bool {
+        matches!(
+            self,
+            ContentFormat::Bin
+                | ContentFormat::JsonGzip
+                | ContentFormat::JsonZlib
+                | ContentFormat::TextGzip
+                | ContentFormat::TextZlib
+        )
+    }
+}
+
+/// Fixed `HEADER_LEN`-byte header of a Neutral IPC record.
+///
+/// The v1 checksum that may follow the fixed header is not part of this struct.
+///
+/// # Example
+///
+/// ```
+/// use neutralipcrs::{ContentFormat, RecordHeader, CTRL_PARSE_TEMPLATE, RESERVED};
+///
+/// let header = RecordHeader {
+///     reserved: RESERVED,
+///     control: CTRL_PARSE_TEMPLATE,
+///     format1: ContentFormat::Json,
+///     length1: 2,
+///     format2: ContentFormat::Text,
+///     length2: 5,
+/// };
+///
+/// let bytes = header.encode();
+/// assert_eq!(RecordHeader::decode(&bytes).unwrap(), header);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordHeader {
+    /// Reserved byte: `RESERVED` for v0 records, `PROTOCOL_V1` for records with checksum
+    pub reserved: u8,
+    /// Control code (action or status)
+    pub control: u8,
+    /// Format of the first content block
+    pub format1: ContentFormat,
+    /// Length of the first content block in bytes
+    pub length1: u32,
+    /// Format of the second content block
+    pub format2: ContentFormat,
+    /// Length of the second content block in bytes
+    pub length2: u32,
+}
+
+impl RecordHeader {
+    /// Decode a record header from its wire representation.
+    ///
+    /// Decodes the `HEADER_LEN` bytes shared by v0 and v1 headers; the
+    /// checksum following a v1 header is not part of it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::UnsupportedProtocolVersion` if the reserved
+    /// byte is neither `RESERVED` nor `PROTOCOL_V1`.
+    pub fn decode(bytes: &[u8; HEADER_LEN]) -> Result<Self> {
+        let reserved = bytes[0];
+        if reserved != RESERVED && reserved != PROTOCOL_V1 {
+            return Err(NeutralIpcError::UnsupportedProtocolVersion(reserved));
+        }
+
+        Ok(Self {
+            reserved,
+            control: bytes[1],
+            format1: bytes[2].into(),
+            length1: u32::from_be_bytes([bytes[3], bytes[4], bytes[5], bytes[6]]),
+            format2: bytes[7].into(),
+            length2: u32::from_be_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]),
+        })
+    }
+
+    /// Encode the record header into its wire representation.
+    pub fn encode(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0u8; HEADER_LEN];
+        bytes[0] = self.reserved;
+        bytes[1] = self.control;
+        bytes[2] = self.format1.into();
+        bytes[3..7].copy_from_slice(&self.length1.to_be_bytes());
+        bytes[7] = self.format2.into();
+        bytes[8..12].copy_from_slice(&self.length2.to_be_bytes());
+        bytes
+    }
+}
+
+/// Decoded IPC record with its content blocks kept as raw bytes.
+///
+/// Unlike the decoded record map, the contents are not converted to UTF-8,
+/// so binary payloads survive intact. Compressed `CONTENT_BIN` blocks are
+/// already decompressed (`compression` feature). Records read from the wire
+/// are decoded with `NeutralIpcRawRecord::decode`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NeutralIpcRawRecord {
+    /// Header as received; the lengths are those of the contents on the wire
+    pub header: RecordHeader,
+    /// First content block
+    pub content1: Vec<u8>,
+    /// Second content block
+    pub content2: Vec<u8>,
+}
+
+impl NeutralIpcRawRecord {
+    /// Decode a complete IPC record read from the wire, keeping the contents as bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The `HEADER_LEN` or `HEADER_LEN_V1` bytes of the header
+    /// * `content1` - The first content block
+    /// * `content2` - The second content block
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header is invalid, a content length differs
+    /// from the header, the checksum does not match, or a compressed block
+    /// cannot be decompressed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::{ContentFormat, NeutralIpcRawRecord, RecordHeader, CTRL_STATUS_OK, RESERVED};
+    ///
+    /// let header = RecordHeader {
+    ///     reserved: RESERVED,
+    ///     control: CTRL_STATUS_OK,
+    ///     format1: ContentFormat::Json,
+    ///     length1: 2,
+    ///     format2: ContentFormat::Bin,
+    ///     length2: 2,
+    /// };
+    /// let record = NeutralIpcRawRecord::decode(&header.encode(), b"{}".to_vec(), vec![0xff, 0x00]).unwrap();
+    /// assert_eq!(record.content2, [0xff, 0x00]);
+    /// ```
+    pub fn decode(header: &[u8], content1: Vec<u8>, content2: Vec<u8>) -> Result<Self> {
+        NeutralIpcRecord::decode_record_bytes(header, content1, content2)
+    }
+
+    /// Get the first content block as text, replacing invalid UTF-8 with U+FFFD.
+    pub fn content1_as_string_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.content1)
+    }
+
+    /// Get the second content block as text, replacing invalid UTF-8 with U+FFFD.
+    pub fn content2_as_string_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.content2)
+    }
+}
+
 /// Neutral IPC record for encoding/decoding protocol messages.
 ///
 /// This struct provides static methods for working with Neutral IPC protocol records.
@@ -33,51 +267,97 @@ use crate::error::{NeutralIpcError, Result};
 pub(crate) struct NeutralIpcRecord;
 
 impl NeutralIpcRecord {
-    /// Decode an IPC record header into a structured format.
+    /// Decode the fixed part of a v0 or v1 IPC record header.
     ///
     /// # Arguments
     ///
-    /// * `record_header` - A byte slice containing exactly `HEADER_LEN` bytes
+    /// * `record_header` - A byte slice containing exactly `HEADER_LEN` bytes,
+    ///   or `HEADER_LEN_V1` bytes for a record with checksum
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::InvalidHeaderLength` if the header length is incorrect,
+    /// or `NeutralIpcError::UnsupportedProtocolVersion` for an unknown reserved byte.
+    pub(crate) fn decode_header(record_header: &[u8]) -> Result<RecordHeader> {
+        if record_header.len() != HEADER_LEN && record_header.len() != HEADER_LEN_V1 {
+            return Err(NeutralIpcError::InvalidHeaderLength);
+        }
+        let mut bytes = [0u8; HEADER_LEN];
+        bytes.copy_from_slice(&record_header[..HEADER_LEN]);
+        RecordHeader::decode(&bytes)
+    }
+
+    /// Read and decode an IPC record header from a reader.
+    ///
+    /// Reads exactly `HEADER_LEN` bytes, or `HEADER_LEN_V1` bytes when the
+    /// reserved byte announces a record with checksum, and decodes them like
+    /// `decode_header`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The reader positioned at the start of a record
     ///
     /// # Returns
     ///
-    /// A `HashMap` containing the decoded header fields:
-    /// - `reserved`: Reserved field read from the header
-    /// - `control`: Control code for the operation
-    /// - `format-1`: Format identifier for the first content block
-    /// - `length-1`: Length of the first content block in bytes
-    /// - `format-2`: Format identifier for the second content block
-    /// - `length-2`: Length of the second content block in bytes
+    /// The decoded header (see `decode_header`).
     ///
     /// # Errors
     ///
-    /// Returns `NeutralIpcError::InvalidHeaderLength` if the header length is incorrect.
-    pub(crate) fn decode_header(record_header: &[u8]) -> Result<HashMap<String, Value>> {
-        if record_header.len() != HEADER_LEN {
-            return Err(NeutralIpcError::InvalidHeaderLength);
-        }
+    /// Returns `NeutralIpcError::Io` if the reader fails or ends before the
+    /// complete header has been read.
+    pub(crate) fn decode_header_from_reader<R: Read>(reader: &mut R) -> Result<RecordHeader> {
+        Self::decode_header(&Self::read_header(reader)?)
+    }
 
-        let reserved = record_header[0];
-        let control = record_header[1];
-        let format1 = record_header[2];
+    /// Read and decode an IPC record header from an async reader.
+    ///
+    /// Same as `decode_header_from_reader`, but also returns the raw header
+    /// bytes, which `decode_record` needs to verify the checksum of v1 records.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The async reader positioned at the start of a record
+    ///
+    /// # Returns
+    ///
+    /// The decoded header (see `decode_header`) and its raw bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::Io` if the reader fails or ends before the
+    /// complete header has been read.
+    #[cfg(any(feature = "async-tokio", feature = "async-std"))]
+    pub(crate) async fn decode_header_from_async_reader<R: AsyncRead + Unpin>(
+        reader: &mut R,
+    ) -> Result<(RecordHeader, Vec<u8>)> {
+        let mut header = vec![0u8; HEADER_LEN];
+        reader.read_exact(&mut header).await?;
 
-        let length1 = u32::from_be_bytes([
-            record_header[3], record_header[4], record_header[5], record_header[6]
-        ]);
+        if header[0] == PROTOCOL_V1 {
+            header.resize(HEADER_LEN_V1, 0);
+            reader.read_exact(&mut header[HEADER_LEN..]).await?;
+        }
 
-        let format2 = record_header[7];
+        Ok((Self::decode_header(&header)?, header))
+    }
 
-        let length2 = u32::from_be_bytes([
-            record_header[8], record_header[9], record_header[10], record_header[11]
-        ]);
+    /// Read the raw bytes of an IPC record header from a reader.
+    ///
+    /// Reads `HEADER_LEN` bytes, followed by the `CHECKSUM_LEN` checksum bytes
+    /// when the reserved byte is `PROTOCOL_V1`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::Io` if the reader fails or ends before the
+    /// complete header has been read.
+    pub(crate) fn read_header<R: Read>(reader: &mut R) -> Result<Vec<u8>> {
+        let mut header = vec![0u8; HEADER_LEN];
+        reader.read_exact(&mut header)?;
 
-        let mut header = HashMap::new();
-        header.insert("reserved".to_string(), Value::Number(reserved.into()));
-        header.insert("control".to_string(), Value::Number(control.into()));
-        header.insert("format-1".to_string(), Value::Number(format1.into()));
-        header.insert("length-1".to_string(), Value::Number(length1.into()));
-        header.insert("format-2".to_string(), Value::Number(format2.into()));
-        header.insert("length-2".to_string(), Value::Number(length2.into()));
+        if header[0] == PROTOCOL_V1 {
+            header.resize(HEADER_LEN_V1, 0);
+            reader.read_exact(&mut header[HEADER_LEN..])?;
+        }
 
         Ok(header)
     }
@@ -96,14 +376,16 @@ impl NeutralIpcRecord {
     ///
     /// A `Vec<u8>` containing the encoded header with exactly `HEADER_LEN` bytes.
     pub(crate) fn encode_header(control: u8, format1: u8, length1: u32, format2: u8, length2: u32) -> Vec<u8> {
-        let mut header = Vec::with_capacity(HEADER_LEN);
-        header.push(RESERVED);
-        header.push(control);
-        header.push(format1);
-        header.extend_from_slice(&length1.to_be_bytes());
-        header.push(format2);
-        header.extend_from_slice(&length2.to_be_bytes());
-        header
+        RecordHeader {
+            reserved: RESERVED,
+            control,
+            format1: format1.into(),
+            length1,
+            format2: format2.into(),
+            length2,
+        }
+        .encode()
+        .to_vec()
     }
 
     /// Encode a complete IPC record with header and content.
@@ -131,13 +413,120 @@ impl NeutralIpcRecord {
         record
     }
 
+    /// Encode a complete protocol v1 IPC record with a CRC32 checksum.
+    ///
+    /// The header has `HEADER_LEN_V1` bytes: the reserved byte is set to
+    /// `PROTOCOL_V1` and the CRC32 of both content blocks is appended.
+    ///
+    /// # Arguments
+    ///
+    /// * `control` - Control code for the operation
+    /// * `format1` - Format identifier for the first content block
+    /// * `content1` - Content for the first block as bytes
+    /// * `format2` - Format identifier for the second content block
+    /// * `content2` - Content for the second block as bytes
+    ///
+    /// # Returns
+    ///
+    /// A `Vec<u8>` containing the complete record with checksum header and both content blocks.
+    pub(crate) fn encode_record_checksum(control: u8, format1: u8, content1: &[u8], format2: u8, content2: &[u8]) -> Vec<u8> {
+        let mut record = Self::encode_header(control, format1, content1.len() as u32, format2, content2.len() as u32);
+        record[0] = PROTOCOL_V1;
+        record.extend_from_slice(&Self::checksum(content1, content2).to_be_bytes());
+        record.extend_from_slice(content1);
+        record.extend_from_slice(content2);
+        record
+    }
+
+    /// Prefix a content block with header lines for `CTRL_PARSE_TEMPLATE_WITH_HEADERS`.
+    ///
+    /// Each header is written as a `key: value\n` line and the list is
+    /// terminated by a zero byte, followed by `content`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::InvalidRequestHeader` if a key is empty or
+    /// contains `:`, or if a key or value contains a line break or zero byte.
+    pub(crate) fn encode_headers(headers: &[(String, String)], content: &[u8]) -> Result<Vec<u8>> {
+        let mut encoded = Vec::new();
+        for (key, value) in headers {
+            if key.is_empty() || key.contains(':') {
+                return Err(NeutralIpcError::InvalidRequestHeader(format!("invalid name '{}'", key)));
+            }
+            if [key, value].iter().any(|s| s.contains(['\n', '\r', '\0'])) {
+                return Err(NeutralIpcError::InvalidRequestHeader(format!(
+                    "'{}' contains a line break or zero byte",
+                    key
+                )));
+            }
+            encoded.extend_from_slice(key.as_bytes());
+            encoded.extend_from_slice(b": ");
+            encoded.extend_from_slice(value.as_bytes());
+            encoded.push(b'\n');
+        }
+        encoded.push(0);
+        encoded.extend_from_slice(content);
+        Ok(encoded)
+    }
+
+    /// Split the header lines from a content block with a header prefix.
+    ///
+    /// Content without a zero byte has no header prefix and is returned whole
+    /// with an empty header list.
+    ///
+    /// # Returns
+    ///
+    /// The headers in order and the remaining content.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::ProtocolViolation` if a header line has no `:`.
+    pub(crate) fn split_headers(content: &str) -> Result<(Vec<(String, String)>, &str)> {
+        let (lines, rest) = match content.split_once('\0') {
+            Some(split) => split,
+            None => return Ok((Vec::new(), content)),
+        };
+
+        let headers = lines
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| match line.split_once(':') {
+                Some((key, value)) => Ok((key.trim().to_string(), value.trim().to_string())),
+                None => Err(NeutralIpcError::ProtocolViolation(format!("malformed header line '{}'", line))),
+            })
+            .collect::<Result<_>>()?;
+
+        Ok((headers, rest))
+    }
+
+    /// Compute the CRC32 checksum of both content blocks.
+    pub(crate) fn checksum(content1: &[u8], content2: &[u8]) -> u32 {
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(content1);
+        hasher.update(content2);
+        hasher.finalize()
+    }
+
+    /// Read the checksum from a v1 header, if present.
+    pub(crate) fn header_checksum(header: &[u8]) -> Option<u32> {
+        match header.get(HEADER_LEN..HEADER_LEN_V1) {
+            Some(&[a, b, c, d]) => Some(u32::from_be_bytes([a, b, c, d])),
+            _ => None,
+        }
+    }
+
     /// Decode a complete IPC record from header and content components.
     ///
+    /// If the header is a v1 header, the checksum is verified against the raw
+    /// content blocks. With the `compression` feature enabled, content blocks
+    /// using the `CONTENT_BIN` format are decompressed when they carry a gzip
+    /// or zlib header.
+    ///
     /// # Arguments
     ///
     /// * `header` - The record header bytes
-    /// * `content1` - The first content block as a string
-    /// * `content2` - The second content block as a string
+    /// * `content1` - The first content block as raw bytes
+    /// * `content2` - The second content block as raw bytes
     ///
     /// # Returns
     ///
@@ -151,27 +540,227 @@ impl NeutralIpcRecord {
     ///
     /// # Errors
     ///
-    /// Returns `NeutralIpcError::InvalidHeaderLength` if the header length is incorrect.
-    pub(crate) fn decode_record(header: &[u8], content1: &str, content2: &str) -> Result<HashMap<String, Value>> {
-        let _header_map = Self::decode_header(header)?;
+    /// Returns an error if:
+    /// - The header length is incorrect (`NeutralIpcError::InvalidHeaderLength`)
+    /// - The reserved byte is unknown (`NeutralIpcError::UnsupportedProtocolVersion`)
+    /// - A content length differs from the header (`NeutralIpcError::ProtocolViolation`)
+    /// - The checksum does not match (`NeutralIpcError::ChecksumMismatch`)
+    /// - A content block is not valid UTF-8 (`NeutralIpcError::InvalidUtf8`)
+    pub(crate) fn decode_record(header: &[u8], content1: &[u8], content2: &[u8]) -> Result<HashMap<String, Value>> {
+        let raw = Self::decode_record_bytes(header, content1.to_vec(), content2.to_vec())?;
+
+        let content1 = String::from_utf8(raw.content1).map_err(NeutralIpcError::InvalidUtf8)?;
+        let content2 = String::from_utf8(raw.content2).map_err(NeutralIpcError::InvalidUtf8)?;
+
+        Ok(Self::build_record(&raw.header, content1, content2))
+    }
+
+    /// Decode a complete IPC record, replacing invalid UTF-8 in the second content block.
+    ///
+    /// Same as `decode_record`, but invalid UTF-8 sequences in `content2` (the
+    /// rendered output) are replaced with U+FFFD instead of failing. The first
+    /// content block (JSON) is always decoded strictly.
+    ///
+    /// # Errors
+    ///
+    /// See `decode_record`; `NeutralIpcError::InvalidUtf8` is only returned for `content1`.
+    pub(crate) fn decode_record_lossy(header: &[u8], content1: &[u8], content2: &[u8]) -> Result<HashMap<String, Value>> {
+        let raw = Self::decode_record_bytes(header, content1.to_vec(), content2.to_vec())?;
+
+        let content1 = String::from_utf8(raw.content1).map_err(NeutralIpcError::InvalidUtf8)?;
+        let content2 = match String::from_utf8(raw.content2) {
+            Ok(content2) => content2,
+            Err(err) => String::from_utf8_lossy(err.as_bytes()).into_owned(),
+        };
+
+        Ok(Self::build_record(&raw.header, content1, content2))
+    }
 
+    /// Decode a complete IPC record, keeping both content blocks as raw bytes.
+    ///
+    /// Same checks as `decode_record`, without the UTF-8 conversion: the
+    /// contents are moved into the raw record, decompressed first if they are
+    /// compressed `CONTENT_BIN` blocks (`compression` feature).
+    ///
+    /// # Errors
+    ///
+    /// See `decode_record`, except `NeutralIpcError::InvalidUtf8`.
+    pub(crate) fn decode_record_bytes(header: &[u8], content1: Vec<u8>, content2: Vec<u8>) -> Result<NeutralIpcRawRecord> {
+        let decoded = Self::validate_record(header, &content1, &content2)?;
+
+        Ok(NeutralIpcRawRecord {
+            header: decoded,
+            content1: Self::decode_bytes(decoded.format1, content1)?,
+            content2: Self::decode_bytes(decoded.format2, content2)?,
+        })
+    }
+
+    /// Decode the header and first content block of a record whose second block is streamed.
+    ///
+    /// The stored `content-2` is empty; the caller checks the length of the
+    /// second block and the checksum while reading it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::ProtocolViolation` if `content1` does not have
+    /// the declared length, or an error if the header or `content1` is invalid.
+    pub(crate) fn decode_record_head(header: &[u8], content1: &[u8]) -> Result<HashMap<String, Value>> {
+        let decoded = Self::decode_header(header)?;
+        if decoded.length1 as usize != content1.len() {
+            return Err(NeutralIpcError::ProtocolViolation(format!(
+                "content-1 has {} bytes but the header declares {}",
+                content1.len(),
+                decoded.length1
+            )));
+        }
+
+        let content1 = Self::decode_content(decoded.format1, content1)?;
+        Ok(Self::build_record(&decoded, content1, String::new()))
+    }
+
+    /// Decode the header and check the raw contents against it.
+    ///
+    /// The content lengths must match the lengths declared in the header and,
+    /// for v1 headers, the checksum must match the contents.
+    fn validate_record(header: &[u8], content1: &[u8], content2: &[u8]) -> Result<RecordHeader> {
+        let decoded = Self::decode_header(header)?;
+
+        for (block, declared, actual) in [(1, decoded.length1, content1.len()), (2, decoded.length2, content2.len())] {
+            if declared as usize != actual {
+                return Err(NeutralIpcError::ProtocolViolation(format!(
+                    "content-{} has {} bytes but the header declares {}",
+                    block, actual, declared
+                )));
+            }
+        }
+
+        if let Some(expected) = Self::header_checksum(header) {
+            let actual = Self::checksum(content1, content2);
+            if actual != expected {
+                return Err(NeutralIpcError::ChecksumMismatch { expected, actual });
+            }
+        }
+
+        Ok(decoded)
+    }
+
+    /// Build the decoded record map from a validated header and decoded contents.
+    fn build_record(header: &RecordHeader, content1: String, content2: String) -> HashMap<String, Value> {
         let mut record = HashMap::new();
         record.insert("reserved".to_string(), Value::Number(RESERVED.into()));
-        record.insert("control".to_string(), Value::Number(header[1].into()));
-        record.insert("format-1".to_string(), Value::Number(header[2].into()));
-        record.insert("content-1".to_string(), Value::String(content1.to_string()));
-        record.insert("format-2".to_string(), Value::Number(header[7].into()));
-        record.insert("content-2".to_string(), Value::String(content2.to_string()));
+        record.insert("control".to_string(), Value::Number(header.control.into()));
+        record.insert("format-1".to_string(), Value::Number(u8::from(header.format1).into()));
+        record.insert("content-1".to_string(), Value::String(content1));
+        record.insert("format-2".to_string(), Value::Number(u8::from(header.format2).into()));
+        record.insert("content-2".to_string(), Value::String(content2));
+        record
+    }
 
-        Ok(record)
+    /// Decompress a content block if it is a compressed `CONTENT_BIN` block
+    /// or has a compressed format such as `CONTENT_JSON_GZ`.
+    fn decode_bytes(format: ContentFormat, content: Vec<u8>) -> Result<Vec<u8>> {
+        #[cfg(feature = "compression")]
+        if let Some(compression) = crate::compression::format_compression(format) {
+            return crate::compression::decompress_as(compression, &content);
+        }
+        #[cfg(feature = "compression")]
+        if format == ContentFormat::Bin {
+            return crate::compression::decompress(&content);
+        }
+        #[cfg(not(feature = "compression"))]
+        let _ = format;
+
+        Ok(content)
+    }
+
+    /// Decode a single content block into a UTF-8 string.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - Format identifier of the content block
+    /// * `content` - The content block as raw bytes
+    pub(crate) fn decode_content(format: ContentFormat, content: &[u8]) -> Result<String> {
+        let content = Self::decode_bytes(format, content.to_vec())?;
+        String::from_utf8(content).map_err(NeutralIpcError::InvalidUtf8)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
     use serde_json::json;
 
+    proptest! {
+        #[test]
+        fn prop_record_header_encode_decode_round_trip(
+            reserved in prop_oneof![Just(RESERVED), Just(PROTOCOL_V1)],
+            control: u8,
+            format1: u8,
+            length1: u32,
+            format2: u8,
+            length2: u32,
+        ) {
+            let header = RecordHeader {
+                reserved,
+                control,
+                format1: format1.into(),
+                length1,
+                format2: format2.into(),
+                length2,
+            };
+            prop_assert_eq!(RecordHeader::decode(&header.encode()).unwrap(), header);
+        }
+
+        #[test]
+        fn prop_record_header_decode_encode_round_trip(
+            reserved in prop_oneof![Just(RESERVED), Just(PROTOCOL_V1)],
+            rest: [u8; HEADER_LEN - 1],
+        ) {
+            let mut bytes = [0u8; HEADER_LEN];
+            bytes[0] = reserved;
+            bytes[1..].copy_from_slice(&rest);
+            prop_assert_eq!(RecordHeader::decode(&bytes).unwrap().encode(), bytes);
+        }
+
+        #[test]
+        fn prop_content_format_round_trip(format: u8) {
+            prop_assert_eq!(u8::from(ContentFormat::from(format)), format);
+        }
+    }
+
+    #[test]
+    fn test_record_header_rejects_unknown_protocol_version() {
+        let mut bytes = RecordHeader {
+            reserved: RESERVED,
+            control: CTRL_STATUS_OK,
+            format1: ContentFormat::Json,
+            length1: 0,
+            format2: ContentFormat::Text,
+            length2: 0,
+        }
+        .encode();
+        bytes[0] = 7;
+
+        assert!(matches!(RecordHeader::decode(&bytes), Err(NeutralIpcError::UnsupportedProtocolVersion(7))));
+        assert!(matches!(NeutralIpcRecord::decode_header(&bytes), Err(NeutralIpcError::UnsupportedProtocolVersion(7))));
+    }
+
+    #[test]
+    fn test_content_format_maps_protocol_constants() {
+        assert_eq!(ContentFormat::from(CONTENT_JSON), ContentFormat::Json);
+        assert_eq!(ContentFormat::from(CONTENT_PATH), ContentFormat::Path);
+        assert_eq!(ContentFormat::from(CONTENT_TEXT), ContentFormat::Text);
+        assert_eq!(ContentFormat::from(CONTENT_BIN), ContentFormat::Bin);
+        assert_eq!(ContentFormat::from(CONTENT_MSGPACK), ContentFormat::MsgPack);
+        assert_eq!(ContentFormat::from(CONTENT_BASE64), ContentFormat::Base64);
+        assert_eq!(ContentFormat::from(CONTENT_JSON_GZ), ContentFormat::JsonGzip);
+        assert_eq!(ContentFormat::from(CONTENT_JSON_ZLIB), ContentFormat::JsonZlib);
+        assert_eq!(ContentFormat::from(CONTENT_TEXT_GZ), ContentFormat::TextGzip);
+        assert_eq!(ContentFormat::from(CONTENT_TEXT_ZLIB), ContentFormat::TextZlib);
+        assert_eq!(ContentFormat::from(99), ContentFormat::Other(99));
+    }
+
     #[test]
     fn test_encode_record_with_msgpack_content_sets_lengths_and_formats() {
         let schema = json!({
@@ -216,9 +805,285 @@ mod tests {
         );
 
         let decoded = NeutralIpcRecord::decode_header(&header).unwrap();
-        assert_eq!(decoded.get("format-1").and_then(|v| v.as_u64()), Some(CONTENT_MSGPACK as u64));
-        assert_eq!(decoded.get("length-1").and_then(|v| v.as_u64()), Some(42));
-        assert_eq!(decoded.get("format-2").and_then(|v| v.as_u64()), Some(CONTENT_TEXT as u64));
-        assert_eq!(decoded.get("length-2").and_then(|v| v.as_u64()), Some(8));
+        assert_eq!(decoded.format1, ContentFormat::MsgPack);
+        assert_eq!(decoded.length1, 42);
+        assert_eq!(decoded.format2, ContentFormat::Text);
+        assert_eq!(decoded.length2, 8);
+    }
+
+    const FORMATS: [u8; 4] = [CONTENT_JSON, CONTENT_PATH, CONTENT_TEXT, CONTENT_BIN];
+
+    /// Encode a record, split it into header and content blocks, and decode it again.
+    fn round_trip(control: u8, format1: u8, content1: &[u8], format2: u8, content2: &[u8]) -> HashMap<String, Value> {
+        let record = NeutralIpcRecord::encode_record(control, format1, content1, format2, content2);
+        assert_eq!(record.len(), HEADER_LEN + content1.len() + content2.len());
+
+        let (header, contents) = record.split_at(HEADER_LEN);
+        let (decoded1, decoded2) = contents.split_at(content1.len());
+
+        let decoded_header = NeutralIpcRecord::decode_header(header).unwrap();
+        assert_eq!(decoded_header.reserved, RESERVED);
+        assert_eq!(decoded_header.control, control);
+        assert_eq!(u8::from(decoded_header.format1), format1);
+        assert_eq!(decoded_header.length1 as usize, content1.len());
+        assert_eq!(u8::from(decoded_header.format2), format2);
+        assert_eq!(decoded_header.length2 as usize, content2.len());
+
+        NeutralIpcRecord::decode_record(header, decoded1, decoded2).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_all_format_combinations() {
+        let content1 = br#"{"data":{"text":"Hello"}}"#;
+        let content2 = b"Rust IPC client: {:;text:}";
+
+        for format1 in FORMATS {
+            for format2 in FORMATS {
+                let decoded = round_trip(CTRL_PARSE_TEMPLATE, format1, content1, format2, content2);
+
+                assert_eq!(decoded.get("control").and_then(|v| v.as_u64()), Some(CTRL_PARSE_TEMPLATE as u64));
+                assert_eq!(decoded.get("format-1").and_then(|v| v.as_u64()), Some(format1 as u64));
+                assert_eq!(decoded.get("format-2").and_then(|v| v.as_u64()), Some(format2 as u64));
+                assert_eq!(decoded.get("content-1").and_then(|v| v.as_str()), Some(r#"{"data":{"text":"Hello"}}"#));
+                assert_eq!(decoded.get("content-2").and_then(|v| v.as_str()), Some("Rust IPC client: {:;text:}"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_trip_zero_length_contents() {
+        let decoded = round_trip(CTRL_STATUS_OK, CONTENT_JSON, b"", CONTENT_TEXT, b"Hello");
+        assert_eq!(decoded.get("content-1").and_then(|v| v.as_str()), Some(""));
+        assert_eq!(decoded.get("content-2").and_then(|v| v.as_str()), Some("Hello"));
+
+        let decoded = round_trip(CTRL_STATUS_OK, CONTENT_JSON, b"{}", CONTENT_TEXT, b"");
+        assert_eq!(decoded.get("content-1").and_then(|v| v.as_str()), Some("{}"));
+        assert_eq!(decoded.get("content-2").and_then(|v| v.as_str()), Some(""));
+
+        let decoded = round_trip(CTRL_STATUS_OK, CONTENT_JSON, b"", CONTENT_TEXT, b"");
+        assert_eq!(decoded.get("content-1").and_then(|v| v.as_str()), Some(""));
+        assert_eq!(decoded.get("content-2").and_then(|v| v.as_str()), Some(""));
+    }
+
+    #[test]
+    fn test_round_trip_large_content() {
+        let content2 = "0123456789abcdef".repeat(64 * 1024 + 1);
+        assert!(content2.len() > 1024 * 1024);
+
+        let decoded = round_trip(CTRL_STATUS_OK, CONTENT_JSON, b"{}", CONTENT_TEXT, content2.as_bytes());
+        assert_eq!(decoded.get("content-2").and_then(|v| v.as_str()), Some(content2.as_str()));
+    }
+
+    #[test]
+    fn test_round_trip_non_ascii_utf8_content() {
+        let content1 = r#"{"data":{"text":"Añadir ünïcödé 日本語 🚀"}}"#;
+        let content2 = "Ñandú: 日本語 🚀";
+
+        let decoded = round_trip(CTRL_STATUS_OK, CONTENT_JSON, content1.as_bytes(), CONTENT_TEXT, content2.as_bytes());
+        assert_eq!(decoded.get("content-1").and_then(|v| v.as_str()), Some(content1));
+        assert_eq!(decoded.get("content-2").and_then(|v| v.as_str()), Some(content2));
+    }
+
+    #[test]
+    fn test_decode_record_reports_invalid_utf8_offset() {
+        let content2 = b"Hello \xff world";
+        let header = NeutralIpcRecord::encode_header(CTRL_STATUS_OK, CONTENT_JSON, 2, CONTENT_TEXT, content2.len() as u32);
+
+        match NeutralIpcRecord::decode_record(&header, b"{}", content2) {
+            Err(NeutralIpcError::InvalidUtf8(err)) => {
+                assert_eq!(err.utf8_error().valid_up_to(), 6);
+                assert_eq!(err.as_bytes()[6], 0xff);
+            }
+            other => panic!("expected InvalidUtf8, got {:?}", other),
+        }
+
+        let decoded = NeutralIpcRecord::decode_record_lossy(&header, b"{}", content2).unwrap();
+        assert_eq!(decoded.get("content-2").and_then(|v| v.as_str()), Some("Hello \u{FFFD} world"));
+
+        let header = NeutralIpcRecord::encode_header(CTRL_STATUS_OK, CONTENT_JSON, 3, CONTENT_TEXT, 0);
+        assert!(matches!(
+            NeutralIpcRecord::decode_record_lossy(&header, b"{\xff}", b""),
+            Err(NeutralIpcError::InvalidUtf8(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_record_bytes_keeps_binary_content() {
+        let content2 = b"\x89PNG\r\n\x1a\n\xff\x00";
+        let header = NeutralIpcRecord::encode_header(CTRL_STATUS_OK, CONTENT_JSON, 2, CONTENT_BIN, content2.len() as u32);
+
+        let raw = NeutralIpcRecord::decode_record_bytes(&header, b"{}".to_vec(), content2.to_vec()).unwrap();
+        assert_eq!(NeutralIpcRawRecord::decode(&header, b"{}".to_vec(), content2.to_vec()).unwrap(), raw);
+        assert_eq!(raw.header.control, CTRL_STATUS_OK);
+        assert_eq!(raw.header.format2, ContentFormat::Bin);
+        assert_eq!(raw.content1_as_string_lossy(), "{}");
+        assert_eq!(raw.content2, content2);
+        assert_eq!(raw.content2_as_string_lossy(), "\u{FFFD}PNG\r\n\u{1a}\n\u{FFFD}\0");
+
+        assert!(matches!(
+            NeutralIpcRecord::decode_record_bytes(&header, b"{}".to_vec(), Vec::new()),
+            Err(NeutralIpcError::ProtocolViolation(_))
+        ));
+    }
+
+    #[test]
+    fn test_encode_record_checksum_round_trip() {
+        let content1 = br#"{"data":{"text":"Hello"}}"#;
+        let content2 = b"Rust IPC client: {:;text:}";
+
+        let record = NeutralIpcRecord::encode_record_checksum(
+            CTRL_PARSE_TEMPLATE,
+            CONTENT_JSON,
+            content1,
+            CONTENT_TEXT,
+            content2,
+        );
+
+        assert_eq!(record.len(), HEADER_LEN_V1 + content1.len() + content2.len());
+        assert_eq!(record[0], PROTOCOL_V1);
+
+        let header = &record[..HEADER_LEN_V1];
+        assert_eq!(NeutralIpcRecord::decode_header(header).unwrap().reserved, PROTOCOL_V1);
+        let checksum = NeutralIpcRecord::checksum(content1, content2);
+        assert_eq!(NeutralIpcRecord::header_checksum(header), Some(checksum));
+
+        let decoded = NeutralIpcRecord::decode_record(header, content1, content2).unwrap();
+        assert_eq!(decoded.get("content-2").and_then(|v| v.as_str()), Some("Rust IPC client: {:;text:}"));
+    }
+
+    #[test]
+    fn test_decode_header_from_reader_reads_v0_and_v1_headers() {
+        let v0 = NeutralIpcRecord::encode_record(CTRL_STATUS_OK, CONTENT_JSON, b"{}", CONTENT_TEXT, b"Hello");
+        let mut reader = v0.as_slice();
+        let header = NeutralIpcRecord::decode_header_from_reader(&mut reader).unwrap();
+        assert_eq!(header.length2, 5);
+        assert_eq!(header.reserved, RESERVED);
+        assert_eq!(reader, b"{}Hello");
+
+        let v1 = NeutralIpcRecord::encode_record_checksum(CTRL_STATUS_OK, CONTENT_JSON, b"{}", CONTENT_TEXT, b"Hello");
+        let mut reader = v1.as_slice();
+        let header = NeutralIpcRecord::decode_header_from_reader(&mut reader).unwrap();
+        assert_eq!(header.length2, 5);
+        assert_eq!(header.reserved, PROTOCOL_V1);
+        assert_eq!(reader, b"{}Hello");
+
+        let mut truncated = &v0[..HEADER_LEN - 1];
+        assert!(matches!(
+            NeutralIpcRecord::decode_header_from_reader(&mut truncated),
+            Err(NeutralIpcError::Io(_))
+        ));
+    }
+
+    #[cfg(any(feature = "async-tokio", feature = "async-std"))]
+    #[test]
+    fn test_decode_header_from_async_reader_reads_v0_and_v1_headers() {
+        let v0 = NeutralIpcRecord::encode_record(CTRL_STATUS_OK, CONTENT_JSON, b"{}", CONTENT_TEXT, b"Hello");
+        let mut reader = v0.as_slice();
+        let (header, bytes) =
+            futures_lite::future::block_on(NeutralIpcRecord::decode_header_from_async_reader(&mut reader)).unwrap();
+        assert_eq!(header.reserved, RESERVED);
+        assert_eq!(bytes, v0[..HEADER_LEN]);
+        assert_eq!(reader, b"{}Hello");
+
+        let v1 = NeutralIpcRecord::encode_record_checksum(CTRL_STATUS_OK, CONTENT_JSON, b"{}", CONTENT_TEXT, b"Hello");
+        let mut reader = v1.as_slice();
+        let (header, bytes) =
+            futures_lite::future::block_on(NeutralIpcRecord::decode_header_from_async_reader(&mut reader)).unwrap();
+        assert_eq!(header.reserved, PROTOCOL_V1);
+        assert_eq!(bytes, v1[..HEADER_LEN_V1]);
+        assert_eq!(reader, b"{}Hello");
+
+        let mut truncated = &v1[..HEADER_LEN_V1 - 1];
+        assert!(matches!(
+            futures_lite::future::block_on(NeutralIpcRecord::decode_header_from_async_reader(&mut truncated)),
+            Err(NeutralIpcError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_record_rejects_content_length_mismatch() {
+        let header = NeutralIpcRecord::encode_header(CTRL_STATUS_OK, CONTENT_JSON, 2, CONTENT_TEXT, 5);
+
+        match NeutralIpcRecord::decode_record(&header, b"{}", b"Hell") {
+            Err(NeutralIpcError::ProtocolViolation(reason)) => {
+                assert_eq!(reason, "content-2 has 4 bytes but the header declares 5")
+            }
+            other => panic!("expected ProtocolViolation, got {:?}", other),
+        }
+        assert!(matches!(
+            NeutralIpcRecord::decode_record(&header, b"{}  ", b"Hello"),
+            Err(NeutralIpcError::ProtocolViolation(_))
+        ));
+        assert!(matches!(
+            NeutralIpcRecord::decode_record_lossy(&header, b"", b"Hello"),
+            Err(NeutralIpcError::ProtocolViolation(_))
+        ));
+        assert!(NeutralIpcRecord::decode_record(&header, b"{}", b"Hello").is_ok());
+    }
+
+    #[test]
+    fn test_encode_and_split_headers() {
+        let headers = vec![
+            ("X-Request-Id".to_string(), "abc-123".to_string()),
+            ("Accept-Language".to_string(), "es: ES".to_string()),
+        ];
+        let encoded = NeutralIpcRecord::encode_headers(&headers, br#"{"data":{}}"#).unwrap();
+        assert_eq!(encoded, b"X-Request-Id: abc-123\nAccept-Language: es: ES\n\0{\"data\":{}}");
+
+        let (decoded, rest) = NeutralIpcRecord::split_headers(std::str::from_utf8(&encoded).unwrap()).unwrap();
+        assert_eq!(decoded, headers);
+        assert_eq!(rest, r#"{"data":{}}"#);
+
+        assert_eq!(NeutralIpcRecord::encode_headers(&[], b"{}").unwrap(), b"\0{}");
+        assert_eq!(NeutralIpcRecord::split_headers("{}").unwrap(), (Vec::new(), "{}"));
+        assert!(matches!(
+            NeutralIpcRecord::split_headers("no colon\n\0{}"),
+            Err(NeutralIpcError::ProtocolViolation(_))
+        ));
+    }
+
+    #[test]
+    fn test_encode_headers_rejects_unencodable_headers() {
+        for (key, value) in [("", "v"), ("a:b", "v"), ("a\nb", "v"), ("k", "line\nbreak"), ("k", "zero\0")] {
+            assert!(matches!(
+                NeutralIpcRecord::encode_headers(&[(key.to_string(), value.to_string())], b""),
+                Err(NeutralIpcError::InvalidRequestHeader(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_decode_record_checksum_mismatch() {
+        let content1 = b"{}";
+        let record = NeutralIpcRecord::encode_record_checksum(CTRL_STATUS_OK, CONTENT_JSON, content1, CONTENT_TEXT, b"Hello");
+        let header = &record[..HEADER_LEN_V1];
+
+        match NeutralIpcRecord::decode_record(header, content1, b"Hellp") {
+            Err(NeutralIpcError::ChecksumMismatch { expected, actual }) => {
+                assert_eq!(expected, NeutralIpcRecord::checksum(content1, b"Hello"));
+                assert_eq!(actual, NeutralIpcRecord::checksum(content1, b"Hellp"));
+            }
+            other => panic!("expected ChecksumMismatch, got {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_decode_record_decompresses_bin_content() {
+        use crate::config::CompressionType;
+
+        let content1 = crate::compression::compress(CompressionType::Gzip, br#"{"has_error":false}"#).unwrap();
+        let content2 = b"Hello!";
+        let header = NeutralIpcRecord::encode_header(
+            CTRL_STATUS_OK,
+            CONTENT_BIN,
+            content1.len() as u32,
+            CONTENT_TEXT,
+            content2.len() as u32,
+        );
+
+        let decoded = NeutralIpcRecord::decode_record(&header, &content1, content2).unwrap();
+        assert_eq!(decoded.get("content-1").and_then(|v| v.as_str()), Some(r#"{"has_error":false}"#));
+        assert_eq!(decoded.get("content-2").and_then(|v| v.as_str()), Some("Hello!"));
     }
 }
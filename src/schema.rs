@@ -0,0 +1,168 @@
+//! Utilities for schema values.
+//!
+//! `deep_merge` is the merge behind `NeutralIpcTemplate::merge_schema` and
+//! the other schema merges, so the result of a merge can be predicted (or
+//! computed ahead of time) without a template or a render.
+
+use serde_json::Value;
+
+/// How `deep_merge` combines two arrays found at the same place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeStrategy {
+    /// The array of the second value replaces the first one, like any other non-object value (default)
+    #[default]
+    Replace,
+    /// The items of the second array are appended to the first one
+    Append,
+}
+
+/// Recursively merge two JSON values.
+///
+/// - Two objects are merged key by key: keys only in `a` keep their value,
+///   keys only in `b` are added, and keys in both are merged recursively.
+/// - Two arrays are combined according to `strategy`.
+/// - In any other case `b` replaces `a`, including `null` and values of
+///   different types; an object in `b` replaces a non-object in `a`.
+///
+/// `NeutralIpcTemplate::merge_schema` merges with `MergeStrategy::Replace`.
+///
+/// # Arguments
+///
+/// * `a` - The base JSON value
+/// * `b` - The JSON value merged over `a`
+/// * `strategy` - How arrays present in both values are combined
+///
+/// # Returns
+///
+/// The merged JSON value.
+///
+/// # Example
+///
+/// ```
+/// use neutralipcrs::schema::{deep_merge, MergeStrategy};
+/// use serde_json::json;
+///
+/// let a = json!({"data": {"title": "Home", "tags": ["a"]}});
+/// let b = json!({"data": {"tags": ["b"], "user": "Ana"}});
+///
+/// assert_eq!(
+///     deep_merge(a.clone(), b.clone(), MergeStrategy::Replace),
+///     json!({"data": {"title": "Home", "tags": ["b"], "user": "Ana"}})
+/// );
+/// assert_eq!(
+///     deep_merge(a, b, MergeStrategy::Append),
+///     json!({"data": {"title": "Home", "tags": ["a", "b"], "user": "Ana"}})
+/// );
+/// ```
+pub fn deep_merge(a: Value, b: Value, strategy: MergeStrategy) -> Value {
+    match (a, b) {
+        (Value::Object(mut map_a), Value::Object(map_b)) => {
+            for (key, value_b) in map_b {
+                match map_a.get_mut(&key) {
+                    Some(value_a) => *value_a = deep_merge(std::mem::take(value_a), value_b, strategy),
+                    None => {
+                        map_a.insert(key, value_b);
+                    }
+                }
+            }
+            Value::Object(map_a)
+        }
+        (Value::Array(mut array_a), Value::Array(array_b)) if strategy == MergeStrategy::Append => {
+            array_a.extend(array_b);
+            Value::Array(array_a)
+        }
+        (_, b) => b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use serde_json::{json, Map};
+
+    /// Arbitrary JSON values, nested up to a few levels.
+    fn json_value() -> impl Strategy<Value = Value> {
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::Bool),
+            any::<i64>().prop_map(Value::from),
+            "[a-z]{0,4}".prop_map(Value::String),
+        ];
+        leaf.prop_recursive(3, 24, 4, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..4).prop_map(Value::Array),
+                prop::collection::btree_map("[a-c]", inner, 0..4)
+                    .prop_map(|map| Value::Object(map.into_iter().collect())),
+            ]
+        })
+    }
+
+    fn json_object() -> impl Strategy<Value = Map<String, Value>> {
+        prop::collection::btree_map("[a-d]", json_value(), 0..5).prop_map(|map| map.into_iter().collect())
+    }
+
+    fn strategy() -> impl Strategy<Value = MergeStrategy> {
+        prop_oneof![Just(MergeStrategy::Replace), Just(MergeStrategy::Append)]
+    }
+
+    proptest! {
+        #[test]
+        fn prop_merge_with_empty_object_is_identity(a in json_object(), strategy in strategy()) {
+            let a = Value::Object(a);
+            prop_assert_eq!(deep_merge(a.clone(), json!({}), strategy), a.clone());
+            prop_assert_eq!(deep_merge(json!({}), a.clone(), strategy), a);
+        }
+
+        #[test]
+        fn prop_merge_keeps_a_only_for_missing_keys(
+            a in json_object(),
+            b in json_object(),
+            strategy in strategy(),
+        ) {
+            let merged = deep_merge(Value::Object(a.clone()), Value::Object(b.clone()), strategy);
+            let merged = merged.as_object().unwrap();
+
+            prop_assert_eq!(merged.len(), a.keys().chain(b.keys()).collect::<std::collections::HashSet<_>>().len());
+            for (key, value) in merged {
+                match (a.get(key), b.get(key)) {
+                    (Some(value_a), None) => prop_assert_eq!(value, value_a),
+                    (None, Some(value_b)) => prop_assert_eq!(value, value_b),
+                    (Some(value_a), Some(value_b)) => {
+                        prop_assert_eq!(value, &deep_merge(value_a.clone(), value_b.clone(), strategy));
+                    }
+                    (None, None) => prop_assert!(false, "unexpected key {}", key),
+                }
+            }
+        }
+
+        #[test]
+        fn prop_merge_replaces_non_objects(a in json_value(), b in json_value()) {
+            let merged = deep_merge(a.clone(), b.clone(), MergeStrategy::Replace);
+            if !(a.is_object() && b.is_object()) {
+                prop_assert_eq!(merged, b);
+            }
+        }
+
+        #[test]
+        fn prop_merge_is_idempotent(a in json_value()) {
+            prop_assert_eq!(deep_merge(a.clone(), a.clone(), MergeStrategy::Replace), a);
+        }
+    }
+
+    #[test]
+    fn test_deep_merge_array_strategies() {
+        let a = json!({"items": [1, 2], "nested": {"items": [{"id": 1}]}, "scalar": [1]});
+        let b = json!({"items": [3], "nested": {"items": [{"id": 2}]}, "scalar": 2});
+
+        assert_eq!(
+            deep_merge(a.clone(), b.clone(), MergeStrategy::Replace),
+            json!({"items": [3], "nested": {"items": [{"id": 2}]}, "scalar": 2})
+        );
+        assert_eq!(
+            deep_merge(a, b, MergeStrategy::Append),
+            json!({"items": [1, 2, 3], "nested": {"items": [{"id": 1}, {"id": 2}]}, "scalar": 2})
+        );
+        assert_eq!(MergeStrategy::default(), MergeStrategy::Replace);
+    }
+}
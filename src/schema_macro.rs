@@ -0,0 +1,135 @@
+//! Declarative builder for nested schemas.
+//!
+//! Available with the `schema-macro` feature. The `neutral_schema!` macro
+//! builds the `serde_json::Value` of a schema from named sections, so the
+//! nesting of `data`, `config` and other top-level keys does not have to be
+//! spelled out with `json!` braces for every render.
+
+/// Build a schema from named sections.
+///
+/// Each section is a name followed by a braced list of entries. An entry is
+/// either `key: expression`, where the expression is serialized with serde,
+/// or `key { ... }`, a nested object with entries of its own, to any depth.
+/// Keys are identifiers or string literals; entries are separated by commas.
+/// A section name given twice is rejected at compile time.
+///
+/// # Panics
+///
+/// Panics if an expression cannot be serialized to JSON (e.g. a map with
+/// non-string keys), like `serde_json::json!`.
+///
+/// # Example
+///
+/// ```
+/// use neutralipcrs::neutral_schema;
+/// use serde_json::json;
+///
+/// let title = "Home";
+/// let schema = neutral_schema! {
+///     data {
+///         title: title,
+///         user { name: "Ana", roles: vec!["admin"] },
+///         "page-id": 7,
+///     },
+///     config { locale: "en" },
+/// };
+///
+/// assert_eq!(schema, json!({
+///     "data": {"title": "Home", "user": {"name": "Ana", "roles": ["admin"]}, "page-id": 7},
+///     "config": {"locale": "en"}
+/// }));
+/// ```
+///
+/// Duplicate sections do not compile:
+///
+/// ```compile_fail
+/// use neutralipcrs::neutral_schema;
+///
+/// let schema = neutral_schema! { data { a: 1 }, data { b: 2 } };
+/// ```
+#[macro_export]
+macro_rules! neutral_schema {
+    (@key $key:ident) => {
+        stringify!($key).to_string()
+    };
+    (@key $key:literal) => {
+        ($key).to_string()
+    };
+    (@object $($entries:tt)*) => {{
+        #[allow(unused_mut)]
+        let mut object = $crate::__serde_json::Map::new();
+        $crate::neutral_schema!(@entries object $($entries)*);
+        $crate::__serde_json::Value::Object(object)
+    }};
+    (@entries $object:ident) => {};
+    (@entries $object:ident $key:tt { $($nested:tt)* } $(, $($rest:tt)*)?) => {
+        $object.insert($crate::neutral_schema!(@key $key), $crate::neutral_schema!(@object $($nested)*));
+        $crate::neutral_schema!(@entries $object $($($rest)*)?);
+    };
+    (@entries $object:ident $key:tt : $value:expr $(, $($rest:tt)*)?) => {
+        $object.insert(
+            $crate::neutral_schema!(@key $key),
+            $crate::__serde_json::to_value(&$value).expect("schema value must serialize to JSON"),
+        );
+        $crate::neutral_schema!(@entries $object $($($rest)*)?);
+    };
+    ($($section:ident { $($entries:tt)* }),* $(,)?) => {{
+        // Duplicate section names are rejected as duplicate variants.
+        #[allow(non_camel_case_types, dead_code)]
+        enum NeutralSchemaSections { $($section),* }
+
+        #[allow(unused_mut)]
+        let mut schema = $crate::__serde_json::Map::new();
+        $(
+            schema.insert(stringify!($section).to_string(), $crate::neutral_schema!(@object $($entries)*));
+        )*
+        $crate::__serde_json::Value::Object(schema)
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+    use serde_json::json;
+
+    #[derive(Serialize)]
+    struct User {
+        name: String,
+        age: u32,
+    }
+
+    #[test]
+    fn test_neutral_schema_matches_json() {
+        let user = User { name: "Ana".to_string(), age: 30 };
+        let schema = neutral_schema! {
+            data { user: user, title: "Home" },
+            config { locale: "en" }
+        };
+        assert_eq!(schema, json!({
+            "data": {"user": {"name": "Ana", "age": 30}, "title": "Home"},
+            "config": {"locale": "en"}
+        }));
+
+        assert_eq!(neutral_schema! {}, json!({}));
+        assert_eq!(neutral_schema! { data {} }, json!({"data": {}}));
+    }
+
+    #[test]
+    fn test_neutral_schema_nesting_and_expressions() {
+        let items = [1, 2, 3];
+        let schema = neutral_schema! {
+            data {
+                a { b { c { d: items.len() * 2, "e-f": None::<u8> } } },
+                list: items,
+                flag: items.contains(&2),
+            },
+        };
+        assert_eq!(schema, json!({
+            "data": {
+                "a": {"b": {"c": {"d": 6, "e-f": null}}},
+                "list": [1, 2, 3],
+                "flag": true
+            }
+        }));
+    }
+}
@@ -4,11 +4,374 @@
 //! through the Neutral IPC server. It handles template setup, schema management,
 //! and result processing.
 
-use serde_json::Value;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use serde_json::{Map, Value};
 use std::collections::HashMap;
-use crate::client::NeutralIpcClient;
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::net::SocketAddr;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
+#[cfg(feature = "watch")]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::future::Future;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::task::{Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::client::{check_schema_size, NeutralIpcClient, RenderStream, SourceReader};
+use crate::config::NeutralIpcConfig;
 use crate::constants::*;
+#[cfg(feature = "dedup")]
+use crate::dedup::NeutralIpcDeduplicator;
 use crate::error::{NeutralIpcError, Result};
+use crate::json;
+use crate::prepared::{DryRun, PreparedRequest};
+use crate::record::{ContentFormat, NeutralIpcRecord};
+use crate::schema::{deep_merge, MergeStrategy};
+
+/// Redirect information decoded from the last rendering result.
+///
+/// Produced by `NeutralIpcTemplate::redirect()` when the template ended with a
+/// 3xx status code and a target location, e.g. `{:redirect; 301 >> url :}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    /// HTTP status code of the redirect (300-399)
+    pub code: u16,
+    /// Target location taken from the status parameter
+    pub location: String,
+    /// Whether the redirect is permanent (301 or 308)
+    pub permanent: bool,
+}
+
+/// Diagnostics of a `CTRL_STATUS_KO` response, see `NeutralIpcTemplate::last_server_diagnostics`.
+///
+/// The server sends its diagnostics in `content-1`, or in `content-2` when
+/// `content-1` is empty. JSON diagnostics are split into their fields; any
+/// other text is kept as the message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerDiagnostics {
+    /// Diagnostics exactly as sent by the server
+    pub raw: String,
+    /// The `message` (or `error`) field of JSON diagnostics, otherwise the raw text
+    pub message: String,
+    /// The `code` field of JSON diagnostics
+    pub code: Option<String>,
+    /// The `line` field of JSON diagnostics: template line the error refers to
+    pub line: Option<u64>,
+    /// The `stack` (or `location`) field of JSON diagnostics
+    pub stack: Option<String>,
+}
+
+impl ServerDiagnostics {
+    /// Parse the diagnostics text of a response.
+    pub(crate) fn parse(raw: &str) -> Self {
+        let fields = match json::from_str(raw) {
+            Ok(Value::Object(fields)) => fields,
+            _ => Map::new(),
+        };
+        let text = |keys: &[&str]| {
+            keys.iter().find_map(|key| match fields.get(*key)? {
+                Value::String(text) => Some(text.clone()),
+                Value::Null => None,
+                value => Some(value.to_string()),
+            })
+        };
+
+        Self {
+            raw: raw.to_string(),
+            message: text(&["message", "error"]).unwrap_or_else(|| raw.trim().to_string()),
+            code: text(&["code"]),
+            line: fields.get("line").and_then(|v| v.as_u64()),
+            stack: text(&["stack", "location"]),
+        }
+    }
+}
+
+/// Kind of template held by a `NeutralIpcTemplate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateKind {
+    /// The template is a file path resolved by the server (`CONTENT_PATH`)
+    Path,
+    /// The template is source code sent with the request (`CONTENT_TEXT`)
+    Source,
+}
+
+/// Line ending style of rendered content, see `NeutralIpcTemplate::set_normalize_newlines`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, serde::Deserialize))]
+pub enum NewlineStyle {
+    /// Unix line endings (`\n`)
+    Lf,
+    /// Windows line endings (`\r\n`)
+    CrLf,
+}
+
+/// Top-level schema sections read by the server, see `NeutralIpcTemplate::set_schema_sections`.
+const DEFAULT_SCHEMA_SECTIONS: [&str; 4] = ["data", "config", "inherit", "version"];
+
+/// Top-level schema key holding the locale, see `NeutralIpcTemplate::set_locale`.
+const LOCALE_KEY: &str = "_neutral_locale";
+
+/// Render run on its own thread, see `NeutralIpcTemplate::render_with_timeout_future`.
+#[derive(Default)]
+struct ThreadedRender {
+    /// Result of the render, or the deadline error, once known
+    outcome: Option<Result<String>>,
+    /// Waker of the future waiting for the outcome
+    waker: Option<Waker>,
+}
+
+/// Iterator over the leaf values of a schema, see `NeutralIpcTemplate::iter_schema_values`.
+struct SchemaLeaves {
+    /// Values still to walk, with their paths; the next one is last
+    stack: Vec<(String, Value)>,
+}
+
+impl Iterator for SchemaLeaves {
+    type Item = (String, Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((path, value)) = self.stack.pop() {
+            let join = |key: &str| {
+                if path.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{}.{}", path, key)
+                }
+            };
+
+            match value {
+                Value::Object(map) if !map.is_empty() => {
+                    let children: Vec<_> = map.into_iter().map(|(key, value)| (join(&key), value)).collect();
+                    self.stack.extend(children.into_iter().rev());
+                }
+                Value::Array(items) if !items.is_empty() => {
+                    let children: Vec<_> = items
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, value)| (join(&index.to_string()), value))
+                        .collect();
+                    self.stack.extend(children.into_iter().rev());
+                }
+                // An empty schema has no leaves, not one leaf without a path.
+                _ if path.is_empty() => {}
+                value => return Some((path, value)),
+            }
+        }
+        None
+    }
+}
+
+/// Default options applied to every new template.
+///
+/// Set with `NeutralIpcTemplate::set_global_defaults`. Each builder method
+/// mirrors the per-template setter of the same option; options that are not
+/// set leave the template default unchanged.
+///
+/// # Example
+///
+/// ```
+/// use neutralipcrs::TemplateOptions;
+/// use std::path::Path;
+///
+/// let options = TemplateOptions::new()
+///     .template_root(Path::new("/srv/templates"))
+///     .request_header("X-Service", "shop");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TemplateOptions {
+    /// Connection settings, see `NeutralIpcTemplate::with_config`
+    config: Option<NeutralIpcConfig>,
+    /// Base directory for relative template paths, see `NeutralIpcTemplate::set_template_root`
+    template_root: Option<PathBuf>,
+    /// Maximum schema size, see `NeutralIpcTemplate::set_max_schema_size`
+    max_schema_size: Option<usize>,
+    /// Request headers, see `NeutralIpcTemplate::set_request_header`
+    request_headers: Vec<(String, String)>,
+    /// JSON Schema for validation, see `NeutralIpcTemplate::set_validation_schema`
+    #[cfg(feature = "validate")]
+    validation_schema: Option<Value>,
+    /// Per-phase timeout, see `NeutralIpcTemplate::set_timeout`
+    timeout: Option<Duration>,
+    /// Connect timeout, see `NeutralIpcTemplate::set_connect_timeout`
+    connect_timeout: Option<Duration>,
+    /// Read and write buffer size, see `NeutralIpcTemplate::set_buffer_size`
+    buffer_size: Option<usize>,
+    /// Whether to remove a leading BOM, see `NeutralIpcTemplate::set_strip_bom`
+    strip_bom: Option<bool>,
+    /// Line endings of the content, see `NeutralIpcTemplate::set_normalize_newlines`
+    normalize_newlines: Option<NewlineStyle>,
+    /// Whether to trim the lines, see `NeutralIpcTemplate::set_trim_trailing_whitespace`
+    trim_trailing_whitespace: Option<bool>,
+    /// Whether unexpected schema keys fail, see `NeutralIpcTemplate::set_strict_schema_layout`
+    strict_schema_layout: Option<bool>,
+    /// Allowed top-level schema sections, see `NeutralIpcTemplate::set_schema_sections`
+    schema_sections: Option<Vec<String>>,
+}
+
+/// Default options set with `NeutralIpcTemplate::set_global_defaults`.
+static GLOBAL_DEFAULTS: RwLock<Option<Arc<TemplateOptions>>> = RwLock::new(None);
+
+impl TemplateOptions {
+    /// Create options with nothing set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the given connection settings, e.g. to enable lossy UTF-8 decoding.
+    pub fn config(mut self, config: NeutralIpcConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Set the base directory for relative template paths.
+    pub fn template_root(mut self, root: &Path) -> Self {
+        self.template_root = Some(root.to_path_buf());
+        self
+    }
+
+    /// Set the maximum schema size in bytes; zero means unlimited.
+    pub fn max_schema_size(mut self, limit: usize) -> Self {
+        self.max_schema_size = Some(limit);
+        self
+    }
+
+    /// Add a request header; an existing header with the same name is replaced.
+    pub fn request_header(mut self, key: &str, value: &str) -> Self {
+        match self.request_headers.iter_mut().find(|(name, _)| name.eq_ignore_ascii_case(key)) {
+            Some((_, current)) => *current = value.to_string(),
+            None => self.request_headers.push((key.to_string(), value.to_string())),
+        }
+        self
+    }
+
+    /// Set the JSON Schema the data schema must match before rendering.
+    #[cfg(feature = "validate")]
+    pub fn validation_schema(mut self, schema: Value) -> Self {
+        self.validation_schema = Some(schema);
+        self
+    }
+
+    /// Set the timeout of each phase of a request.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the timeout of the connect phase.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the read and write buffer size in bytes.
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = Some(buffer_size);
+        self
+    }
+
+    /// Set whether a leading UTF-8 byte order mark is removed from the content.
+    pub fn strip_bom(mut self, strip: bool) -> Self {
+        self.strip_bom = Some(strip);
+        self
+    }
+
+    /// Set the line endings the content is converted to.
+    pub fn normalize_newlines(mut self, style: NewlineStyle) -> Self {
+        self.normalize_newlines = Some(style);
+        self
+    }
+
+    /// Set whether trailing whitespace is removed from every line of the content.
+    pub fn trim_trailing_whitespace(mut self, trim: bool) -> Self {
+        self.trim_trailing_whitespace = Some(trim);
+        self
+    }
+
+    /// Set whether schema keys outside the allowed sections fail.
+    pub fn strict_schema_layout(mut self, strict: bool) -> Self {
+        self.strict_schema_layout = Some(strict);
+        self
+    }
+
+    /// Set the allowed top-level schema sections.
+    pub fn schema_sections(mut self, sections: &[&str]) -> Self {
+        self.schema_sections = Some(sections.iter().map(|section| section.to_string()).collect());
+        self
+    }
+
+    /// Get the current global default options, if any.
+    fn global() -> Option<Arc<TemplateOptions>> {
+        GLOBAL_DEFAULTS.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+}
+
+/// A template validated by the server, rendered by token.
+///
+/// Created with `NeutralIpcTemplate::compile`. Renders send only the schema
+/// and the token, so the server does not parse the template again.
+#[derive(Debug, Clone)]
+pub struct CompiledTemplate {
+    /// Token identifying the parsed template on the server
+    token: String,
+    /// Connection settings of the template it was compiled from
+    config: NeutralIpcConfig,
+}
+
+impl CompiledTemplate {
+    /// Get the token identifying the parsed template on the server.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Render the compiled template with the given schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - JSON schema as a `Value` or string
+    ///
+    /// # Returns
+    ///
+    /// The rendered content.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::NotSupported` if the server responds with
+    /// `CTRL_STATUS_KO`, e.g. because it no longer knows the token after a
+    /// restart, `NeutralIpcError::SchemaTooLarge` if the schema is over
+    /// `max_schema_size` with `SchemaSizePolicy::Fail`, or an error if IPC
+    /// communication fails.
+    pub fn render(&self, schema: Value) -> Result<String> {
+        let schema = match schema {
+            Value::String(schema) => schema,
+            schema => serde_json::to_string(&schema)?,
+        };
+        check_schema_size(&self.config, schema.len(), self.config.get_max_schema_size())?;
+        let mut client = NeutralIpcClient::with_config(
+            self.config.clone(),
+            CTRL_RENDER_COMPILED,
+            CONTENT_JSON,
+            schema.as_bytes(),
+            CONTENT_TEXT,
+            self.token.as_bytes()
+        )?;
+        let result = client.start()?;
+
+        if result.get("control").and_then(|v| v.as_u64()) == Some(CTRL_STATUS_KO as u64) {
+            return Err(NeutralIpcError::NotSupported);
+        }
+        let (_, content) = NeutralIpcTemplate::rendered_result(CTRL_RENDER_COMPILED, result)?;
+        Ok(content)
+    }
+}
 
 /// Main interface for template processing through the Neutral IPC server.
 ///
@@ -35,6 +398,12 @@ use crate::error::{NeutralIpcError, Result};
 ///
 /// println!("{}", result); // Output: "Hello World!"
 /// ```
+///
+/// With the `serde` feature, templates implement `Serialize` and `Deserialize`.
+/// The template, its schema and its render settings are serialized; the
+/// result of the last rendering, the connection settings, a forced endpoint,
+/// a streamed source, an attached deduplicator and a file watcher are not.
+#[cfg_attr(feature = "serde", derive(Serialize, serde::Deserialize))]
 pub struct NeutralIpcTemplate {
     /// Template content or file path
     template: String,
@@ -45,7 +414,79 @@ pub struct NeutralIpcTemplate {
     /// Schema bytes (JSON text bytes or MsgPack bytes)
     schema: Vec<u8>,
     /// Parsed result from the last rendering operation
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub(crate) result: HashMap<String, Value>,
+    /// Base directory for relative template paths, overriding the configuration
+    #[cfg_attr(feature = "serde", serde(default))]
+    template_root: Option<PathBuf>,
+    /// Request headers sent with `CTRL_PARSE_TEMPLATE_WITH_HEADERS`, in insertion order
+    #[cfg_attr(feature = "serde", serde(default))]
+    request_headers: Vec<(String, String)>,
+    /// Maximum schema size overriding the configuration (`Some(0)` means unlimited)
+    #[cfg_attr(feature = "serde", serde(default))]
+    max_schema_size: Option<usize>,
+    /// Whether the schema is protected from modification, see `seal_schema`
+    #[cfg_attr(feature = "serde", serde(default))]
+    schema_sealed: bool,
+    /// Per-phase timeout overriding the configuration
+    #[cfg_attr(feature = "serde", serde(default))]
+    timeout: Option<Duration>,
+    /// Connect timeout overriding the configuration
+    #[cfg_attr(feature = "serde", serde(default))]
+    connect_timeout: Option<Duration>,
+    /// Read and write buffer size overriding the configuration
+    #[cfg_attr(feature = "serde", serde(default))]
+    buffer_size: Option<usize>,
+    /// Whether a leading UTF-8 BOM is removed from the rendered content
+    #[cfg_attr(feature = "serde", serde(default))]
+    strip_bom: bool,
+    /// Line endings the rendered content is converted to, if any
+    #[cfg_attr(feature = "serde", serde(default))]
+    normalize_newlines: Option<NewlineStyle>,
+    /// Whether trailing whitespace is removed from every line of the rendered content
+    #[cfg_attr(feature = "serde", serde(default))]
+    trim_trailing_whitespace: bool,
+    /// Whether schemas with top-level keys outside the allowed sections are rejected
+    #[cfg_attr(feature = "serde", serde(default))]
+    strict_schema_layout: bool,
+    /// Whether `CTRL_STATUS_KO` responses fail with `NeutralIpcError::ServerError`
+    #[cfg_attr(feature = "serde", serde(default))]
+    fail_on_server_error: bool,
+    /// Allowed top-level schema sections replacing `DEFAULT_SCHEMA_SECTIONS`
+    #[cfg_attr(feature = "serde", serde(default))]
+    schema_sections: Option<Vec<String>>,
+    /// JSON Schema the data schema is validated against before rendering
+    #[cfg(feature = "validate")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    validation_schema: Option<Value>,
+    /// Template source streamed from a reader, see `set_source_reader`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    source_reader: Option<Arc<SourceReader>>,
+    /// Connection settings used instead of the configuration file
+    #[cfg_attr(feature = "serde", serde(skip))]
+    config: Option<NeutralIpcConfig>,
+    /// Server address overriding the host and port of the configuration
+    #[cfg_attr(feature = "serde", serde(skip))]
+    endpoint: Option<SocketAddr>,
+    /// Deduplicator coalescing identical concurrent renders
+    #[cfg(feature = "dedup")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    deduplicator: Option<Arc<NeutralIpcDeduplicator>>,
+    /// Watcher of the template file, see `watch_template_file`
+    #[cfg(feature = "watch")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    watch: Option<TemplateWatch>,
+}
+
+/// File system watcher of a file template.
+#[cfg(feature = "watch")]
+struct TemplateWatch {
+    /// Watcher of the directory of the file; dropping it stops watching
+    _watcher: notify::RecommendedWatcher,
+    /// Local path of the watched file
+    path: PathBuf,
+    /// Set by the watcher when the file changed since it was last read
+    dirty: Arc<AtomicBool>,
 }
 
 impl NeutralIpcTemplate {
@@ -61,13 +502,122 @@ impl NeutralIpcTemplate {
     ///
     /// A new `NeutralIpcTemplate` instance or an error if initialization fails.
     pub fn new() -> Result<Self> {
-        Ok(Self {
-            template: "".to_string(),
-            tpl_type: CONTENT_PATH,
-            schema_type: CONTENT_JSON,
-            schema: b"{}".to_vec(),
+        Ok(Self::from_parts("".to_string(), CONTENT_PATH, CONTENT_JSON, b"{}".to_vec()))
+    }
+
+    /// Build a template from its parts and apply the global default options.
+    fn from_parts(template: String, tpl_type: u8, schema_type: u8, schema: Vec<u8>) -> Self {
+        let mut this = Self {
+            template,
+            tpl_type,
+            schema_type,
+            schema,
             result: HashMap::new(),
-        })
+            template_root: None,
+            request_headers: Vec::new(),
+            max_schema_size: None,
+            schema_sealed: false,
+            timeout: None,
+            connect_timeout: None,
+            buffer_size: None,
+            strip_bom: false,
+            normalize_newlines: None,
+            trim_trailing_whitespace: false,
+            strict_schema_layout: false,
+            fail_on_server_error: false,
+            schema_sections: None,
+            #[cfg(feature = "validate")]
+            validation_schema: None,
+            source_reader: None,
+            config: None,
+            endpoint: None,
+            #[cfg(feature = "dedup")]
+            deduplicator: None,
+            #[cfg(feature = "watch")]
+            watch: None,
+        };
+        if let Some(options) = TemplateOptions::global() {
+            this.apply_options(&options);
+        }
+        this
+    }
+
+    /// Apply the options that are set in `options`, overriding current values.
+    fn apply_options(&mut self, options: &TemplateOptions) {
+        if let Some(config) = &options.config {
+            self.config = Some(config.clone());
+        }
+        if let Some(root) = &options.template_root {
+            self.template_root = Some(root.clone());
+        }
+        if let Some(limit) = options.max_schema_size {
+            self.max_schema_size = Some(limit);
+        }
+        for (key, value) in &options.request_headers {
+            self.set_request_header(key, value);
+        }
+        #[cfg(feature = "validate")]
+        if let Some(schema) = &options.validation_schema {
+            self.validation_schema = Some(schema.clone());
+        }
+        if let Some(timeout) = options.timeout {
+            self.set_timeout(timeout);
+        }
+        if let Some(timeout) = options.connect_timeout {
+            self.set_connect_timeout(timeout);
+        }
+        if let Some(buffer_size) = options.buffer_size {
+            self.set_buffer_size(buffer_size);
+        }
+        if let Some(strip) = options.strip_bom {
+            self.strip_bom = strip;
+        }
+        if let Some(style) = options.normalize_newlines {
+            self.normalize_newlines = Some(style);
+        }
+        if let Some(trim) = options.trim_trailing_whitespace {
+            self.trim_trailing_whitespace = trim;
+        }
+        if let Some(strict) = options.strict_schema_layout {
+            self.strict_schema_layout = strict;
+        }
+        if let Some(sections) = &options.schema_sections {
+            self.schema_sections = Some(sections.clone());
+        }
+    }
+
+    /// Set the default options applied to every template created afterwards.
+    ///
+    /// Templates already created are not affected, and options set on a
+    /// template after it is created take precedence over the defaults.
+    /// The defaults are shared by all threads.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The default options
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::{NeutralIpcConfig, NeutralIpcTemplate, TemplateOptions};
+    /// use serde_json::json;
+    ///
+    /// let mut config = NeutralIpcConfig::new();
+    /// config.set_lossy_utf8(true);
+    /// NeutralIpcTemplate::set_global_defaults(
+    ///     TemplateOptions::new().config(config).max_schema_size(1024 * 1024),
+    /// );
+    ///
+    /// let mut template = NeutralIpcTemplate::from_src_value("{:;text:}", json!({})).unwrap();
+    /// template.set_max_schema_size(None); // back to the configuration limit
+    /// ```
+    pub fn set_global_defaults(options: TemplateOptions) {
+        *GLOBAL_DEFAULTS.write().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(options));
+    }
+
+    /// Remove the default options set with `set_global_defaults`.
+    pub fn clear_global_defaults() {
+        *GLOBAL_DEFAULTS.write().unwrap_or_else(PoisonError::into_inner) = None;
     }
 
     /// Create a template from a file path and JSON schema.
@@ -91,13 +641,7 @@ impl NeutralIpcTemplate {
             serde_json::to_string(&schema)?
         };
 
-        Ok(Self {
-            template: template.to_string(),
-            tpl_type: CONTENT_PATH,
-            schema_type: CONTENT_JSON,
-            schema: schema_str.into_bytes(),
-            result: HashMap::new(),
-        })
+        Ok(Self::from_parts(template.to_string(), CONTENT_PATH, CONTENT_JSON, schema_str.into_bytes()))
     }
 
     /// Create a template from source code and JSON schema.
@@ -121,13 +665,38 @@ impl NeutralIpcTemplate {
             serde_json::to_string(&schema)?
         };
 
-        Ok(Self {
-            template: template.to_string(),
-            tpl_type: CONTENT_TEXT,
-            schema_type: CONTENT_JSON,
-            schema: schema_str.into_bytes(),
-            result: HashMap::new(),
-        })
+        Ok(Self::from_parts(template.to_string(), CONTENT_TEXT, CONTENT_JSON, schema_str.into_bytes()))
+    }
+
+    /// Create a template from source code read from a reader and JSON schema.
+    ///
+    /// Reads the reader to its end, e.g. stdin or a piped process, see
+    /// `set_source_from_reader`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Reader providing the UTF-8 template source code
+    /// * `schema` - JSON schema as a `Value` or string
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::Io` if reading fails, `NeutralIpcError::InvalidUtf8`
+    /// if the source is not valid UTF-8, or an error if the schema cannot be
+    /// serialized to JSON.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let mut template = NeutralIpcTemplate::from_reader(std::io::stdin(), json!({})).unwrap();
+    /// let result = template.render().unwrap();
+    /// ```
+    pub fn from_reader<R: Read>(reader: R, schema: Value) -> Result<Self> {
+        let mut template = Self::from_src_value("", schema)?;
+        template.set_source_from_reader(reader)?;
+        Ok(template)
     }
 
     /// Create a template from a file path and MsgPack schema bytes.
@@ -137,13 +706,7 @@ impl NeutralIpcTemplate {
     /// * `template` - File path to the template
     /// * `schema` - MsgPack-encoded schema bytes
     pub fn from_file_msgpack(template: &str, schema: &[u8]) -> Result<Self> {
-        Ok(Self {
-            template: template.to_string(),
-            tpl_type: CONTENT_PATH,
-            schema_type: CONTENT_MSGPACK,
-            schema: schema.to_vec(),
-            result: HashMap::new(),
-        })
+        Ok(Self::from_parts(template.to_string(), CONTENT_PATH, CONTENT_MSGPACK, schema.to_vec()))
     }
 
     /// Create a template from source code and MsgPack schema bytes.
@@ -153,15 +716,77 @@ impl NeutralIpcTemplate {
     /// * `template` - Template source code
     /// * `schema` - MsgPack-encoded schema bytes
     pub fn from_src_msgpack(template: &str, schema: &[u8]) -> Result<Self> {
-        Ok(Self {
-            template: template.to_string(),
-            tpl_type: CONTENT_TEXT,
-            schema_type: CONTENT_MSGPACK,
-            schema: schema.to_vec(),
-            result: HashMap::new(),
-        })
+        Ok(Self::from_parts(template.to_string(), CONTENT_TEXT, CONTENT_MSGPACK, schema.to_vec()))
+    }
+
+    /// Create a template from a file path and a JSON schema file.
+    ///
+    /// # Arguments
+    ///
+    /// * `template_path` - File path to the template
+    /// * `schema_path` - Path to a JSON file containing the schema
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::SchemaFileIo` if the schema file cannot be read,
+    /// or `NeutralIpcError::SchemaFileJson` if it does not contain valid JSON.
+    pub fn from_file_with_schema_file(template_path: &str, schema_path: &Path) -> Result<Self> {
+        Self::from_file_value(template_path, Self::read_schema_file(schema_path)?)
+    }
+
+    /// Create a template from source code and a typed schema.
+    ///
+    /// The schema is serialized with serde and must serialize to a JSON object.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - Template source code
+    /// * `schema` - Any value implementing `Serialize`, typically a struct
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schema cannot be serialized or does not
+    /// serialize to a JSON object (`NeutralIpcError::InvalidSchema`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Data {
+    ///     text: String,
+    /// }
+    ///
+    /// #[derive(Serialize)]
+    /// struct Schema {
+    ///     data: Data,
+    /// }
+    ///
+    /// let schema = Schema { data: Data { text: "World".to_string() } };
+    /// let template = NeutralIpcTemplate::from_src_typed("Hello {:;text:}!", &schema).unwrap();
+    /// ```
+    pub fn from_src_typed<T: Serialize>(template: &str, schema: &T) -> Result<Self> {
+        Self::from_src_value(template, Self::typed_schema(schema)?)
     }
 
+    /// Create a template from a file path and a typed schema.
+    ///
+    /// The schema is serialized with serde and must serialize to a JSON object.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - File path to the template
+    /// * `schema` - Any value implementing `Serialize`, typically a struct
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schema cannot be serialized or does not
+    /// serialize to a JSON object (`NeutralIpcError::InvalidSchema`).
+    pub fn from_file_typed<T: Serialize>(template: &str, schema: &T) -> Result<Self> {
+        Self::from_file_value(template, Self::typed_schema(schema)?)
+    }
 
     /// Render the template with the current schema through the Neutral server.
     ///
@@ -178,9 +803,19 @@ impl NeutralIpcTemplate {
     /// # Errors
     ///
     /// Returns an error if:
+    /// - The schema does not match the validation schema (`validate` feature)
     /// - IPC communication with the server fails
     /// - The server returns an invalid response
     /// - The response cannot be parsed as JSON
+    /// - The server answers with `CTRL_STATUS_KO` and `set_fail_on_server_error`
+    ///   is enabled, or the schema was sent as a file path (`NeutralIpcError::ServerError`)
+    ///
+    /// Other `CTRL_STATUS_KO` responses are returned as rendered; check
+    /// `has_error()` or `get_status_code()`.
+    ///
+    /// Errors of the exchange with the server, from connecting to decoding the
+    /// response, are wrapped in `NeutralIpcError::Render`, naming the template
+    /// and the schema size.
     ///
     /// # Example
     ///
@@ -200,76 +835,61 @@ impl NeutralIpcTemplate {
     /// assert_eq!(result, "Hello World!");
     /// ```
     pub fn render(&mut self) -> Result<String> {
-        let mut client = NeutralIpcClient::new(
-            CTRL_PARSE_TEMPLATE,
-            self.schema_type,
-            self.schema.as_slice(),
-            self.tpl_type,
-            &self.template
-        );
-
-        let result = client.start()?;
-
-        let status = result.get("control")
-            .and_then(|v| v.as_u64())
-            .ok_or(NeutralIpcError::InvalidResponse)? as u8;
-
-        let content1 = result.get("content-1")
-            .and_then(|v| v.as_str())
-            .ok_or(NeutralIpcError::InvalidResponse)?;
-
-        let content2 = result.get("content-2")
-            .and_then(|v| v.as_str())
-            .ok_or(NeutralIpcError::InvalidResponse)?;
-
-        let result_data: Value = serde_json::from_str(content1)?;
-        self.result = HashMap::new();
-        self.result.insert("status".to_string(), Value::Number(status.into()));
-        self.result.insert("result".to_string(), result_data);
-        self.result.insert("content".to_string(), Value::String(content2.to_string()));
-
-        Ok(content2.to_string())
+        #[cfg(feature = "validate")]
+        if self.validation_schema.is_some() && self.schema_type != CONTENT_PATH {
+            self.validate(&self.decode_schema()?)?;
+        }
+        self.render_schema(None)
     }
 
-    /// Set the template to use a file path.
+    /// Render the template without validating the schema.
     ///
-    /// Changes the template type to `CONTENT_PATH` and updates the template content
-    /// to the specified file path.
+    /// Same as `render()`, but skips the validation schema set with
+    /// `set_validation_schema()` for this render.
     ///
-    /// # Arguments
+    /// # Errors
     ///
-    /// * `path` - Path to the template file
-    pub fn set_path(&mut self, path: &str) {
-        self.tpl_type = CONTENT_PATH;
-        self.template = path.to_string();
+    /// Same as `render()`, except `NeutralIpcError::SchemaValidation`.
+    #[cfg(feature = "validate")]
+    pub fn render_unchecked(&mut self) -> Result<String> {
+        self.render_schema(None)
     }
 
-    /// Set the template to use source code directly.
+    /// Render the template, replacing invalid UTF-8 in the rendered content.
     ///
-    /// Changes the template type to `CONTENT_TEXT` and updates the template content
-    /// to the provided source code string.
+    /// Same as `render()`, but decodes the rendered content (content-2) as if
+    /// `lossy_utf8` were enabled in the configuration: invalid sequences are
+    /// replaced with U+FFFD instead of failing with `NeutralIpcError::InvalidUtf8`,
+    /// e.g. for templates that output Latin-1 text from legacy data sources.
+    /// The configuration of the template is left unchanged.
     ///
-    /// # Arguments
+    /// # Errors
     ///
-    /// * `source` - Template source code
-    pub fn set_source(&mut self, source: &str) {
-        self.tpl_type = CONTENT_TEXT;
-        self.template = source.to_string();
+    /// Same as `render()`, except `NeutralIpcError::InvalidUtf8` for the
+    /// rendered content.
+    pub fn render_to_string_lossy(&mut self) -> Result<String> {
+        let config = self.config.take();
+        // Not `unwrap_or_default()`: `new()` also loads the configuration file.
+        let mut lossy = match &config {
+            Some(config) => config.clone(),
+            None => NeutralIpcConfig::new(),
+        };
+        lossy.set_lossy_utf8(true);
+        self.config = Some(lossy);
+        let rendered = self.render();
+        self.config = config;
+        rendered
     }
 
-    /// Merge new schema data with the existing schema.
-    ///
-    /// This method performs a deep merge of JSON objects, allowing you to
-    /// incrementally build up complex schemas. For objects with overlapping
-    /// keys, the new values will override the existing ones.
-    ///
-    /// # Arguments
+    /// Render the template and escape the output for HTML.
     ///
-    /// * `schema` - New schema data to merge (as `Value` or string)
+    /// The rendered content is returned with `&`, `<`, `>`, `"` and `'`
+    /// replaced by character references, for embedding it in an attribute
+    /// value or element text. The stored result keeps the unescaped content.
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// `Ok(())` if the merge was successful, or an error if schema parsing fails.
+    /// Same as `render()`.
     ///
     /// # Example
     ///
@@ -277,310 +897,5188 @@ impl NeutralIpcTemplate {
     /// use neutralipcrs::NeutralIpcTemplate;
     /// use serde_json::json;
     ///
-    /// let mut template = NeutralIpcTemplate::new().unwrap();
-    /// template.merge_schema(json!({"data": {"value": 1}})).unwrap();
-    /// template.merge_schema(json!({"data": {"extra": 2}})).unwrap();
-    /// // Schema now contains: {"base": {"value": 1, "extra": 2}}
+    /// let mut template = NeutralIpcTemplate::from_src_value("{:;title:}", json!({"data": {"title": "<b>"}})).unwrap();
+    /// let escaped = template.render_to_html_escaped().unwrap();
+    /// println!("<div title=\"{}\">", escaped); // <div title="&lt;b&gt;">
     /// ```
-    pub fn merge_schema(&mut self, schema: Value) -> Result<()> {
-        let current_schema: Value = match self.schema_type {
-            CONTENT_MSGPACK => rmp_serde::from_slice(&self.schema)?,
-            _ => serde_json::from_slice(&self.schema)?,
-        };
-        let new_schema = if schema.is_string() {
-            serde_json::from_str(schema.as_str().unwrap())?
+    pub fn render_to_html_escaped(&mut self) -> Result<String> {
+        self.render().map(|rendered| Self::escape_html(&rendered))
+    }
+
+    /// Render the template and escape the output for a JavaScript string literal.
+    ///
+    /// Backslashes, quotes, backticks, `$` and line terminators are escaped,
+    /// as well as `<`, `>` and `&` so that the output cannot close a `<script>`
+    /// element. The result is safe between single, double or back quotes.
+    /// The stored result keeps the unescaped content.
+    ///
+    /// # Errors
+    ///
+    /// Same as `render()`.
+    pub fn render_to_js_escaped(&mut self) -> Result<String> {
+        self.render().map(|rendered| Self::escape_js(&rendered))
+    }
+
+    /// Set the JSON Schema the data schema must match before rendering.
+    ///
+    /// `render()` and `render_with_override()` validate the schema they are
+    /// about to send and fail with `NeutralIpcError::SchemaValidation`, listing
+    /// every violation, instead of contacting the server.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - The JSON Schema document
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::{NeutralIpcError, NeutralIpcTemplate};
+    /// use serde_json::json;
+    ///
+    /// let mut template = NeutralIpcTemplate::from_src_value("{:;title:}", json!({"data": {"titel": "Typo"}})).unwrap();
+    /// template.set_validation_schema(json!({
+    ///     "type": "object",
+    ///     "properties": {"data": {"required": ["title"]}}
+    /// }));
+    ///
+    /// assert!(matches!(template.render(), Err(NeutralIpcError::SchemaValidation(_))));
+    /// ```
+    #[cfg(feature = "validate")]
+    pub fn set_validation_schema(&mut self, schema: Value) {
+        self.validation_schema = Some(schema);
+    }
+
+    /// Validate a data schema against the validation schema, if any.
+    #[cfg(feature = "validate")]
+    fn validate(&self, schema: &Value) -> Result<()> {
+        let Some(validation_schema) = &self.validation_schema else {
+            return Ok(());
+        };
+        let validator = jsonschema::validator_for(validation_schema)
+            .map_err(|err| NeutralIpcError::InvalidSchema(format!("invalid validation schema: {}", err)))?;
+
+        let violations: Vec<(String, String)> = validator
+            .iter_errors(schema)
+            .map(|err| (err.instance_path.to_string(), err.to_string()))
+            .collect();
+        if violations.is_empty() {
+            Ok(())
         } else {
-            schema
+            Err(NeutralIpcError::SchemaValidation(violations))
+        }
+    }
+
+    /// Render the template with a temporary schema override.
+    ///
+    /// The overrides are deep-merged into a copy of the current schema, the
+    /// merged schema is sent to the server and then discarded. The stored
+    /// schema is left unchanged; only the result of the render is kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `overrides` - Schema data to merge for this render only (as `Value` or string)
+    ///
+    /// # Returns
+    ///
+    /// The rendered template content as a string.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schema cannot be merged or if rendering fails
+    /// (see `render()`).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let mut template = NeutralIpcTemplate::from_src_value("{:;text:}", json!({"data": {"text": "A"}})).unwrap();
+    /// let variant = template.render_with_override(json!({"data": {"text": "B"}})).unwrap();
+    /// let original = template.render().unwrap();
+    ///
+    /// assert_eq!(variant, "B");
+    /// assert_eq!(original, "A");
+    /// ```
+    pub fn render_with_override(&mut self, overrides: Value) -> Result<String> {
+        let merged = deep_merge(self.decode_schema()?, Self::parse_schema(overrides)?, MergeStrategy::Replace);
+        #[cfg(feature = "validate")]
+        self.validate(&merged)?;
+        let schema = self.encode_schema(&merged)?;
+        self.render_schema(Some(&schema))
+    }
+
+    /// Render independent templates concurrently.
+    ///
+    /// Each template is rendered on its own thread over its own connection to
+    /// the server, so the total latency is close to that of the slowest render
+    /// instead of the sum of all of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `templates` - The templates to render
+    ///
+    /// # Returns
+    ///
+    /// The result of each render, in the same order as `templates`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let templates = ["A", "B", "C"]
+    ///     .iter()
+    ///     .map(|text| NeutralIpcTemplate::from_src_value("{:;text:}", json!({"data": {"text": text}})).unwrap())
+    ///     .collect();
+    ///
+    /// let results = NeutralIpcTemplate::render_parallel(templates);
+    /// assert_eq!(results[1].as_deref().unwrap(), "B");
+    /// ```
+    pub fn render_parallel(templates: Vec<NeutralIpcTemplate>) -> Vec<Result<String>> {
+        thread::scope(|scope| {
+            let handles: Vec<_> = templates
+                .into_iter()
+                .map(|mut template| scope.spawn(move || template.render()))
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle.join().unwrap_or_else(|_| {
+                        Err(NeutralIpcError::Io(io::Error::other("render thread panicked")))
+                    })
+                })
+                .collect()
+        })
+    }
+
+    /// Render the template on its own thread, as a future resolved by a deadline.
+    ///
+    /// A compatibility shim for executors without tokio or async-std: the
+    /// blocking `render()` runs on a new thread as soon as this is called,
+    /// and the returned future, which can be polled by any executor, resolves
+    /// when the render completes or when `deadline` passes, whichever comes
+    /// first. The template is moved to the render thread, so its result is not
+    /// stored. A render still running at the deadline is not cancelled; it
+    /// ends within the timeouts of the configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `deadline` - The instant after which the render is abandoned
+    ///
+    /// # Errors
+    ///
+    /// The future resolves to the errors of `render()`, or to
+    /// `NeutralIpcError::DeadlineExceeded` if the deadline passes first.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let template = NeutralIpcTemplate::from_src_value("{:;text:}", json!({"data": {"text": "Hi"}})).unwrap();
+    /// let rendered = template.render_with_timeout_future(Instant::now() + Duration::from_secs(2));
+    /// // Poll `rendered` with the executor of the application.
+    /// ```
+    pub fn render_with_timeout_future(mut self, deadline: Instant) -> impl Future<Output = Result<String>> {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(self.render());
+        });
+
+        let shared = Arc::new(Mutex::new(ThreadedRender::default()));
+        let bridge = Arc::clone(&shared);
+        thread::spawn(move || {
+            let outcome = match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(rendered) => rendered,
+                Err(RecvTimeoutError::Timeout) => Err(NeutralIpcError::DeadlineExceeded),
+                Err(RecvTimeoutError::Disconnected) => Err(NeutralIpcError::Io(io::Error::other("render thread panicked"))),
+            };
+            let mut shared = bridge.lock().unwrap_or_else(PoisonError::into_inner);
+            shared.outcome = Some(outcome);
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        });
+
+        std::future::poll_fn(move |cx| {
+            let mut shared = shared.lock().unwrap_or_else(PoisonError::into_inner);
+            match shared.outcome.take() {
+                Some(outcome) => Poll::Ready(outcome),
+                None => {
+                    shared.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        })
+    }
+
+    /// Send the template to the server and store the rendering result.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - Schema bytes to send instead of the stored schema, if any
+    fn render_schema(&mut self, schema: Option<&[u8]>) -> Result<String> {
+        #[cfg(feature = "watch")]
+        self.reload_watched_template()?;
+        let schema = schema.unwrap_or(self.schema.as_slice());
+
+        // A streamed source is not part of the request key.
+        #[cfg(feature = "dedup")]
+        let rendered = match self.deduplicator.as_ref().filter(|_| self.source_reader.is_none()) {
+            Some(deduplicator) => deduplicator.run(self.request_key(schema), || self.request_render(schema)),
+            None => self.request_render(schema),
         };
+        #[cfg(not(feature = "dedup"))]
+        let rendered = self.request_render(schema);
+
+        self.store_rendered(rendered, schema.len())
+    }
+
+    /// Store the result of a render request and check its status.
+    ///
+    /// # Returns
+    ///
+    /// The rendered content.
+    fn store_rendered(&mut self, rendered: Result<(HashMap<String, Value>, String)>, schema_size: usize) -> Result<String> {
+        let (result, content) = rendered?;
+        self.result = result;
+        self.check_server_status().map_err(|err| self.render_error(err, schema_size))?;
+        Ok(self.postprocess_content(content))
+    }
+
+    /// Apply the output options of the template to rendered content and store it.
+    ///
+    /// The BOM is stripped first, then line endings are normalized and
+    /// trailing whitespace trimmed.
+    ///
+    /// # Returns
+    ///
+    /// The processed content.
+    fn postprocess_content(&mut self, content: String) -> String {
+        if !self.strip_bom && self.normalize_newlines.is_none() && !self.trim_trailing_whitespace {
+            return content;
+        }
 
-        let merged = Self::deep_merge(current_schema, new_schema);
-        self.schema = match self.schema_type {
-            CONTENT_MSGPACK => rmp_serde::to_vec(&merged)?,
-            _ => serde_json::to_vec(&merged)?,
+        let mut content = match content.strip_prefix('\u{feff}') {
+            Some(stripped) if self.strip_bom => stripped.to_string(),
+            _ => content,
         };
-        Ok(())
+        if let Some(style) = self.normalize_newlines {
+            content = content.replace("\r\n", "\n");
+            if style == NewlineStyle::CrLf {
+                content = content.replace('\n', "\r\n");
+            }
+        }
+        if self.trim_trailing_whitespace {
+            content = content
+                .split_inclusive('\n')
+                .map(|line| {
+                    let body = line.trim_end_matches('\n');
+                    let body = body.strip_suffix('\r').unwrap_or(body);
+                    let ending = &line[body.len()..];
+                    format!("{}{}", body.trim_end(), ending)
+                })
+                .collect();
+        }
+
+        self.result.insert("content".to_string(), Value::String(content.clone()));
+        content
+    }
+
+    /// Render the template with two schemas, e.g. to compare the outputs of an A/B test.
+    ///
+    /// Both renders run concurrently over their own connections; the stored
+    /// schema is not used nor modified. The stored result is that of
+    /// `schema_b` if both succeed. Renders are not coalesced by an attached
+    /// deduplicator.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema_a` - Schema of the first render (as `Value` or string)
+    /// * `schema_b` - Schema of the second render (as `Value` or string)
+    ///
+    /// # Returns
+    ///
+    /// The rendered content with `schema_a` and with `schema_b`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::InvalidSchema` if the schema of the template is
+    /// sent as a file path, or the error of the first failing render (see
+    /// `render()`).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let mut template = NeutralIpcTemplate::from_file_value("banner.ntpl", json!({})).unwrap();
+    /// let (a, b) = template
+    ///     .render_diff(json!({"data": {"variant": "a"}}), json!({"data": {"variant": "b"}}))
+    ///     .unwrap();
+    /// ```
+    pub fn render_diff(&mut self, schema_a: Value, schema_b: Value) -> Result<(String, String)> {
+        if self.schema_type == CONTENT_PATH {
+            return Err(NeutralIpcError::InvalidSchema(
+                "the schema is a file path read by the server and cannot be replaced".to_string(),
+            ));
+        }
+        #[cfg(feature = "watch")]
+        self.reload_watched_template()?;
+
+        let (schema_a, schema_b) = (Self::parse_schema(schema_a)?, Self::parse_schema(schema_b)?);
+        #[cfg(feature = "validate")]
+        {
+            self.validate(&schema_a)?;
+            self.validate(&schema_b)?;
+        }
+        let (schema_a, schema_b) = (self.encode_schema(&schema_a)?, self.encode_schema(&schema_b)?);
+
+        let this = &*self;
+        let (rendered_a, rendered_b) = thread::scope(|scope| {
+            let rendered_b = scope.spawn(|| this.request_render(&schema_b));
+            let rendered_a = this.request_render(&schema_a);
+            let rendered_b = rendered_b.join().unwrap_or_else(|_| {
+                Err(NeutralIpcError::Io(io::Error::other("render thread panicked")))
+            });
+            (rendered_a, rendered_b)
+        });
+
+        let content_a = self.store_rendered(rendered_a, schema_a.len())?;
+        let content_b = self.store_rendered(rendered_b, schema_b.len())?;
+        Ok((content_a, content_b))
+    }
+
+    /// Render the template with its schema and with an empty schema.
+    ///
+    /// Same as `render_diff` with the stored schema and `{}`, to see what the
+    /// schema contributes to the output.
+    ///
+    /// # Returns
+    ///
+    /// The rendered content with the stored schema and with the empty schema.
+    ///
+    /// # Errors
+    ///
+    /// See `render_diff`.
+    pub fn render_schema_diff(&mut self) -> Result<(String, String)> {
+        let schema = self.decode_schema()?;
+        self.render_diff(schema, Value::Object(Map::new()))
+    }
+
+    /// Wrap an error of the exchange with the server with the template it was sent for.
+    ///
+    /// File templates are identified by their path, source templates by a
+    /// short hash of the source code.
+    fn render_error(&self, err: NeutralIpcError, schema_size: usize) -> NeutralIpcError {
+        NeutralIpcError::Render { template: self.render_label(), schema_size, source: Box::new(err) }
+    }
+
+    /// Identify the template in render errors: its path, or a short hash of its source code.
+    fn render_label(&self) -> String {
+        if let Some(source) = &self.source_reader {
+            return format!("source reader of {} bytes", source.len());
+        }
+        match self.kind() {
+            TemplateKind::Path => self.template.clone(),
+            TemplateKind::Source => {
+                let mut hasher = DefaultHasher::new();
+                self.template.hash(&mut hasher);
+                format!("source {:08x}", hasher.finish() as u32)
+            }
+        }
+    }
+
+    /// Encode the render request of this template once, to send it repeatedly.
+    ///
+    /// The request is encoded as `render()` would send it now, with the
+    /// connection settings of the template for the template root, request
+    /// headers, compression and checksum. The prepared request does not
+    /// borrow the template, which can be changed or dropped afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors `render()` reports before connecting, e.g.
+    /// `NeutralIpcError::SchemaTooLarge` or `NeutralIpcError::PathTraversal`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::{NeutralIpcConfig, NeutralIpcTemplate};
+    /// use serde_json::json;
+    ///
+    /// let template = NeutralIpcTemplate::from_file_value("footer.ntpl", json!({"data": {"year": 2026}})).unwrap();
+    /// let footer = template.prepare().unwrap();
+    /// drop(template);
+    ///
+    /// let config = NeutralIpcConfig::new();
+    /// let html = footer.send(&config).unwrap().content;
+    /// ```
+    pub fn prepare(&self) -> Result<PreparedRequest> {
+        let (control, request) = self.encode_render_request(&self.connection_config())?;
+        Ok(PreparedRequest::new(control, request, self.render_label(), self.schema.len()))
+    }
+
+    /// Encode the render request of this template without sending it.
+    ///
+    /// The record is built by the same code as for `render()`, with the same
+    /// size guards, template root resolution, request headers, compression
+    /// and checksum, and returned split into its header and content blocks.
+    /// Nothing is sent; with `template_cache_negotiation` enabled, the record
+    /// is the full request sent when the server does not know the hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors `render()` reports before connecting, e.g.
+    /// `NeutralIpcError::SchemaTooLarge` or `NeutralIpcError::SchemaValidation`
+    /// (`validate` feature), and `NeutralIpcError::SourceReaderUnavailable`
+    /// for a source set with `set_source_reader`, which would be consumed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::{NeutralIpcConfig, NeutralIpcTemplate, CTRL_PARSE_TEMPLATE};
+    /// use serde_json::json;
+    ///
+    /// let template = NeutralIpcTemplate::from_src_value("{:;title:}", json!({"data": {"title": "Home"}}))
+    ///     .unwrap()
+    ///     .with_config(NeutralIpcConfig::without_file());
+    /// let dry_run = template.dry_run().unwrap();
+    ///
+    /// assert_eq!(dry_run.header.control, CTRL_PARSE_TEMPLATE);
+    /// assert_eq!(dry_run.content2, b"{:;title:}");
+    /// ```
+    pub fn dry_run(&self) -> Result<DryRun> {
+        #[cfg(feature = "validate")]
+        if self.validation_schema.is_some() && self.schema_type != CONTENT_PATH {
+            self.validate(&self.decode_schema()?)?;
+        }
+        let (_, request) = self.encode_render_request(&self.connection_config())?;
+        DryRun::from_record(&request)
+    }
+
+    /// Render the template, streaming the rendered content as it arrives.
+    ///
+    /// Sends the same request as `render()` and returns once the server has
+    /// sent the status of the render, which is stored as the result (see
+    /// `get_status_code` and similar). The rendered content is then read from
+    /// the returned stream, chunk by chunk as the server sends it, so it can be
+    /// forwarded before the render completes. The stored result does not hold
+    /// the content. Renders are not coalesced by an attached deduplicator.
+    ///
+    /// # Returns
+    ///
+    /// The stream of the rendered content.
+    ///
+    /// # Errors
+    ///
+    /// Same as `render()`; errors while reading the content are yielded by
+    /// the stream.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    /// use std::io::Write;
+    ///
+    /// let mut template = NeutralIpcTemplate::from_file_value("page.ntpl", json!({})).unwrap();
+    /// let stream = template.render_stream().unwrap();
+    /// println!("status: {}", template.get_status_code());
+    /// for chunk in stream {
+    ///     std::io::stdout().write_all(&chunk.unwrap()).unwrap();
+    /// }
+    /// ```
+    pub fn render_stream(&mut self) -> Result<RenderStream> {
+        #[cfg(feature = "watch")]
+        self.reload_watched_template()?;
+        #[cfg(feature = "validate")]
+        if self.validation_schema.is_some() && self.schema_type != CONTENT_PATH {
+            self.validate(&self.decode_schema()?)?;
+        }
+
+        let (control, mut client) = self.render_client_with(self.connection_config(), &self.schema)?;
+        let (mut record, mut stream) = match client.start_stream() {
+            Ok((record, stream)) => (record.clone(), stream),
+            Err(err) => return Err(self.render_error(err, self.schema.len())),
+        };
+        if record.get("control").and_then(|v| v.as_u64()) == Some(CTRL_STATUS_KO as u64) {
+            let diagnostics = match Iterator::by_ref(&mut stream).collect::<Result<Vec<_>>>() {
+                Ok(chunks) => chunks.concat(),
+                Err(err) => return Err(self.render_error(err, self.schema.len())),
+            };
+            record.insert("content-2".to_string(), Value::String(String::from_utf8_lossy(&diagnostics).into_owned()));
+        }
+
+        let (result, _) = Self::rendered_result(control, &record).map_err(|err| self.render_error(err, self.schema.len()))?;
+        self.result = result;
+        self.check_server_status().map_err(|err| self.render_error(err, self.schema.len()))?;
+        Ok(stream)
+    }
+
+    /// Perform the IPC round-trip for a render request.
+    ///
+    /// # Returns
+    ///
+    /// The result map to store (status, result data and content) and the rendered content.
+    fn request_render(&self, schema: &[u8]) -> Result<(HashMap<String, Value>, String)> {
+        let config = self.connection_config();
+        let (control, mut client) = self.render_client_with(config.clone(), schema)?;
+        let mut waited = 0;
+        loop {
+            let rendered = match self.request_render_by_hash(&config, control, schema) {
+                Ok(Some(rendered)) => Ok(rendered),
+                Ok(None) => client.start().and_then(|result| Self::rendered_result(control, result)),
+                Err(err) => Err(err),
+            };
+            let (result, content) = rendered.map_err(|err| self.render_error(err, schema.len()))?;
+
+            let retry_after = match Self::retry_after(&result) {
+                Some(retry_after) if config.get_respect_retry_after() => retry_after,
+                _ => return Ok((result, content)),
+            };
+            let delay = retry_after.min(config.get_max_retry_delay_ms());
+            if waited + delay > config.get_max_retry_delay_ms() {
+                return Err(self.render_error(NeutralIpcError::RateLimited { retry_after_ms: retry_after }, schema.len()));
+            }
+            thread::sleep(Duration::from_millis(delay));
+            waited += delay;
+        }
+    }
+
+    /// Get the delay the server asked to wait before retrying, if the render was rate-limited.
+    ///
+    /// A render is rate-limited when `content-1` has a `retry_after_ms` field
+    /// and the response is `CTRL_STATUS_KO` or has the status code `429`.
+    fn retry_after(result: &HashMap<String, Value>) -> Option<u64> {
+        let content1 = if result.get("status").and_then(|v| v.as_u64()) == Some(CTRL_STATUS_KO as u64) {
+            json::from_str(result.get("diagnostics")?.as_str()?).ok()?
+        } else {
+            let content1 = result.get("result")?;
+            if content1.get("status_code").and_then(|v| v.as_str()) != Some("429") {
+                return None;
+            }
+            content1.clone()
+        };
+        content1.get("retry_after_ms")?.as_u64()
     }
 
-    /// Replace the current schema with MsgPack bytes.
-    ///
-    /// This method switches the schema format to `CONTENT_MSGPACK`.
-    pub fn set_schema_msgpack(&mut self, schema: &[u8]) {
-        self.schema_type = CONTENT_MSGPACK;
-        self.schema = schema.to_vec();
+    /// Render the template asynchronously.
+    ///
+    /// Same as `render()`, but the IPC round-trip runs on the async runtime
+    /// selected by the `async-tokio` or `async-std` feature instead of
+    /// blocking the thread. Renders are not coalesced by an attached
+    /// deduplicator, and SOCKS5 proxies are not supported.
+    ///
+    /// # Errors
+    ///
+    /// Same as `render()`. A timeout is reported as `NeutralIpcError::Io` with
+    /// `std::io::ErrorKind::TimedOut`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn example() -> Result<(), neutralipcrs::NeutralIpcError> {
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let mut template = NeutralIpcTemplate::from_src_value("Hello {:;text:}!", json!({"data": {"text": "World"}}))?;
+    /// let result = template.render_async().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(any(feature = "async-tokio", feature = "async-std"))]
+    pub async fn render_async(&mut self) -> Result<String> {
+        #[cfg(feature = "watch")]
+        self.reload_watched_template()?;
+        #[cfg(feature = "validate")]
+        if self.validation_schema.is_some() && self.schema_type != CONTENT_PATH {
+            self.validate(&self.decode_schema()?)?;
+        }
+        self.require_buffered_source()?;
+        let (control, mut client) = self.render_client(&self.schema)?;
+        let rendered = match client.start_async().await {
+            Ok(result) => Self::rendered_result(control, result),
+            Err(err) => Err(err),
+        };
+        let (result, content) = rendered.map_err(|err| self.render_error(err, self.schema.len()))?;
+        self.result = result;
+        self.check_server_status().map_err(|err| self.render_error(err, self.schema.len()))?;
+        Ok(self.postprocess_content(content))
+    }
+
+    /// Create the IPC client for a render request.
+    ///
+    /// # Returns
+    ///
+    /// The control code of the request and the client ready to start.
+    #[cfg(any(feature = "async-tokio", feature = "async-std"))]
+    fn render_client(&self, schema: &[u8]) -> Result<(u8, NeutralIpcClient)> {
+        self.render_client_with(self.connection_config(), schema)
+    }
+
+    /// Get the connection settings for a request of this template.
+    fn connection_config(&self) -> NeutralIpcConfig {
+        // Not `unwrap_or_default()`: `new()` also loads the configuration file.
+        let mut config = match &self.config {
+            Some(config) => config.clone(),
+            None => NeutralIpcConfig::new(),
+        };
+        if let Some(endpoint) = self.endpoint {
+            config.set_host(endpoint.ip().to_string());
+            config.set_port(endpoint.port());
+        }
+        if let Some(timeout) = self.timeout {
+            config.set_timeout_override(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            config.set_connect_timeout(timeout);
+        }
+        if let Some(buffer_size) = self.buffer_size {
+            config.set_buffer_size(buffer_size);
+        }
+        config
+    }
+
+    /// Validate the template with the server for rendering by token.
+    ///
+    /// Sends the template once with `CTRL_VALIDATE_TEMPLATE`. The server parses
+    /// it and returns a token, which `CompiledTemplate::render` sends instead of
+    /// the template. The validation response is stored as the result of this
+    /// template.
+    ///
+    /// # Returns
+    ///
+    /// The compiled template, using the connection settings of this template.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::NotSupported` if the server responds with
+    /// `CTRL_STATUS_KO`, `NeutralIpcError::InvalidResponse` if the response has
+    /// no token, or an error if IPC communication fails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let mut template = NeutralIpcTemplate::from_file_value("page.ntpl", json!({})).unwrap();
+    /// let compiled = template.compile().unwrap();
+    ///
+    /// let html = compiled.render(json!({"data": {"title": "Home"}})).unwrap();
+    /// ```
+    pub fn compile(&mut self) -> Result<CompiledTemplate> {
+        let mut config = self.connection_config();
+        if self.template_root.is_some() {
+            config.set_template_root(self.template_root.clone());
+        }
+        let mut client = NeutralIpcClient::with_config(
+            config.clone(),
+            CTRL_VALIDATE_TEMPLATE,
+            self.schema_type,
+            &self.schema,
+            self.tpl_type,
+            self.template.as_bytes()
+        )?;
+        let result = client.start()?;
+
+        let status = result.get("control")
+            .and_then(|v| v.as_u64())
+            .ok_or(NeutralIpcError::InvalidResponse)? as u8;
+        if status == CTRL_STATUS_KO {
+            return Err(NeutralIpcError::NotSupported);
+        }
+
+        let content1 = result.get("content-1")
+            .and_then(|v| v.as_str())
+            .ok_or(NeutralIpcError::InvalidResponse)?;
+        let validation = json::from_str(content1)
+            .map_err(|error| NeutralIpcError::invalid_result_json(error, content1))?;
+        let token = validation.get("token")
+            .and_then(|v| v.as_str())
+            .ok_or(NeutralIpcError::InvalidResponse)?
+            .to_string();
+
+        self.result = HashMap::from([
+            ("status".to_string(), Value::Number(status.into())),
+            ("result".to_string(), validation),
+        ]);
+        Ok(CompiledTemplate { token, config })
+    }
+
+    /// Create the IPC client for a render request using the given connection settings.
+    ///
+    /// The template root of this template, if any, overrides the one in `config`.
+    fn render_client_with(&self, mut config: NeutralIpcConfig, schema: &[u8]) -> Result<(u8, NeutralIpcClient)> {
+        let limit = match self.max_schema_size {
+            Some(limit) => Some(limit).filter(|limit| *limit > 0),
+            None => config.get_max_schema_size(),
+        };
+        check_schema_size(&config, schema.len(), limit)?;
+        if self.strict_schema_layout || cfg!(feature = "logging") {
+            let decoded = match self.schema_type {
+                CONTENT_JSON => json::from_slice(schema).ok(),
+                CONTENT_MSGPACK => rmp_serde::from_slice(schema).ok(),
+                _ => None,
+            };
+            if let Some(schema) = decoded {
+                self.check_schema_layout(&schema)?;
+            }
+        }
+
+        let template_size = self.source_reader.as_ref().map_or(self.template.len() as u64, |source| source.len());
+        if template_size > u32::MAX as u64 {
+            return Err(NeutralIpcError::TemplateTooLarge { size: template_size, limit: u32::MAX as u64 });
+        }
+
+        if self.template_root.is_some() {
+            config.set_template_root(self.template_root.clone());
+        }
+
+        let (control, content1) = self.request_content(schema)?;
+        let mut client = NeutralIpcClient::with_config(
+            config,
+            control,
+            self.schema_type,
+            &content1,
+            self.tpl_type,
+            self.template.as_bytes()
+        )?;
+        if let Some(source) = &self.source_reader {
+            client.set_source_reader(Arc::clone(source));
+        }
+
+        Ok((control, client))
+    }
+
+    /// Render a source template by the hash of its source, if negotiated.
+    ///
+    /// # Returns
+    ///
+    /// The rendered result, or `None` if the template must be sent in full:
+    /// negotiation is disabled, the request `control` is not a plain
+    /// `CTRL_PARSE_TEMPLATE`, or the server answers `CTRL_STATUS_KO` because
+    /// it does not know the hash or the control code.
+    fn request_render_by_hash(&self, config: &NeutralIpcConfig, control: u8, schema: &[u8]) -> Result<Option<(HashMap<String, Value>, String)>> {
+        if !config.get_template_cache_negotiation()
+            || self.tpl_type != CONTENT_TEXT
+            || control != CTRL_PARSE_TEMPLATE
+            || self.source_reader.is_some()
+        {
+            return Ok(None);
+        }
+
+        let hash: String = Sha256::digest(self.template.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect();
+        let mut client = NeutralIpcClient::with_config(
+            config.clone(),
+            CTRL_PARSE_TEMPLATE_HASH,
+            self.schema_type,
+            schema,
+            CONTENT_TEXT,
+            hash.as_bytes()
+        )?;
+        let result = client.start()?;
+
+        match result.get("control").and_then(|v| v.as_u64()) {
+            Some(status) if status == CTRL_STATUS_KO as u64 => Ok(None),
+            _ => Self::rendered_result(CTRL_PARSE_TEMPLATE, result).map(Some),
+        }
+    }
+
+    /// Encode the render request record of this template for a pipelined connection.
+    ///
+    /// # Returns
+    ///
+    /// The control code of the request and the encoded record.
+    pub(crate) fn encode_render_request(&self, config: &NeutralIpcConfig) -> Result<(u8, Vec<u8>)> {
+        self.require_buffered_source()?;
+        let (control, client) = self.render_client_with(config.clone(), &self.schema)?;
+        Ok((control, client.encode_request()))
+    }
+
+    /// Store the decoded response record of a render request sent with `control`.
+    ///
+    /// # Returns
+    ///
+    /// The rendered content.
+    pub(crate) fn store_render_response(&mut self, control: u8, record: &HashMap<String, Value>) -> Result<String> {
+        let (result, content) = Self::rendered_result(control, record)?;
+        self.result = result;
+        self.check_server_status()?;
+        Ok(self.postprocess_content(content))
+    }
+
+    /// Fail with `NeutralIpcError::ServerError` if the stored result is a
+    /// `CTRL_STATUS_KO` response to a schema sent as a file path, or to any
+    /// render with `set_fail_on_server_error` enabled.
+    ///
+    /// For a schema sent as a file path the error names the path, as the
+    /// server could not render with the schema at all.
+    fn check_server_status(&self) -> Result<()> {
+        if !self.fail_on_server_error && self.schema_type != CONTENT_PATH {
+            return Ok(());
+        }
+        let diagnostics = match self.last_server_diagnostics() {
+            Some(diagnostics) => diagnostics,
+            None => return Ok(()),
+        };
+        let message = if diagnostics.message.is_empty() { "no diagnostics" } else { diagnostics.message.as_str() };
+
+        Err(NeutralIpcError::ServerError(if self.schema_type == CONTENT_PATH {
+            format!("schema path '{}': {}", String::from_utf8_lossy(&self.schema), message)
+        } else {
+            message.to_string()
+        }))
+    }
+
+    /// Get the diagnostics of the last render, if the server answered it with `CTRL_STATUS_KO`.
+    ///
+    /// Available after such a render, whether it returned its content (the
+    /// default) or failed with `NeutralIpcError::ServerError` (see
+    /// `set_fail_on_server_error`), until the next render. Diagnostics that
+    /// are not JSON are kept as text.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let mut template = NeutralIpcTemplate::from_src_value("{:;text:}", json!({})).unwrap();
+    /// template.render().unwrap();
+    /// if let Some(diagnostics) = template.last_server_diagnostics() {
+    ///     eprintln!("server error {:?}: {}", diagnostics.code, diagnostics.message);
+    /// }
+    /// ```
+    pub fn last_server_diagnostics(&self) -> Option<ServerDiagnostics> {
+        if self.result.get("status").and_then(|v| v.as_u64()) != Some(CTRL_STATUS_KO as u64) {
+            return None;
+        }
+        let raw = self.result.get("diagnostics").and_then(|v| v.as_str()).unwrap_or("");
+        Some(ServerDiagnostics::parse(raw))
+    }
+
+    /// Convert the decoded response record of a render request into the result to store.
+    ///
+    /// # Returns
+    ///
+    /// The result map (status, result data, content and response headers) and the rendered content.
+    /// For a `CTRL_STATUS_KO` response the map holds the status and the raw
+    /// diagnostics instead, and the content is empty.
+    pub(crate) fn rendered_result(control: u8, result: &HashMap<String, Value>) -> Result<(HashMap<String, Value>, String)> {
+        let status = result.get("control")
+            .and_then(|v| v.as_u64())
+            .ok_or(NeutralIpcError::InvalidResponse)? as u8;
+
+        let content1 = result.get("content-1")
+            .and_then(|v| v.as_str())
+            .ok_or(NeutralIpcError::InvalidResponse)?;
+
+        let content2 = result.get("content-2")
+            .and_then(|v| v.as_str())
+            .ok_or(NeutralIpcError::InvalidResponse)?;
+
+        let mut rendered = HashMap::new();
+        if status == CTRL_STATUS_KO {
+            let diagnostics = match content1.trim() {
+                "" | "{}" => content2,
+                _ => content1,
+            };
+            // Diagnostics are often plain text, so `content-1` is kept as the result only if it is JSON.
+            let result_data = json::from_str(content1).unwrap_or(Value::Null);
+            rendered.insert("status".to_string(), Value::Number(status.into()));
+            rendered.insert("diagnostics".to_string(), Value::String(diagnostics.to_string()));
+            rendered.insert("result".to_string(), result_data);
+            rendered.insert("content".to_string(), Value::String(content2.to_string()));
+            return Ok((rendered, content2.to_string()));
+        }
+        let content1 = if control == CTRL_PARSE_TEMPLATE_WITH_HEADERS {
+            let (headers, content1) = NeutralIpcRecord::split_headers(content1)?;
+            let headers = headers.into_iter().map(|(key, value)| (key, Value::String(value))).collect();
+            rendered.insert("headers".to_string(), Value::Object(headers));
+            content1
+        } else {
+            content1
+        };
+
+        let result_data = json::from_str(content1)
+            .map_err(|error| NeutralIpcError::invalid_result_json(error, content1))?;
+        rendered.insert("status".to_string(), Value::Number(status.into()));
+        rendered.insert("result".to_string(), result_data);
+        rendered.insert("content".to_string(), Value::String(content2.to_string()));
+        if let Some(endpoint) = result.get("endpoint") {
+            rendered.insert("endpoint".to_string(), endpoint.clone());
+        }
+
+        Ok((rendered, content2.to_string()))
+    }
+
+    /// Build the control code and `content-1` for a render request.
+    ///
+    /// Without request headers this is `CTRL_PARSE_TEMPLATE` with the schema;
+    /// otherwise `CTRL_PARSE_TEMPLATE_WITH_HEADERS` with the schema prefixed by
+    /// the header lines.
+    fn request_content(&self, schema: &[u8]) -> Result<(u8, Vec<u8>)> {
+        if self.request_headers.is_empty() {
+            Ok((CTRL_PARSE_TEMPLATE, schema.to_vec()))
+        } else {
+            let content = NeutralIpcRecord::encode_headers(&self.request_headers, schema)?;
+            Ok((CTRL_PARSE_TEMPLATE_WITH_HEADERS, content))
+        }
+    }
+
+    /// Set a request header sent to the server with the next renders.
+    ///
+    /// Header names are compared case-insensitively: setting an existing
+    /// header replaces its value. While any header is set, renders use
+    /// `CTRL_PARSE_TEMPLATE_WITH_HEADERS`, which the server must support.
+    /// Names must not be empty or contain `:`, and neither names nor values
+    /// may contain line breaks; otherwise rendering fails with
+    /// `NeutralIpcError::InvalidRequestHeader`.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Header name, e.g. `"X-Request-Id"`
+    /// * `value` - Header value
+    pub fn set_request_header(&mut self, key: &str, value: &str) {
+        match self.request_headers.iter_mut().find(|(name, _)| name.eq_ignore_ascii_case(key)) {
+            Some((_, current)) => *current = value.to_string(),
+            None => self.request_headers.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    /// Remove all request headers, so renders use `CTRL_PARSE_TEMPLATE` again.
+    pub fn clear_request_headers(&mut self) {
+        self.request_headers.clear();
+    }
+
+    /// Get the response headers returned by the server in the last render.
+    ///
+    /// Response headers are only returned for renders sent with request headers.
+    ///
+    /// # Returns
+    ///
+    /// The `(name, value)` pairs, or an empty vector if there are none.
+    pub fn get_response_headers(&self) -> Vec<(String, String)> {
+        match self.result.get("headers") {
+            Some(Value::Object(headers)) => headers
+                .iter()
+                .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Use the given connection settings instead of loading the configuration file.
+    ///
+    /// By default every render loads `NeutralIpcConfig::new()`. With this,
+    /// renders use `config` instead, e.g. one built with `NeutralIpcConfig::builder()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The connection settings for this template
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::{NeutralIpcConfig, NeutralIpcTemplate};
+    /// use serde_json::json;
+    ///
+    /// let config = NeutralIpcConfig::builder().port(4274).no_file().build().unwrap();
+    /// let template = NeutralIpcTemplate::from_src_value("{:;text:}", json!({}))
+    ///     .unwrap()
+    ///     .with_config(config);
+    /// ```
+    pub fn with_config(mut self, config: NeutralIpcConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Pin the server this template is rendered by.
+    ///
+    /// Every request of this template connects to `addr` instead of the host
+    /// and port of the configuration, e.g. to try a new server build with a
+    /// few templates. A pinned server that fails makes the render fail; no
+    /// other server is tried. Through a SOCKS5 proxy, `addr` is the target
+    /// the proxy connects to.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - Address of the server
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let mut template = NeutralIpcTemplate::from_src_value("{:;text:}", json!({})).unwrap();
+    /// template.force_endpoint("10.0.0.7:4273".parse().unwrap());
+    /// template.render().unwrap();
+    ///
+    /// assert_eq!(template.get_endpoint(), Some("10.0.0.7:4273".parse().unwrap()));
+    /// ```
+    pub fn force_endpoint(&mut self, addr: SocketAddr) {
+        self.endpoint = Some(addr);
+    }
+
+    /// Remove the server pinned with `force_endpoint`.
+    pub fn clear_forced_endpoint(&mut self) {
+        self.endpoint = None;
+    }
+
+    /// Get the address of the server that handled the last render.
+    ///
+    /// # Returns
+    ///
+    /// The peer address of the connection of the last blocking render, which
+    /// is the proxy address when connecting through a SOCKS5 proxy, or `None`
+    /// if there is no such render.
+    pub fn get_endpoint(&self) -> Option<SocketAddr> {
+        self.result.get("endpoint")?.as_str()?.parse().ok()
+    }
+
+    /// Attach a deduplicator that coalesces identical concurrent renders.
+    ///
+    /// Templates sharing the same deduplicator and rendering the same template
+    /// with the same schema at the same time perform a single IPC round-trip.
+    ///
+    /// # Arguments
+    ///
+    /// * `deduplicator` - The shared deduplicator
+    #[cfg(feature = "dedup")]
+    pub fn with_deduplicator(mut self, deduplicator: Arc<NeutralIpcDeduplicator>) -> Self {
+        self.deduplicator = Some(deduplicator);
+        self
+    }
+
+    /// Compute the key identifying a render request for deduplication.
+    ///
+    /// Includes the resolved server address, proxy and timeouts, so requests
+    /// bound for different servers or with different timeouts are not merged.
+    #[cfg(feature = "dedup")]
+    fn request_key(&self, schema: &[u8]) -> u64 {
+        let config = self.connection_config();
+        let mut hasher = DefaultHasher::new();
+        CTRL_PARSE_TEMPLATE.hash(&mut hasher);
+        self.request_headers.hash(&mut hasher);
+        self.schema_type.hash(&mut hasher);
+        schema.hash(&mut hasher);
+        self.tpl_type.hash(&mut hasher);
+        self.template.hash(&mut hasher);
+        self.template_root.hash(&mut hasher);
+        config.get_host().hash(&mut hasher);
+        config.get_port().hash(&mut hasher);
+        #[cfg(feature = "proxy")]
+        config.get_proxy().hash(&mut hasher);
+        config.get_timeout_duration().hash(&mut hasher);
+        config.get_connect_timeout().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Render the template and parse the output as JSON.
+    ///
+    /// For templates generating JSON documents, e.g. API responses.
+    ///
+    /// # Returns
+    ///
+    /// The rendered content parsed as a JSON value.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::Json` if the rendered content is not valid
+    /// JSON, or an error if rendering fails (see `render()`).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let mut template = NeutralIpcTemplate::from_src_value(r#"{"id": {:;id:}}"#, json!({"data": {"id": 7}})).unwrap();
+    /// let value = template.render_as_json().unwrap();
+    ///
+    /// assert_eq!(value["id"], 7);
+    /// ```
+    pub fn render_as_json(&mut self) -> Result<Value> {
+        let content = self.render()?;
+        Ok(json::from_str(&content)?)
+    }
+
+    /// Render the template and pretty-print the output as JSON.
+    ///
+    /// # Returns
+    ///
+    /// The rendered content re-serialized with indentation.
+    ///
+    /// # Errors
+    ///
+    /// See `render_as_json()`.
+    pub fn render_as_json_pretty(&mut self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.render_as_json()?)?)
+    }
+
+    /// Render the template and count the words in the output.
+    ///
+    /// Words are whitespace-delimited. The count can be retrieved again later
+    /// with `last_render_word_count()` without re-rendering.
+    ///
+    /// # Returns
+    ///
+    /// The number of words in the rendered content.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if rendering fails (see `render()`).
+    pub fn render_count_words(&mut self) -> Result<usize> {
+        let content = self.render()?;
+        Ok(content.split_whitespace().count())
+    }
+
+    /// Get the word count of the content from the last rendering operation.
+    ///
+    /// # Returns
+    ///
+    /// The number of whitespace-delimited words, or `None` if nothing has been rendered yet.
+    pub fn last_render_word_count(&self) -> Option<usize> {
+        self.result.get("content")
+            .and_then(|v| v.as_str())
+            .map(|content| content.split_whitespace().count())
+    }
+
+    /// Render the template and return a truncated preview of the content.
+    ///
+    /// The content is cut after `max_chars` Unicode scalar values (not bytes),
+    /// so a multi-byte character is never split, and `"…"` is appended when
+    /// anything was cut.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_chars` - Maximum number of characters kept from the content
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if rendering fails (see `render()`).
+    pub fn preview(&mut self, max_chars: usize) -> Result<String> {
+        let content = self.render()?;
+        Ok(Self::truncate_chars(&content, max_chars))
+    }
+
+    /// Get a truncated preview of the content from the last rendering operation.
+    ///
+    /// Same as `preview`, without re-rendering.
+    ///
+    /// # Returns
+    ///
+    /// The truncated content, or `None` if nothing has been rendered yet.
+    pub fn preview_cached(&self, max_chars: usize) -> Option<String> {
+        self.result.get("content")
+            .and_then(|v| v.as_str())
+            .map(|content| Self::truncate_chars(content, max_chars))
+    }
+
+    /// Keep the first `max_chars` characters of `content`, appending `"…"` if cut.
+    fn truncate_chars(content: &str, max_chars: usize) -> String {
+        match content.char_indices().nth(max_chars) {
+            Some((end, _)) => format!("{}…", &content[..end]),
+            None => content.to_string(),
+        }
+    }
+
+    /// Build a shell command sending the render request of this template with `nc`.
+    ///
+    /// The command writes the exact bytes of the encoded IPC record with
+    /// `printf`, as POSIX octal escapes (`\NNN`) since `\x` escapes are not
+    /// portable, and pipes them to `nc` (or `ncat`) connected to the configured
+    /// server, so a request can be reproduced outside the crate when
+    /// reporting bugs. The header and body sizes are given in a comment line.
+    /// Debug helper: the whole request, schema included, is embedded in the
+    /// command.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request cannot be built (e.g. an invalid
+    /// request header or a schema over the size limit).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let template = NeutralIpcTemplate::from_src_value("Hello {:;text:}!", json!({})).unwrap();
+    /// println!("{}", template.to_netcat_command().unwrap());
+    /// ```
+    pub fn to_netcat_command(&self) -> Result<String> {
+        let config = self.connection_config();
+        let (_, request) = self.encode_render_request(&config)?;
+        let header_len = if config.get_checksum() { HEADER_LEN_V1 } else { HEADER_LEN };
+        let (header, body) = request.split_at(header_len.min(request.len()));
+        let escape = |bytes: &[u8]| bytes.iter().map(|byte| format!("\\{:03o}", byte)).collect::<String>();
+
+        Ok(format!(
+            "# header: {} bytes, body: {} bytes\nprintf '{}''{}' | nc {} {}",
+            header.len(),
+            body.len(),
+            escape(header),
+            escape(body),
+            config.get_host(),
+            config.get_port()
+        ))
+    }
+
+    /// Format the render request of this template as a hex dump.
+    ///
+    /// One line per 16 bytes with the offset, the bytes in hex and their
+    /// printable ASCII characters, like `hexdump -C`. Debug helper, see
+    /// `to_netcat_command`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request cannot be built (see `to_netcat_command`).
+    pub fn to_hex_dump(&self) -> Result<String> {
+        let (_, request) = self.encode_render_request(&self.connection_config())?;
+        let mut dump = String::new();
+        for (line, bytes) in request.chunks(16).enumerate() {
+            let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+            let ascii: String = bytes.iter()
+                .map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' })
+                .collect();
+            let (left, right) = hex.split_at(hex.len().min(8));
+            dump.push_str(&format!("{:08x}  {:<23}  {:<23}  |{}|\n", line * 16, left.join(" "), right.join(" "), ascii));
+        }
+        Ok(dump)
+    }
+
+    /// List the template files known to the Neutral server.
+    ///
+    /// Sends a `CTRL_LIST_TEMPLATES` request and parses the response `content-1`
+    /// as a JSON array of file path strings.
+    ///
+    /// # Returns
+    ///
+    /// The list of template file paths reported by the server.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - IPC communication with the server fails
+    /// - The server responds with `CTRL_STATUS_KO` (`NeutralIpcError::NotSupported`)
+    /// - The response is not a JSON array of strings
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    ///
+    /// let templates = NeutralIpcTemplate::list_templates().unwrap();
+    /// for path in templates {
+    ///     println!("{}", path);
+    /// }
+    /// ```
+    pub fn list_templates() -> Result<Vec<String>> {
+        let mut client = NeutralIpcClient::new(
+            CTRL_LIST_TEMPLATES,
+            CONTENT_JSON,
+            b"{}",
+            CONTENT_TEXT,
+            b""
+        )?;
+
+        let result = client.start()?;
+
+        let status = result.get("control")
+            .and_then(|v| v.as_u64())
+            .ok_or(NeutralIpcError::InvalidResponse)? as u8;
+
+        if status == CTRL_STATUS_KO {
+            return Err(NeutralIpcError::NotSupported);
+        }
+
+        let content1 = result.get("content-1")
+            .and_then(|v| v.as_str())
+            .ok_or(NeutralIpcError::InvalidResponse)?;
+
+        let templates: Vec<String> = serde_json::from_str(content1)
+            .map_err(|error| NeutralIpcError::invalid_result_json(error, content1))?;
+
+        Ok(templates)
+    }
+
+    /// Ask the Neutral server to reload its configuration.
+    ///
+    /// Sends a `CTRL_RELOAD_CONFIG` request with the settings of the
+    /// configuration file, so updated server settings are applied without a
+    /// restart. See `reload_server_config_with` for other settings.
+    ///
+    /// The server only accepts the command from clients it trusts: the calling
+    /// process must have the credentials the server expects for it (e.g. run
+    /// as the user allowed by the server configuration).
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::PermissionDenied` if the server refuses the
+    /// command for lack of privileges, `NeutralIpcError::ServerError` if it
+    /// fails for any other reason, or an error if IPC communication fails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::{NeutralIpcError, NeutralIpcTemplate};
+    ///
+    /// match NeutralIpcTemplate::reload_server_config() {
+    ///     Ok(()) => println!("server configuration reloaded"),
+    ///     Err(NeutralIpcError::PermissionDenied(reason)) => eprintln!("not allowed: {}", reason),
+    ///     Err(err) => eprintln!("{}", err),
+    /// }
+    /// ```
+    pub fn reload_server_config() -> Result<()> {
+        Self::reload_server_config_with(&NeutralIpcConfig::new())
+    }
+
+    /// Ask the Neutral server to reload its configuration, using the given connection settings.
+    ///
+    /// Same as `reload_server_config`. A refusal is reported as
+    /// `NeutralIpcError::PermissionDenied` when the diagnostics `code` of the
+    /// `CTRL_STATUS_KO` response is `permission_denied`, `EACCES`, `EPERM` or `403`.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Connection settings of the server
+    ///
+    /// # Errors
+    ///
+    /// See `reload_server_config`.
+    pub fn reload_server_config_with(config: &NeutralIpcConfig) -> Result<()> {
+        let mut client = NeutralIpcClient::with_config(config.clone(), CTRL_RELOAD_CONFIG, CONTENT_JSON, b"{}", CONTENT_TEXT, b"")?;
+        let result = client.start()?;
+
+        let status = result.get("control")
+            .and_then(|v| v.as_u64())
+            .ok_or(NeutralIpcError::InvalidResponse)? as u8;
+        match status {
+            CTRL_STATUS_OK => return Ok(()),
+            CTRL_STATUS_KO => {}
+            _ => return Err(NeutralIpcError::InvalidResponse),
+        }
+
+        let content = |key: &str| result.get(key).and_then(|v| v.as_str()).unwrap_or("");
+        let diagnostics = ServerDiagnostics::parse(match content("content-1").trim() {
+            "" | "{}" => content("content-2"),
+            content1 => content1,
+        });
+        let message = if diagnostics.message.is_empty() { "no diagnostics".to_string() } else { diagnostics.message };
+        match diagnostics.code.as_deref() {
+            Some("permission_denied" | "EACCES" | "EPERM" | "403") => Err(NeutralIpcError::PermissionDenied(message)),
+            _ => Err(NeutralIpcError::ServerError(message)),
+        }
+    }
+
+    /// Get the kind of template, file path or source code.
+    pub fn kind(&self) -> TemplateKind {
+        match self.tpl_type {
+            CONTENT_TEXT => TemplateKind::Source,
+            _ => TemplateKind::Path,
+        }
+    }
+
+    /// Get the template file path.
+    ///
+    /// # Returns
+    ///
+    /// The file path if the template is file-based, `None` otherwise.
+    pub fn path(&self) -> Option<&str> {
+        match self.kind() {
+            TemplateKind::Path => Some(&self.template),
+            TemplateKind::Source => None,
+        }
+    }
+
+    /// Get the template source code.
+    ///
+    /// # Returns
+    ///
+    /// The source code if the template is source-based, `None` otherwise.
+    pub fn source(&self) -> Option<&str> {
+        match self.kind() {
+            TemplateKind::Source => Some(&self.template),
+            TemplateKind::Path => None,
+        }
+    }
+
+    /// Set the template to use a file path.
+    ///
+    /// Changes the template type to `CONTENT_PATH` and updates the template content
+    /// to the specified file path. The result of the last rendering is discarded.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the template file
+    pub fn set_path(&mut self, path: &str) {
+        self.tpl_type = CONTENT_PATH;
+        self.template = path.to_string();
+        self.source_reader = None;
+        self.result.clear();
+        #[cfg(feature = "watch")]
+        self.stop_watching_template();
+    }
+
+    /// Set the template to use source code directly.
+    ///
+    /// Changes the template type to `CONTENT_TEXT` and updates the template content
+    /// to the provided source code string. The result of the last rendering is
+    /// discarded.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Template source code
+    pub fn set_source(&mut self, source: &str) {
+        self.tpl_type = CONTENT_TEXT;
+        self.template = source.to_string();
+        self.source_reader = None;
+        self.result.clear();
+        #[cfg(feature = "watch")]
+        self.stop_watching_template();
+    }
+
+    /// Watch the template file and reload it when it changes.
+    ///
+    /// While watching, a render after the file was modified reads the file
+    /// and sends its content as source code, switching the template to source
+    /// mode, so edits take effect without restarting the process. Until the
+    /// first change the server keeps reading the file by path. The path is
+    /// resolved against the template root, if any, and must be readable by
+    /// this process. Setting another path or source stops watching.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::Watch` if the template is not a file path or
+    /// the watcher cannot be set up, or `NeutralIpcError::PathTraversal` if the
+    /// path is outside the template root.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let mut template = NeutralIpcTemplate::from_file_value("/srv/templates/home.ntpl", json!({})).unwrap();
+    /// template.watch_template_file().unwrap();
+    ///
+    /// // Edits to home.ntpl are picked up by the next render.
+    /// let html = template.render().unwrap();
+    /// ```
+    #[cfg(feature = "watch")]
+    pub fn watch_template_file(&mut self) -> Result<()> {
+        use notify::{RecursiveMode, Watcher};
+
+        if self.tpl_type != CONTENT_PATH {
+            return Err(NeutralIpcError::Watch("the template is not a file path".to_string()));
+        }
+        let mut config = self.connection_config();
+        if self.template_root.is_some() {
+            config.set_template_root(self.template_root.clone());
+        }
+        let path = std::path::absolute(crate::client::resolve_template_path(&config, &self.template)?)?;
+        let dir = path.parent().unwrap_or(Path::new("/")).to_path_buf();
+
+        // Watch the directory rather than the file, so that files replaced by
+        // editors on save are still seen.
+        let dirty = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&dirty);
+        let file = path.clone();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event {
+                if !event.kind.is_access() && event.paths.iter().any(|changed| changed == &file) {
+                    flag.store(true, Ordering::SeqCst);
+                }
+            }
+        })
+        .map_err(|err| NeutralIpcError::Watch(err.to_string()))?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|err| NeutralIpcError::Watch(err.to_string()))?;
+
+        self.watch = Some(TemplateWatch { _watcher: watcher, path, dirty });
+        Ok(())
+    }
+
+    /// Stop watching the template file.
+    ///
+    /// The template keeps its current path or reloaded source.
+    #[cfg(feature = "watch")]
+    pub fn stop_watching_template(&mut self) {
+        self.watch = None;
+    }
+
+    /// Read the watched template file as source code if it changed.
+    #[cfg(feature = "watch")]
+    fn reload_watched_template(&mut self) -> Result<()> {
+        let Some(watch) = &self.watch else {
+            return Ok(());
+        };
+        if watch.dirty.swap(false, Ordering::SeqCst) {
+            match fs::read_to_string(&watch.path) {
+                Ok(source) => {
+                    self.tpl_type = CONTENT_TEXT;
+                    self.template = source;
+                }
+                Err(err) => {
+                    watch.dirty.store(true, Ordering::SeqCst);
+                    return Err(err.into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the template source code read from a reader.
+    ///
+    /// Reads the reader to its end in chunks of the configured read buffer
+    /// size (see `NeutralIpcConfig::set_read_buffer_size`) and stores the
+    /// source as with `set_source`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Reader providing the UTF-8 template source code
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::Io` if reading fails, or
+    /// `NeutralIpcError::InvalidUtf8` if the source is not valid UTF-8. The
+    /// template is left unchanged on error.
+    pub fn set_source_from_reader<R: Read>(&mut self, mut reader: R) -> Result<()> {
+        let chunk_size = self.config.as_ref().map_or_else(
+            || NeutralIpcConfig::default().get_read_buffer_size(),
+            NeutralIpcConfig::get_read_buffer_size,
+        );
+        let mut chunk = vec![0u8; chunk_size.max(1)];
+        let mut source = Vec::new();
+        loop {
+            match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(read) => source.extend_from_slice(&chunk[..read]),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        let source = String::from_utf8(source).map_err(NeutralIpcError::InvalidUtf8)?;
+        self.set_source(&source);
+        Ok(())
+    }
+
+    /// Stream the template source code from a reader when rendering.
+    ///
+    /// Unlike `set_source_from_reader`, the source is not held in memory: the
+    /// next render writes the record header with the declared length and then
+    /// copies the bytes of the reader to the server in chunks of the write
+    /// buffer size, e.g. for generated templates of several megabytes. The
+    /// reader is consumed by that render; set the source again before the
+    /// next one. With checksums enabled the source is read in full before
+    /// sending, as the checksum precedes it.
+    ///
+    /// Only `render()` and the renders built on it stream the source.
+    /// Template cache negotiation and an attached deduplicator are skipped,
+    /// and requests encoded in advance (`prepare`, pipelines, `render_async`,
+    /// `to_netcat_command`) fail with `NeutralIpcError::SourceReaderUnavailable`.
+    /// The result of the last rendering is discarded.
+    ///
+    /// # Arguments
+    ///
+    /// * `len` - Length of the source in bytes
+    /// * `reader` - Reader yielding exactly `len` bytes of template source code
+    ///
+    /// # Errors
+    ///
+    /// Rendering fails with `NeutralIpcError::TemplateTooLarge` if `len` does
+    /// not fit in a record, or with `NeutralIpcError::ContentLengthMismatch`,
+    /// wrapped in `NeutralIpcError::Render`, if the reader yields more or fewer
+    /// bytes than `len`; the connection is closed without a response then.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    /// use std::fs::File;
+    ///
+    /// let file = File::open("generated.ntpl").unwrap();
+    /// let len = file.metadata().unwrap().len();
+    ///
+    /// let mut template = NeutralIpcTemplate::from_src_value("", json!({"data": {}})).unwrap();
+    /// template.set_source_reader(len, Box::new(file));
+    /// let result = template.render().unwrap();
+    /// ```
+    pub fn set_source_reader(&mut self, len: u64, reader: Box<dyn Read + Send>) {
+        self.tpl_type = CONTENT_TEXT;
+        self.template = String::new();
+        self.source_reader = Some(Arc::new(SourceReader::new(len, reader)));
+        self.result.clear();
+        #[cfg(feature = "watch")]
+        self.stop_watching_template();
+    }
+
+    /// Fail if the template source is streamed from a reader, which requests encoded in advance cannot carry.
+    fn require_buffered_source(&self) -> Result<()> {
+        match self.source_reader {
+            Some(_) => Err(NeutralIpcError::SourceReaderUnavailable(
+                "a streamed source cannot be encoded in advance".to_string(),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Set the template source code from its base64 encoding.
+    ///
+    /// Decodes `encoded` (standard alphabet, with padding) and stores the raw
+    /// UTF-8 source as with `set_source`. Useful for templates embedded in
+    /// YAML or TOML files without escaping.
+    ///
+    /// # Arguments
+    ///
+    /// * `encoded` - The base64-encoded template source
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::Base64` if `encoded` is not valid base64, or
+    /// `NeutralIpcError::InvalidUtf8` if the decoded source is not valid UTF-8.
+    /// The template is left unchanged on error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcTemplate;
+    ///
+    /// let mut template = NeutralIpcTemplate::new().unwrap();
+    /// template.set_source_base64("SGVsbG8gezo7dGV4dDp9IQ==").unwrap();
+    ///
+    /// assert_eq!(template.source(), Some("Hello {:;text:}!"));
+    /// ```
+    pub fn set_source_base64(&mut self, encoded: &str) -> Result<()> {
+        let source = String::from_utf8(BASE64.decode(encoded.trim())?).map_err(NeutralIpcError::InvalidUtf8)?;
+        self.set_source(&source);
+        Ok(())
+    }
+
+    /// Get the base64 encoding of the template source code.
+    ///
+    /// # Returns
+    ///
+    /// The source encoded with the standard base64 alphabet, or `None` if the
+    /// template is a file path.
+    pub fn get_source_as_base64(&self) -> Option<String> {
+        self.source().map(|source| BASE64.encode(source))
+    }
+
+    /// Set the base directory for relative template paths.
+    ///
+    /// Overrides the `template_root` of the configuration for this template.
+    /// Relative file-path templates are resolved against the root before they
+    /// are sent, and paths escaping it fail with `NeutralIpcError::PathTraversal`
+    /// (see `NeutralIpcConfig::set_template_root`).
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The base directory for relative template paths
+    pub fn set_template_root(&mut self, root: &Path) {
+        self.template_root = Some(root.to_path_buf());
+    }
+
+    /// Set the maximum schema size for this template.
+    ///
+    /// Overrides the `max_schema_size` of the configuration. Renders whose
+    /// serialized schema is larger than the limit fail with
+    /// `NeutralIpcError::SchemaTooLarge` before connecting to the server, or
+    /// only warn with `SchemaSizePolicy::Warn`.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The limit in bytes; zero means unlimited, `None` uses the configuration
+    pub fn set_max_schema_size(&mut self, limit: Option<usize>) {
+        self.max_schema_size = limit;
+    }
+
+    /// Set the socket timeout for this template.
+    ///
+    /// Overrides the `timeout` of the configuration for the renders of this
+    /// template only: each phase of a request (connect, every write and every
+    /// read) must complete within it. The `total_timeout` of the
+    /// configuration still applies.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The per-phase timeout; zero is rejected and ignored
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    /// use std::time::Duration;
+    ///
+    /// let mut dashboard = NeutralIpcTemplate::from_file_value("dashboard.ntpl", json!({})).unwrap();
+    /// dashboard.set_timeout(Duration::from_secs(60));
+    /// dashboard.set_buffer_size(1024 * 1024);
+    /// ```
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        if !timeout.is_zero() {
+            self.timeout = Some(timeout);
+        }
+    }
+
+    /// Set the connect timeout for this template.
+    ///
+    /// Overrides the timeout of the connect phase only; without it the
+    /// connection uses the per-phase timeout (see `set_timeout`).
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The connect timeout; zero is rejected and ignored
+    pub fn set_connect_timeout(&mut self, timeout: Duration) {
+        if !timeout.is_zero() {
+            self.connect_timeout = Some(timeout);
+        }
+    }
+
+    /// Set the read and write buffer size for this template.
+    ///
+    /// Overrides the buffer sizes of the configuration for the renders of
+    /// this template only.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer_size` - The buffer size in bytes; zero is rejected and ignored
+    pub fn set_buffer_size(&mut self, buffer_size: usize) {
+        if buffer_size > 0 {
+            self.buffer_size = Some(buffer_size);
+        }
+    }
+
+    /// Remove a leading UTF-8 byte order mark from the rendered content.
+    ///
+    /// Like the other output options, applies to the content returned by
+    /// `render()` (and the other renders storing a result) and to the stored
+    /// content, but not to `render_stream()`. Disabled by default, so the
+    /// content is returned exactly as the server sent it.
+    ///
+    /// # Arguments
+    ///
+    /// * `strip` - `true` to remove the BOM
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::{NewlineStyle, NeutralIpcTemplate};
+    /// use serde_json::json;
+    ///
+    /// let mut legacy = NeutralIpcTemplate::from_file_value("legacy.ntpl", json!({})).unwrap();
+    /// legacy.set_strip_bom(true);
+    /// legacy.set_normalize_newlines(Some(NewlineStyle::Lf));
+    /// legacy.set_trim_trailing_whitespace(true);
+    /// ```
+    pub fn set_strip_bom(&mut self, strip: bool) {
+        self.strip_bom = strip;
+    }
+
+    /// Convert the line endings of the rendered content.
+    ///
+    /// `\r\n` and `\n` line endings are converted to `style`; lone `\r`
+    /// characters are kept. See `set_strip_bom` for the renders it applies to.
+    ///
+    /// # Arguments
+    ///
+    /// * `style` - The line endings to convert to, or `None` to keep them (default)
+    pub fn set_normalize_newlines(&mut self, style: Option<NewlineStyle>) {
+        self.normalize_newlines = style;
+    }
+
+    /// Remove trailing whitespace from every line of the rendered content.
+    ///
+    /// Line endings are kept. See `set_strip_bom` for the renders it applies to.
+    ///
+    /// # Arguments
+    ///
+    /// * `trim` - `true` to trim the lines
+    pub fn set_trim_trailing_whitespace(&mut self, trim: bool) {
+        self.trim_trailing_whitespace = trim;
+    }
+
+    /// Remove the timeout and buffer size overrides of this template.
+    ///
+    /// Later renders use the values of the configuration again.
+    pub fn clear_connection_overrides(&mut self) {
+        self.timeout = None;
+        self.connect_timeout = None;
+        self.buffer_size = None;
+    }
+
+    /// Get the size of the serialized schema in bytes.
+    ///
+    /// This is the size sent to the server (before any compression) and the
+    /// size compared against the maximum schema size.
+    pub fn schema_size(&self) -> usize {
+        self.schema.len()
+    }
+
+    /// Set the template source code from an environment variable.
+    ///
+    /// Reads the named variable and calls `set_source` with its value.
+    ///
+    /// # Arguments
+    ///
+    /// * `var_name` - Name of the environment variable holding the template source
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::EnvVarNotFound` if the variable is not set, or
+    /// `NeutralIpcError::InvalidUtf8` if its value is not valid UTF-8.
+    pub fn set_template_from_env(&mut self, var_name: &str) -> Result<()> {
+        let source = Self::read_env(var_name)?;
+        self.set_source(&source);
+        Ok(())
+    }
+
+    /// Replace the current schema with JSON from an environment variable.
+    ///
+    /// The schema format is switched to `CONTENT_JSON`.
+    ///
+    /// # Arguments
+    ///
+    /// * `var_name` - Name of the environment variable holding the JSON schema
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::EnvVarNotFound` if the variable is not set,
+    /// `NeutralIpcError::InvalidUtf8` if its value is not valid UTF-8, or an
+    /// error if the value is not valid JSON.
+    pub fn set_schema_from_env(&mut self, var_name: &str) -> Result<()> {
+        self.check_unsealed()?;
+        let schema = Self::read_env(var_name)?;
+        json::from_str(&schema)?;
+        self.schema_type = CONTENT_JSON;
+        self.schema = schema.into_bytes();
+        Ok(())
+    }
+
+    /// Merge new schema data with the existing schema.
+    ///
+    /// This method performs a deep merge of JSON objects, allowing you to
+    /// incrementally build up complex schemas. For objects with overlapping
+    /// keys, the new values will override the existing ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - New schema data to merge (as `Value` or string)
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the merge was successful, or an error if schema parsing fails.
+    /// A string that is not valid JSON is reported as
+    /// `NeutralIpcError::InvalidSchemaJson` with the start of the string.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let mut template = NeutralIpcTemplate::new().unwrap();
+    /// template.merge_schema(json!({"data": {"value": 1}})).unwrap();
+    /// template.merge_schema(json!({"data": {"extra": 2}})).unwrap();
+    /// // Schema now contains: {"base": {"value": 1, "extra": 2}}
+    /// ```
+    pub fn merge_schema(&mut self, schema: Value) -> Result<()> {
+        self.check_unsealed()?;
+        let current_schema = self.decode_schema()?;
+        let new_schema = Self::parse_schema(schema)?;
+
+        let merged = deep_merge(current_schema, new_schema, MergeStrategy::Replace);
+        self.check_schema_layout(&merged)?;
+        self.schema = self.encode_schema(&merged)?;
+        Ok(())
+    }
+
+    /// Set the locale the template formats numbers, dates and currencies for.
+    ///
+    /// The locale is stored in the schema under the top-level key
+    /// `_neutral_locale`, next to `data` and the other sections, so it cannot
+    /// clash with user keys; the key is always allowed by
+    /// `set_strict_schema_layout`. The locale is a BCP 47 language tag such as
+    /// `"en-US"` or `"es"`; it is passed on as is, not validated.
+    ///
+    /// # Arguments
+    ///
+    /// * `locale` - BCP 47 language tag
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::SchemaSealedError` if the schema is sealed,
+    /// or an error if the schema cannot be decoded or encoded (e.g. a schema
+    /// given as a file path).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let mut template = NeutralIpcTemplate::from_src_value("", json!({"data": {}})).unwrap();
+    /// template.set_locale("en-US").unwrap();
+    ///
+    /// assert_eq!(template.get_locale().as_deref(), Some("en-US"));
+    /// assert!(template.schema_contains(&json!({"data": {}, "_neutral_locale": "en-US"})).unwrap());
+    /// ```
+    pub fn set_locale(&mut self, locale: &str) -> Result<()> {
+        self.check_unsealed()?;
+        let mut schema = self.decode_schema()?;
+        match &mut schema {
+            Value::Object(sections) => {
+                sections.insert(LOCALE_KEY.to_string(), Value::String(locale.to_string()));
+            }
+            _ => return Err(NeutralIpcError::InvalidSchema("the schema is not a JSON object".to_string())),
+        }
+        self.schema = self.encode_schema(&schema)?;
+        Ok(())
+    }
+
+    /// Get the locale set with `set_locale`, if any.
+    ///
+    /// # Returns
+    ///
+    /// The `_neutral_locale` string of the schema, or `None` if it is not set
+    /// or the schema cannot be decoded.
+    pub fn get_locale(&self) -> Option<String> {
+        self.decode_schema().ok()?.get(LOCALE_KEY)?.as_str().map(str::to_string)
+    }
+
+    /// Reject schemas with top-level keys outside the allowed sections.
+    ///
+    /// The server ignores any other top-level key, so such keys usually are
+    /// data merged at the wrong level, e.g. next to `data` instead of inside it.
+    /// Keys are checked by `merge_schema` and before every render.
+    ///
+    /// When disabled (default), unexpected keys are logged as warnings with
+    /// the `logging` feature and otherwise ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `strict` - `true` to fail with `NeutralIpcError::SchemaLayout`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::{NeutralIpcError, NeutralIpcTemplate};
+    /// use serde_json::json;
+    ///
+    /// let mut template = NeutralIpcTemplate::from_src_value("{:;title:}", json!({"data": {}})).unwrap();
+    /// template.set_strict_schema_layout(true);
+    ///
+    /// let err = template.merge_schema(json!({"title": "Home"})).unwrap_err();
+    /// assert!(matches!(err, NeutralIpcError::SchemaLayout(keys) if keys == ["title"]));
+    /// ```
+    pub fn set_strict_schema_layout(&mut self, strict: bool) {
+        self.strict_schema_layout = strict;
+    }
+
+    /// Set whether renders answered with `CTRL_STATUS_KO` fail.
+    ///
+    /// By default such a render returns `Ok` with the content sent by the
+    /// server, and the failure is read from `has_error()`,
+    /// `get_status_code()` and `last_server_diagnostics()`. When enabled,
+    /// the render fails with `NeutralIpcError::ServerError` carrying the
+    /// diagnostic message instead; the result is still stored. Renders with
+    /// a schema sent as a file path always fail, as the server could not
+    /// read the schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `fail` - `true` to fail with `NeutralIpcError::ServerError`
+    pub fn set_fail_on_server_error(&mut self, fail: bool) {
+        self.fail_on_server_error = fail;
+    }
+
+    /// Set the allowed top-level schema sections.
+    ///
+    /// Replaces the default sections `data`, `config`, `inherit` and
+    /// `version`, e.g. for servers reading additional sections. See
+    /// `set_strict_schema_layout`.
+    ///
+    /// # Arguments
+    ///
+    /// * `sections` - Names of the allowed top-level keys
+    pub fn set_schema_sections(&mut self, sections: &[&str]) {
+        self.schema_sections = Some(sections.iter().map(|section| section.to_string()).collect());
+    }
+
+    /// Check the top-level keys of a schema against the allowed sections.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::SchemaLayout` with the unexpected keys if the
+    /// layout is strict; otherwise they are only logged.
+    fn check_schema_layout(&self, schema: &Value) -> Result<()> {
+        let Value::Object(sections) = schema else {
+            return Ok(());
+        };
+        let unexpected: Vec<String> = sections
+            .keys()
+            .filter(|key| key.as_str() != LOCALE_KEY)
+            .filter(|key| match &self.schema_sections {
+                Some(allowed) => !allowed.contains(key),
+                None => !DEFAULT_SCHEMA_SECTIONS.contains(&key.as_str()),
+            })
+            .cloned()
+            .collect();
+        if unexpected.is_empty() {
+            return Ok(());
+        }
+        if self.strict_schema_layout {
+            return Err(NeutralIpcError::SchemaLayout(unexpected));
+        }
+        #[cfg(feature = "logging")]
+        log::warn!("schema keys outside the known sections are ignored by the server: {}", unexpected.join(", "));
+        Ok(())
+    }
+
+    /// Merge the schema from a JSON file with the existing schema.
+    ///
+    /// The file content is deep-merged as with `merge_schema`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to a JSON file containing schema data
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::SchemaFileIo` if the file cannot be read,
+    /// `NeutralIpcError::SchemaFileJson` if it does not contain valid JSON
+    /// (with line and column), or an error if the merge fails.
+    pub fn merge_schema_file(&mut self, path: &Path) -> Result<()> {
+        self.check_unsealed()?;
+        let schema = Self::read_schema_file(path)?;
+        self.merge_schema(schema)
+    }
+
+    /// Merge the schemas from several JSON files, in order.
+    ///
+    /// Later files override values from earlier ones. Merging stops at the
+    /// first file that fails; files merged before it remain applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - Paths to JSON files containing schema data
+    ///
+    /// # Errors
+    ///
+    /// See `merge_schema_file`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use std::path::PathBuf;
+    ///
+    /// let mut template = NeutralIpcTemplate::new().unwrap();
+    /// template.merge_schema_files(&[
+    ///     PathBuf::from("schema/site.json"),
+    ///     PathBuf::from("schema/locale-es.json"),
+    /// ]).unwrap();
+    /// ```
+    pub fn merge_schema_files(&mut self, paths: &[PathBuf]) -> Result<()> {
+        for path in paths {
+            self.merge_schema_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Inherit the schema of a parent template.
+    ///
+    /// The schema of `parent` is deep-merged under the schema of this template,
+    /// so values of this template take precedence over those of the parent.
+    /// Only the schema is taken from `parent`; its template, settings and the
+    /// result of its last rendering are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `parent` - Template holding the base schema
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::SchemaSealedError` if the schema of this
+    /// template is sealed, or `NeutralIpcError::InvalidSchema` if either schema
+    /// is a file path read by the server.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let base = NeutralIpcTemplate::from_src_value("", json!({
+    ///     "config": {"locale": "en"},
+    ///     "data": {"site": "Example", "title": "Example"}
+    /// })).unwrap();
+    ///
+    /// let mut page = NeutralIpcTemplate::from_file_value("home.ntpl", json!({"data": {"title": "Home"}})).unwrap();
+    /// page.extend_schema(&base).unwrap();
+    ///
+    /// assert!(page.schema_contains(&json!({
+    ///     "config": {"locale": "en"},
+    ///     "data": {"site": "Example", "title": "Home"}
+    /// })).unwrap());
+    /// ```
+    pub fn extend_schema(&mut self, parent: &NeutralIpcTemplate) -> Result<()> {
+        self.check_unsealed()?;
+        let parent_schema = parent.decode_schema()?;
+        let current_schema = self.decode_schema()?;
+
+        let merged = deep_merge(parent_schema, current_schema, MergeStrategy::Replace);
+        self.schema = self.encode_schema(&merged)?;
+        Ok(())
+    }
+
+    /// Merge the schema of another template with the existing schema.
+    ///
+    /// Same as `merge_schema` with the schema of `other`: its values override
+    /// those of this template. See `extend_schema` for the reverse direction.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - Template whose schema is merged
+    ///
+    /// # Errors
+    ///
+    /// See `extend_schema`.
+    pub fn merge_from_other(&mut self, other: &NeutralIpcTemplate) -> Result<()> {
+        self.check_unsealed()?;
+        let other_schema = other.decode_schema()?;
+        let current_schema = self.decode_schema()?;
+
+        let merged = deep_merge(current_schema, other_schema, MergeStrategy::Replace);
+        self.schema = self.encode_schema(&merged)?;
+        Ok(())
+    }
+
+    /// Merge YAML schema data with the existing schema.
+    ///
+    /// The YAML document is converted to JSON, with anchors and merge keys
+    /// resolved, and deep-merged as with `merge_schema`.
+    ///
+    /// # Arguments
+    ///
+    /// * `yaml` - YAML document containing schema data
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::SchemaParse` if the document cannot be parsed,
+    /// or an error if the merge fails.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcTemplate;
+    ///
+    /// let mut template = NeutralIpcTemplate::new().unwrap();
+    /// template.merge_schema_yaml("data:\n  text: Hello\n").unwrap();
+    /// ```
+    #[cfg(feature = "yaml")]
+    pub fn merge_schema_yaml(&mut self, yaml: &str) -> Result<()> {
+        let schema = crate::formats::from_yaml_str(yaml)?;
+        self.merge_schema(schema)
+    }
+
+    /// Merge the schema from a YAML file with the existing schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to a YAML file containing schema data
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::SchemaFileIo` if the file cannot be read, or
+    /// see `merge_schema_yaml`.
+    #[cfg(feature = "yaml")]
+    pub fn merge_schema_yaml_file(&mut self, path: &Path) -> Result<()> {
+        let yaml = Self::read_schema_source(path)?;
+        self.merge_schema_yaml(&yaml)
+    }
+
+    /// Merge TOML schema data with the existing schema.
+    ///
+    /// The TOML document is converted to JSON, with datetimes as strings, and
+    /// deep-merged as with `merge_schema`.
+    ///
+    /// # Arguments
+    ///
+    /// * `toml` - TOML document containing schema data
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::SchemaParse` if the document cannot be parsed,
+    /// or an error if the merge fails.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcTemplate;
+    ///
+    /// let mut template = NeutralIpcTemplate::new().unwrap();
+    /// template.merge_schema_toml("[data]\ntext = \"Hello\"\n").unwrap();
+    /// ```
+    #[cfg(feature = "toml")]
+    pub fn merge_schema_toml(&mut self, toml: &str) -> Result<()> {
+        let schema = crate::formats::from_toml_str(toml)?;
+        self.merge_schema(schema)
+    }
+
+    /// Merge the schema from a TOML file with the existing schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to a TOML file containing schema data
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::SchemaFileIo` if the file cannot be read, or
+    /// see `merge_schema_toml`.
+    #[cfg(feature = "toml")]
+    pub fn merge_schema_toml_file(&mut self, path: &Path) -> Result<()> {
+        let toml = Self::read_schema_source(path)?;
+        self.merge_schema_toml(&toml)
+    }
+
+    /// Merge the JSON schema fetched from a URL with the existing schema.
+    ///
+    /// The document at `url` (HTTP or HTTPS) is fetched with the
+    /// `schema_fetch_timeout_secs` of the template configuration and
+    /// deep-merged as with `merge_schema`. With `schema_fetch_cache` enabled,
+    /// responses carrying an `ETag` are cached and revalidated on later
+    /// fetches of the same URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - URL of a JSON document
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::SchemaFetchError` if the request fails, the
+    /// server answers with an error status or the response is not JSON, or
+    /// an error if the merge fails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let mut template = NeutralIpcTemplate::from_file_value("page.ntpl", json!({})).unwrap();
+    /// template.merge_schema_from_url("https://config.internal/site.json").unwrap();
+    /// ```
+    #[cfg(feature = "http-schema")]
+    pub fn merge_schema_from_url(&mut self, url: &str) -> Result<()> {
+        self.check_unsealed()?;
+        let schema = crate::http_schema::fetch_schema(url, &self.connection_config())?;
+        self.merge_schema(schema)
+    }
+
+    /// Remove every schema cached by `merge_schema_from_url`.
+    #[cfg(feature = "http-schema")]
+    pub fn clear_schema_fetch_cache() {
+        crate::http_schema::clear_cache();
+    }
+
+    /// Append items to an array in the schema.
+    ///
+    /// Unlike `merge_schema`, which replaces arrays, this method navigates the
+    /// schema by dot-notation path (e.g. `"data.items"`) and appends the given
+    /// items to the array found there. Missing objects along the path and the
+    /// array itself are created as needed; existing values, even `null` or an
+    /// empty object, are never replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Dot-separated path to the array
+    /// * `items` - JSON array of items to append (any other value is appended as a single item)
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::SchemaMergeConflict` if the path points to a
+    /// non-array value or crosses a non-object value, or an error if the schema
+    /// cannot be decoded or encoded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let mut template = NeutralIpcTemplate::from_src_value("", json!({"data": {"items": [1]}})).unwrap();
+    /// template.append_schema_array("data.items", json!([2, 3])).unwrap();
+    /// // Schema now contains: {"data": {"items": [1, 2, 3]}}
+    /// ```
+    pub fn append_schema_array(&mut self, path: &str, items: Value) -> Result<()> {
+        self.check_unsealed()?;
+        let mut schema = self.decode_schema()?;
+
+        let mut node = &mut schema;
+        let mut current_path = String::new();
+        let mut keys = path.split('.').peekable();
+        while let Some(key) = keys.next() {
+            if !current_path.is_empty() {
+                current_path.push('.');
+            }
+            current_path.push_str(key);
+
+            // Only missing keys are created: an existing `null` or `{}` is a conflict like any other value.
+            let missing = if keys.peek().is_some() { Value::Object(Map::new()) } else { Value::Array(Vec::new()) };
+            node = match node {
+                Value::Object(map) => map.entry(key).or_insert(missing),
+                _ => return Err(NeutralIpcError::SchemaMergeConflict(current_path)),
+            };
+        }
+
+        match (node, items) {
+            (Value::Array(array), Value::Array(items)) => array.extend(items),
+            (Value::Array(array), item) => array.push(item),
+            _ => return Err(NeutralIpcError::SchemaMergeConflict(path.to_string())),
+        }
+
+        self.schema = self.encode_schema(&schema)?;
+        Ok(())
+    }
+
+    /// Compute the differences between this template's schema and another one.
+    ///
+    /// The comparison is recursive over JSON objects. Nested keys are reported
+    /// as dot-separated paths (e.g. `"data.text"`). The returned object has the
+    /// following members:
+    /// - `removed`: keys present in `self` but not in `other`, with their values
+    /// - `added`: keys present in `other` but not in `self`, with their values
+    /// - `changed`: keys present in both with different values, as `{"from": .., "to": ..}`
+    ///
+    /// A schema that cannot be decoded is treated as `null`.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The template whose schema is compared against this one
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let a = NeutralIpcTemplate::from_src_value("", json!({"data": {"text": "a", "old": 1}})).unwrap();
+    /// let b = NeutralIpcTemplate::from_src_value("", json!({"data": {"text": "b", "new": 2}})).unwrap();
+    ///
+    /// let diff = a.schema_diff(&b);
+    /// assert_eq!(diff["removed"]["data.old"], 1);
+    /// assert_eq!(diff["added"]["data.new"], 2);
+    /// assert_eq!(diff["changed"]["data.text"], json!({"from": "a", "to": "b"}));
+    /// ```
+    pub fn schema_diff(&self, other: &NeutralIpcTemplate) -> Value {
+        let from = self.decode_schema().unwrap_or(Value::Null);
+        let to = other.decode_schema().unwrap_or(Value::Null);
+
+        let mut removed = Map::new();
+        let mut added = Map::new();
+        let mut changed = Map::new();
+        Self::diff_values("", &from, &to, &mut removed, &mut added, &mut changed);
+
+        let mut diff = Map::new();
+        diff.insert("removed".to_string(), Value::Object(removed));
+        diff.insert("added".to_string(), Value::Object(added));
+        diff.insert("changed".to_string(), Value::Object(changed));
+        Value::Object(diff)
+    }
+
+    /// Compare the schemas of two templates structurally.
+    ///
+    /// Both schemas are decoded before comparing, so key order and the
+    /// encoding (JSON or MsgPack) do not matter. A schema that cannot be
+    /// decoded is never equal to anything.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The template whose schema is compared against this one
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcTemplate;
+    ///
+    /// let a = NeutralIpcTemplate::from_src_value("", r#"{"data": {"a": 1, "b": 2}}"#.into()).unwrap();
+    /// let b = NeutralIpcTemplate::from_src_value("", r#"{"data": {"b": 2, "a": 1}}"#.into()).unwrap();
+    ///
+    /// assert!(a.schema_equals(&b));
+    /// ```
+    pub fn schema_equals(&self, other: &NeutralIpcTemplate) -> bool {
+        match (self.decode_schema(), other.decode_schema()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// Check whether the schema contains the given values.
+    ///
+    /// Objects in `subset` match when every key is present in the schema at
+    /// the same location with a matching value; any other value, including
+    /// arrays, must be equal.
+    ///
+    /// # Arguments
+    ///
+    /// * `subset` - The keys and values expected in the schema
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schema cannot be decoded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let template = NeutralIpcTemplate::from_src_value("", json!({"data": {"text": "Hi", "n": 1}})).unwrap();
+    ///
+    /// assert!(template.schema_contains(&json!({"data": {"text": "Hi"}})).unwrap());
+    /// assert!(!template.schema_contains(&json!({"data": {"text": "Bye"}})).unwrap());
+    /// ```
+    pub fn schema_contains(&self, subset: &Value) -> Result<bool> {
+        Ok(Self::contains_value(&self.decode_schema()?, subset))
+    }
+
+    /// Infer the JSON type of every leaf value in the schema.
+    ///
+    /// Objects are walked recursively and their leaves are reported by
+    /// dot-separated path (e.g. `"data.text"`). Arrays and empty objects are
+    /// leaves. The types are `"string"`, `"number"`, `"boolean"`, `"null"`,
+    /// `"array"` and `"object"`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schema cannot be decoded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let template = NeutralIpcTemplate::from_src_value("", json!({"data": {"text": "Hi", "ids": [1]}})).unwrap();
+    /// let types = template.infer_schema_types().unwrap();
+    ///
+    /// assert_eq!(types["data.text"], "string");
+    /// assert_eq!(types["data.ids"], "array");
+    /// ```
+    pub fn infer_schema_types(&self) -> Result<HashMap<String, String>> {
+        let schema = self.decode_schema()?;
+        let mut types = HashMap::new();
+        Self::collect_types("", &schema, &mut types);
+        Ok(types)
+    }
+
+    /// List the dot-separated paths of every leaf value in the schema.
+    ///
+    /// Objects and arrays are walked recursively; array elements are addressed
+    /// by index (e.g. `"data.items.0.name"`). Empty objects and arrays are
+    /// leaves. The paths are sorted lexicographically.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schema cannot be decoded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let template = NeutralIpcTemplate::from_src_value("", json!({
+    ///     "data": {"text": "Hi", "items": [{"name": "a"}]}
+    /// })).unwrap();
+    ///
+    /// assert_eq!(template.get_schema_keys().unwrap(), ["data.items.0.name", "data.text"]);
+    /// ```
+    pub fn get_schema_keys(&self) -> Result<Vec<String>> {
+        Ok(self.get_schema_leaf_values()?.into_iter().map(|(path, _)| path).collect())
+    }
+
+    /// List the path and value of every leaf value in the schema.
+    ///
+    /// Same as `get_schema_keys`, with the value found at each path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schema cannot be decoded.
+    pub fn get_schema_leaf_values(&self) -> Result<Vec<(String, Value)>> {
+        let mut leaves: Vec<_> = self.iter_schema_values()?.collect();
+        leaves.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(leaves)
+    }
+
+    /// Iterate over the path and value of every leaf value in the schema.
+    ///
+    /// Same leaves as `get_schema_leaf_values`, yielded as the schema tree is
+    /// walked instead of collected and sorted first, so a caller looking for
+    /// a few fields can stop early. Objects and non-empty arrays are not
+    /// yielded themselves, only their leaves.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schema cannot be decoded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let template = NeutralIpcTemplate::from_src_value("", json!({
+    ///     "data": {"user": {"name": "Ana", "admin": true}, "title": "Home"}
+    /// })).unwrap();
+    ///
+    /// let flags: Vec<_> = template.iter_schema_values().unwrap().filter(|(_, value)| value.is_boolean()).collect();
+    /// assert_eq!(flags, [("data.user.admin".to_string(), json!(true))]);
+    /// ```
+    pub fn iter_schema_values(&self) -> Result<impl Iterator<Item = (String, Value)>> {
+        self.iter_schema_values_at("")
+    }
+
+    /// Iterate over the leaf values of a sub-tree of the schema.
+    ///
+    /// Same as `iter_schema_values`, restricted to the value at the
+    /// dot-separated `prefix` (array elements by index). Yielded paths are
+    /// full paths, starting with `prefix`. A prefix naming a leaf yields that
+    /// leaf only, and a prefix that does not exist yields nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - Dot-separated path of the sub-tree, e.g. `"data.user"`;
+    ///   empty for the whole schema
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schema cannot be decoded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let template = NeutralIpcTemplate::from_src_value("", json!({
+    ///     "data": {"user": {"name": "Ana", "roles": ["admin"]}, "title": "Home"}
+    /// })).unwrap();
+    ///
+    /// let paths: Vec<_> = template.iter_schema_values_at("data.user").unwrap().map(|(path, _)| path).collect();
+    /// assert_eq!(paths, ["data.user.name", "data.user.roles.0"]);
+    /// ```
+    pub fn iter_schema_values_at(&self, prefix: &str) -> Result<impl Iterator<Item = (String, Value)>> {
+        let mut value = self.decode_schema()?;
+        if !prefix.is_empty() {
+            for key in prefix.split('.') {
+                let child = match &mut value {
+                    Value::Object(map) => map.remove(key),
+                    Value::Array(items) => key
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|index| *index < items.len())
+                        .map(|index| items.swap_remove(index)),
+                    _ => None,
+                };
+                match child {
+                    Some(child) => value = child,
+                    None => return Ok(SchemaLeaves { stack: Vec::new() }),
+                }
+            }
+        }
+        Ok(SchemaLeaves { stack: vec![(prefix.to_string(), value)] })
+    }
+
+    /// Convert string values that are valid JSON numbers into numbers.
+    ///
+    /// The whole schema is walked, including arrays. A string is converted only
+    /// if it is exactly a JSON number literal, so values such as `"007"`,
+    /// `" 12"` or `"1,5"` are left as strings.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schema cannot be decoded or encoded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let mut template = NeutralIpcTemplate::from_src_value("", json!({"data": {"page": "2", "zip": "007"}})).unwrap();
+    /// template.coerce_schema_numbers().unwrap();
+    /// // Schema now contains: {"data": {"page": 2, "zip": "007"}}
+    /// ```
+    pub fn coerce_schema_numbers(&mut self) -> Result<()> {
+        self.check_unsealed()?;
+        let mut schema = self.decode_schema()?;
+        Self::coerce_numbers(&mut schema);
+        self.schema = self.encode_schema(&schema)?;
+        Ok(())
+    }
+
+    /// Replace the current schema with a typed schema.
+    ///
+    /// The schema is serialized with serde and must serialize to a JSON object.
+    /// It is stored in the current schema format (JSON or MsgPack).
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - Any value implementing `Serialize`, typically a struct
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schema cannot be serialized or does not
+    /// serialize to a JSON object (`NeutralIpcError::InvalidSchema`).
+    pub fn replace_schema_typed<T: Serialize>(&mut self, schema: &T) -> Result<()> {
+        self.check_unsealed()?;
+        let schema = Self::typed_schema(schema)?;
+        if self.schema_type == CONTENT_PATH {
+            self.schema_type = CONTENT_JSON;
+        }
+        self.schema = self.encode_schema(&schema)?;
+        Ok(())
+    }
+
+    /// Replace the current schema with MsgPack bytes.
+    ///
+    /// This method switches the schema format to `CONTENT_MSGPACK`. It does
+    /// nothing while the schema is sealed, see `try_set_schema_msgpack`.
+    pub fn set_schema_msgpack(&mut self, schema: &[u8]) {
+        let _ = self.try_set_schema_msgpack(schema);
+    }
+
+    /// Same as `set_schema_msgpack`, failing if the schema is sealed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::SchemaSealedError` if the schema is sealed.
+    pub fn try_set_schema_msgpack(&mut self, schema: &[u8]) -> Result<()> {
+        self.check_unsealed()?;
+        self.schema_type = CONTENT_MSGPACK;
+        self.schema = schema.to_vec();
+        Ok(())
+    }
+
+    /// Replace the current schema with the path of a JSON schema file read by the server.
+    ///
+    /// The path is sent instead of the schema, switching the schema format to
+    /// `CONTENT_PATH`, which saves sending large schema files that the server
+    /// can read from a shared filesystem. The schema is then not available to
+    /// the client: merging, comparing or inspecting it fails with
+    /// `NeutralIpcError::InvalidSchema` until it is replaced again, and schema
+    /// validation (`validate` feature) is skipped. If the server cannot read
+    /// the file, rendering fails with `NeutralIpcError::ServerError` carrying
+    /// the diagnostics of the server, wrapped in `NeutralIpcError::Render`.
+    ///
+    /// Does nothing while the schema is sealed, see `try_set_schema_path`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the schema file, as seen by the server
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    ///
+    /// let mut template = NeutralIpcTemplate::new().unwrap();
+    /// template.set_path("catalog.ntpl");
+    /// template.set_schema_path("/shared/schemas/catalog.json");
+    /// let html = template.render().unwrap();
+    /// ```
+    pub fn set_schema_path(&mut self, path: &str) {
+        let _ = self.try_set_schema_path(path);
+    }
+
+    /// Same as `set_schema_path`, failing if the schema is sealed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::SchemaSealedError` if the schema is sealed.
+    pub fn try_set_schema_path(&mut self, path: &str) -> Result<()> {
+        self.check_unsealed()?;
+        self.schema_type = CONTENT_PATH;
+        self.schema = path.as_bytes().to_vec();
+        Ok(())
+    }
+
+    /// Set the content type sent for the schema, keeping the schema bytes.
+    ///
+    /// Allows content types defined by a server implementation, with
+    /// `ContentFormat::Other`. The schema bytes are sent unchanged; merging or
+    /// inspecting the schema treats any type other than `CONTENT_MSGPACK` and
+    /// `CONTENT_PATH` as JSON. Does nothing while the schema is sealed, see
+    /// `try_set_schema_content_type`.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - Content type of the schema bytes
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::{ContentFormat, NeutralIpcTemplate};
+    ///
+    /// let mut template = NeutralIpcTemplate::new().unwrap();
+    /// template.set_schema_content_type(ContentFormat::Other(70));
+    /// ```
+    pub fn set_schema_content_type(&mut self, format: ContentFormat) {
+        let _ = self.try_set_schema_content_type(format);
+    }
+
+    /// Same as `set_schema_content_type`, failing if the schema is sealed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NeutralIpcError::SchemaSealedError` if the schema is sealed.
+    pub fn try_set_schema_content_type(&mut self, format: ContentFormat) -> Result<()> {
+        self.check_unsealed()?;
+        self.schema_type = format.into();
+        Ok(())
+    }
+
+    /// Protect the schema from modification.
+    ///
+    /// While sealed, every method that changes the stored schema (merging,
+    /// appending, coercing or replacing it) fails with
+    /// `NeutralIpcError::SchemaSealedError` and leaves it unchanged; the
+    /// infallible setters, such as `set_schema_msgpack`, do nothing. Rendering,
+    /// including `render_with_override`, which does not store its overrides,
+    /// is unaffected. Useful for libraries handing out templates built on a
+    /// base schema that consumers must not change.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralipcrs::{NeutralIpcError, NeutralIpcTemplate};
+    /// use serde_json::json;
+    ///
+    /// let mut template = NeutralIpcTemplate::from_src_value("{:;site:}", json!({"data": {"site": "Example"}})).unwrap();
+    /// template.seal_schema();
+    ///
+    /// let err = template.merge_schema(json!({"data": {"site": "Other"}})).unwrap_err();
+    /// assert!(matches!(err, NeutralIpcError::SchemaSealedError));
+    /// ```
+    pub fn seal_schema(&mut self) {
+        self.schema_sealed = true;
+    }
+
+    /// Allow the schema to be modified again after `seal_schema`.
+    pub fn unseal_schema(&mut self) {
+        self.schema_sealed = false;
+    }
+
+    /// Check whether the schema is sealed.
+    pub fn is_schema_sealed(&self) -> bool {
+        self.schema_sealed
+    }
+
+    /// Fail with `NeutralIpcError::SchemaSealedError` if the schema is sealed.
+    fn check_unsealed(&self) -> Result<()> {
+        if self.schema_sealed {
+            Err(NeutralIpcError::SchemaSealedError)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Set the content type sent for the template, keeping the template.
+    ///
+    /// Allows content types defined by a server implementation, with
+    /// `ContentFormat::Other`. Templates of any type other than `CONTENT_TEXT`
+    /// are of kind `TemplateKind::Path`. The result of the last rendering is
+    /// discarded.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - Content type of the template
+    pub fn set_template_content_type(&mut self, format: ContentFormat) {
+        self.tpl_type = format.into();
+        self.result.clear();
+    }
+
+    /// Check if the last rendering operation resulted in an error.
+    ///
+    /// This method examines the result from the last `render()` call and
+    /// determines if an error occurred based on:
+    /// - The status code (non-zero indicates error)
+    /// - The `has_error` field in the result data
+    ///
+    /// # Returns
+    ///
+    /// `true` if an error occurred, `false` otherwise.
+    pub fn has_error(&self) -> bool {
+        if let Some(status) = self.result.get("status").and_then(|v| v.as_u64()) {
+            if status != 0 {
+                return true;
+            }
+        }
+
+        if let Some(result) = self.result.get("result") {
+            if let Some(has_error) = result.get("has_error").and_then(|v| v.as_bool()) {
+                return has_error;
+            }
+        }
+
+        false
+    }
+
+    /// Get the status code from the last rendering result.
+    ///
+    /// # Returns
+    ///
+    /// The status code as `&str` from the JSON, or an empty string if not present or if
+    /// any error occurs during extraction.
+    pub fn get_status_code(&self) -> &str {
+        self.result.get("result")
+            .and_then(|r| r.get("status_code"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+    }
+
+    /// Get the status text from the last rendering result.
+    ///
+    /// # Returns
+    ///
+    /// The status text as `&str` from the JSON, or an empty string reference
+    /// if not present or if any error occurs during extraction.
+    pub fn get_status_text(&self) -> &str {
+        self.result.get("result")
+            .and_then(|r| r.get("status_text"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+    }
+
+    /// Get the status parameter from the last rendering result.
+    ///
+    /// # Returns
+    ///
+    /// The status parameter as `&str` from the JSON, or an empty string reference
+    /// if not present or if any error occurs during extraction.
+    pub fn get_status_param(&self) -> &str {
+        self.result.get("result")
+            .and_then(|r| r.get("status_param"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+    }
+
+    /// Get the redirect produced by the last rendering result.
+    ///
+    /// # Returns
+    ///
+    /// A `Redirect` if the status code is 3xx and the status parameter holds a
+    /// location, or `None` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use neutralipcrs::NeutralIpcTemplate;
+    /// use serde_json::json;
+    ///
+    /// let mut template = NeutralIpcTemplate::from_src_value("{:redirect; 301 >> /home :}", json!({})).unwrap();
+    /// template.render().unwrap();
+    ///
+    /// if let Some(redirect) = template.redirect() {
+    ///     println!("Location: {}", redirect.location);
+    /// }
+    /// ```
+    pub fn redirect(&self) -> Option<Redirect> {
+        if !self.is_redirect() {
+            return None;
+        }
+
+        let location = self.get_status_param();
+        if location.is_empty() {
+            return None;
+        }
+
+        let code = self.status_code_number()?;
+        Some(Redirect {
+            code,
+            location: location.to_string(),
+            permanent: code == 301 || code == 308,
+        })
+    }
+
+    /// Check if the last rendering result has a redirection (3xx) status code.
+    pub fn is_redirect(&self) -> bool {
+        matches!(self.status_code_number(), Some(300..=399))
+    }
+
+    /// Check if the last rendering result has a client error (4xx) status code.
+    pub fn is_client_error(&self) -> bool {
+        matches!(self.status_code_number(), Some(400..=499))
+    }
+
+    /// Check if the last rendering result has a server error (5xx) status code.
+    pub fn is_server_error(&self) -> bool {
+        matches!(self.status_code_number(), Some(500..=599))
+    }
+
+    /// Parse the status code of the last rendering result as a number.
+    fn status_code_number(&self) -> Option<u16> {
+        self.get_status_code().trim().parse().ok()
+    }
+
+    /// Get the HTTP headers from the last rendering result.
+    ///
+    /// Reads the `headers` member of the result data, which may be either an
+    /// object (`{"Cache-Control": "no-cache"}`) or an array of name/value pairs
+    /// (`[["Set-Cookie", "a=1"], ["Set-Cookie", "b=2"]]`). Header names are
+    /// normalized to lowercase. Scalar values are converted to strings and
+    /// malformed entries are skipped.
+    ///
+    /// # Returns
+    ///
+    /// The list of `(name, value)` pairs, or an empty vector if no header data
+    /// is present or it is malformed.
+    pub fn get_headers(&self) -> Vec<(String, String)> {
+        let headers = match self.result.get("result").and_then(|r| r.get("headers")) {
+            Some(headers) => headers,
+            None => return Vec::new(),
+        };
+
+        match headers {
+            Value::Object(map) => map.iter()
+                .filter_map(|(name, value)| Self::header_pair(name, value))
+                .collect(),
+            Value::Array(items) => items.iter()
+                .filter_map(|item| match item.as_array().map(|pair| pair.as_slice()) {
+                    Some([Value::String(name), value]) => Self::header_pair(name, value),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Build a normalized header pair, accepting only scalar values.
+    fn header_pair(name: &str, value: &Value) -> Option<(String, String)> {
+        let value = match value {
+            Value::String(s) => s.clone(),
+            Value::Number(n) => n.to_string(),
+            Value::Bool(b) => b.to_string(),
+            _ => return None,
+        };
+        Some((name.to_lowercase(), value))
+    }
+
+    /// Get the complete result data from the last rendering operation.
+    ///
+    /// # Returns
+    ///
+    /// A reference to the complete result `Value` if available, or `None`
+    /// if no result has been stored yet.
+    pub fn get_result(&self) -> Option<&Value> {
+        self.result.get("result")
+    }
+
+    /// Get the IPC status of the last rendering operation.
+    ///
+    /// This is the control byte of the server response (`CTRL_STATUS_OK` or
+    /// `CTRL_STATUS_KO`), not the template status code of `get_status_code()`.
+    ///
+    /// # Returns
+    ///
+    /// The status, or `None` if no result has been stored yet.
+    pub fn get_raw_status(&self) -> Option<u8> {
+        self.result.get("status")
+            .and_then(|v| v.as_u64())
+            .map(|status| status as u8)
+    }
+
+    /// Get the content of the last rendering operation.
+    ///
+    /// Re-reads the rendered content without rendering again, after the
+    /// output options of the template are applied.
+    ///
+    /// # Returns
+    ///
+    /// The rendered content, or `None` if nothing has been rendered yet or
+    /// the server answered with `CTRL_STATUS_KO`.
+    pub fn get_content(&self) -> Option<&str> {
+        self.result.get("content").and_then(|v| v.as_str())
+    }
+
+    /// Get the server-side render time of the last rendering result.
+    ///
+    /// Read from the optional `render_time_ms` field of the result. Current
+    /// Neutral server releases do not report it; it is only available from
+    /// servers that add it to the result.
+    ///
+    /// # Returns
+    ///
+    /// The render time in milliseconds, or `None` if the field is absent or not a number.
+    pub fn get_render_time_ms(&self) -> Option<f64> {
+        self.result.get("result")
+            .and_then(|r| r.get("render_time_ms"))
+            .and_then(|v| v.as_f64())
+    }
+
+    /// Get the schema variables used by the last rendering.
+    ///
+    /// Read from the optional `variables_used` field of the result. Current
+    /// Neutral server releases do not report it; it is only available from
+    /// servers that add it to the result.
+    ///
+    /// # Returns
+    ///
+    /// The variable names, or `None` if the field is absent or not an array.
+    /// Non-string entries are skipped.
+    pub fn get_template_variables_used(&self) -> Option<Vec<String>> {
+        self.result_string_list("variables_used")
+    }
+
+    /// Get the warnings reported by the server for the last rendering.
+    ///
+    /// Read from the optional `warnings` field of the result. Current Neutral
+    /// server releases do not report it; it is only available from servers
+    /// that add it to the result.
+    ///
+    /// # Returns
+    ///
+    /// The warning messages, or `None` if the field is absent or not an array.
+    /// Non-string entries are skipped.
+    pub fn get_server_warnings(&self) -> Option<Vec<String>> {
+        self.result_string_list("warnings")
+    }
+
+    /// Read an array of strings from the result data.
+    fn result_string_list(&self, field: &str) -> Option<Vec<String>> {
+        let items = self.result.get("result")?.get(field)?.as_array()?;
+        Some(items.iter().filter_map(|v| v.as_str()).map(str::to_string).collect())
+    }
+
+    /// Decode the current schema bytes into a JSON value according to the schema type.
+    fn decode_schema(&self) -> Result<Value> {
+        let schema = match self.schema_type {
+            CONTENT_PATH => {
+                return Err(NeutralIpcError::InvalidSchema(format!(
+                    "the schema is the file path '{}', read by the server",
+                    String::from_utf8_lossy(&self.schema)
+                )));
+            }
+            CONTENT_MSGPACK => rmp_serde::from_slice(&self.schema)?,
+            _ => json::from_slice(&self.schema)?,
+        };
+        Ok(schema)
+    }
+
+    /// Encode a JSON value into schema bytes according to the schema type.
+    fn encode_schema(&self, schema: &Value) -> Result<Vec<u8>> {
+        let bytes = match self.schema_type {
+            CONTENT_MSGPACK => rmp_serde::to_vec(schema)?,
+            _ => serde_json::to_vec(schema)?,
+        };
+        Ok(bytes)
+    }
+
+    /// Serialize a typed schema into a JSON value, requiring a JSON object.
+    fn typed_schema<T: Serialize>(schema: &T) -> Result<Value> {
+        match serde_json::to_value(schema)? {
+            schema @ Value::Object(_) => Ok(schema),
+            other => Err(NeutralIpcError::InvalidSchema(format!(
+                "schema must serialize to a JSON object, got {}",
+                Self::value_kind(&other)
+            ))),
+        }
+    }
+
+    /// Get a human readable name for the kind of a JSON value.
+    fn value_kind(value: &Value) -> &'static str {
+        match value {
+            Value::Null => "null",
+            Value::Bool(_) => "a boolean",
+            Value::Number(_) => "a number",
+            Value::String(_) => "a string",
+            Value::Array(_) => "an array",
+            Value::Object(_) => "an object",
+        }
+    }
+
+    /// Recursively collect the JSON type of every leaf value under `path`.
+    fn collect_types(path: &str, value: &Value, types: &mut HashMap<String, String>) {
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                for (key, value) in map {
+                    let path = if path.is_empty() {
+                        key.to_string()
+                    } else {
+                        format!("{}.{}", path, key)
+                    };
+                    Self::collect_types(&path, value, types);
+                }
+            }
+            value => {
+                let kind = match value {
+                    Value::Null => "null",
+                    Value::Bool(_) => "boolean",
+                    Value::Number(_) => "number",
+                    Value::String(_) => "string",
+                    Value::Array(_) => "array",
+                    Value::Object(_) => "object",
+                };
+                types.insert(path.to_string(), kind.to_string());
+            }
+        }
+    }
+
+    /// Recursively replace strings holding a JSON number literal with the number.
+    fn coerce_numbers(value: &mut Value) {
+        match value {
+            Value::String(s) => {
+                if let Ok(number) = s.parse::<serde_json::Number>() {
+                    *value = Value::Number(number);
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(Self::coerce_numbers),
+            Value::Object(map) => map.values_mut().for_each(Self::coerce_numbers),
+            _ => {}
+        }
+    }
+
+    /// Read a schema file into a string, reporting errors with the file path.
+    fn read_schema_source(path: &Path) -> Result<String> {
+        fs::read_to_string(path).map_err(|source| NeutralIpcError::SchemaFileIo {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Read an environment variable as UTF-8.
+    fn read_env(var_name: &str) -> Result<String> {
+        env::var(var_name).map_err(|err| match err {
+            env::VarError::NotPresent => NeutralIpcError::EnvVarNotFound(var_name.to_string()),
+            env::VarError::NotUnicode(value) => match String::from_utf8(value.as_encoded_bytes().to_vec()) {
+                Err(err) => NeutralIpcError::InvalidUtf8(err),
+                Ok(_) => unreachable!("environment variable reported as not unicode"),
+            },
+        })
+    }
+
+    /// Read and parse a JSON schema file, reporting errors with the file path.
+    fn read_schema_file(path: &Path) -> Result<Value> {
+        let content = Self::read_schema_source(path)?;
+        serde_json::from_str(&content).map_err(|source| NeutralIpcError::SchemaFileJson {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Parse a schema argument given either as a JSON `Value` or as a JSON string.
+    fn parse_schema(schema: Value) -> Result<Value> {
+        match schema {
+            Value::String(s) => json::from_str(&s).map_err(|error| NeutralIpcError::invalid_schema_json(error, &s)),
+            schema => Ok(schema),
+        }
+    }
+
+    /// Recursively collect the differences between two JSON values.
+    ///
+    /// Objects are walked key by key; any other pair of values is compared
+    /// as a whole and reported under `path` when different.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Dot-separated path of the values being compared
+    /// * `a` - The original JSON value
+    /// * `b` - The JSON value compared against `a`
+    /// * `removed` - Collects keys present only in `a`
+    /// * `added` - Collects keys present only in `b`
+    /// * `changed` - Collects keys whose values differ
+    fn diff_values(
+        path: &str,
+        a: &Value,
+        b: &Value,
+        removed: &mut Map<String, Value>,
+        added: &mut Map<String, Value>,
+        changed: &mut Map<String, Value>,
+    ) {
+        let join = |key: &str| {
+            if path.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}.{}", path, key)
+            }
+        };
+
+        match (a, b) {
+            (Value::Object(map_a), Value::Object(map_b)) => {
+                for (key, value_a) in map_a {
+                    match map_b.get(key) {
+                        Some(value_b) => Self::diff_values(&join(key), value_a, value_b, removed, added, changed),
+                        None => {
+                            removed.insert(join(key), value_a.clone());
+                        }
+                    }
+                }
+                for (key, value_b) in map_b {
+                    if !map_a.contains_key(key) {
+                        added.insert(join(key), value_b.clone());
+                    }
+                }
+            }
+            (a, b) if a != b => {
+                let mut change = Map::new();
+                change.insert("from".to_string(), a.clone());
+                change.insert("to".to_string(), b.clone());
+                changed.insert(path.to_string(), Value::Object(change));
+            }
+            _ => {}
+        }
+    }
+
+    /// Replace the characters with a meaning in HTML by character references.
+    fn escape_html(content: &str) -> String {
+        let mut escaped = String::with_capacity(content.len());
+        for c in content.chars() {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\'' => escaped.push_str("&#39;"),
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    /// Escape `content` for a JavaScript string literal inside a `<script>` element.
+    fn escape_js(content: &str) -> String {
+        let mut escaped = String::with_capacity(content.len());
+        for c in content.chars() {
+            match c {
+                '\\' => escaped.push_str("\\\\"),
+                '"' => escaped.push_str("\\\""),
+                '\'' => escaped.push_str("\\'"),
+                '`' => escaped.push_str("\\`"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                // `$` would start a `${...}` substitution in a template literal.
+                '<' | '>' | '&' | '$' | '\u{2028}' | '\u{2029}' => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+                c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    /// Recursively check that `value` contains every key and value of `subset`.
+    fn contains_value(value: &Value, subset: &Value) -> bool {
+        match (value, subset) {
+            (Value::Object(map), Value::Object(subset_map)) => subset_map.iter().all(|(key, subset_value)| {
+                map.get(key).is_some_and(|value| Self::contains_value(value, subset_value))
+            }),
+            (value, subset) => value == subset,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmp_serde::from_slice;
+    use serde_json::json;
+    use crate::client::is_server_available;
+    use crate::mock::{read_request, write_response, MockServer};
+
+    /// Skip test if the Neutral server is not available.
+    ///
+    /// This helper function checks server availability and panics with a
+    /// clear message if the server is not running, allowing tests to be
+    /// skipped gracefully during development.
+    fn skip_if_server_unavailable() {
+        if !is_server_available() {
+            panic!("Neutral TS server not available - skipping test");
+        }
+    }
+
+    #[test]
+    fn test_template_src() {
+        skip_if_server_unavailable();
+
+        let schema = json!({
+            "data": {
+                "text": "Hello!",
+                "number": 123
+            }
+        });
+
+        let mut template = NeutralIpcTemplate::from_src_value("Rust IPC client: {:;text:} {:;number:}", schema).unwrap();
+        let result = template.render().unwrap();
+        let status_code = template.get_status_code();
+        let status_text = template.get_status_text();
+        let status_param = template.get_status_param();
+
+        assert!(!template.has_error());
+        assert_eq!(status_code, "200");
+        assert_eq!(status_text, "OK");
+        assert_eq!(status_param, "");
+        assert_eq!(result, "Rust IPC client: Hello! 123");
+    }
+
+    #[test]
+    fn test_template_file() {
+        skip_if_server_unavailable();
+
+        let schema = json!({
+            "data": {
+                "text": "Hello!",
+                "number": 123
+            }
+        });
+
+        let manifest_dir = env!("CARGO_MANIFEST_DIR");
+        let tpl_file = format!("{}/tests/template.ntpl", manifest_dir);
+
+        let mut template = NeutralIpcTemplate::from_file_value(&tpl_file, schema).unwrap();
+        let result = template.render().unwrap();
+        let status_code = template.get_status_code();
+        let status_text = template.get_status_text();
+        let status_param = template.get_status_param();
+
+        assert!(!template.has_error());
+        assert_eq!(status_code, "200");
+        assert_eq!(status_text, "OK");
+        assert_eq!(status_param, "");
+        assert_eq!(result, "Rust IPC client: Hello! 123");
+    }
+
+
+    #[test]
+    fn test_template_merge_schema() {
+        skip_if_server_unavailable();
+
+        let schema = json!({
+            "data": {
+                "text": "Hello!",
+                "number": 123
+            }
+        });
+
+        let schema_merge = json!({
+            "data": {
+                "text": "Hello! (merged)"
+            }
+        });
+
+        let mut template = NeutralIpcTemplate::from_src_value("Rust IPC client: {:;text:} {:;number:}", schema).unwrap();
+        template.merge_schema(schema_merge).unwrap();
+        let result = template.render().unwrap();
+        let status_code = template.get_status_code();
+        let status_text = template.get_status_text();
+        let status_param = template.get_status_param();
+
+        assert!(!template.has_error());
+        assert_eq!(status_code, "200");
+        assert_eq!(status_text, "OK");
+        assert_eq!(status_param, "");
+        assert_eq!(result, "Rust IPC client: Hello! (merged) 123");
+    }
+
+    #[test]
+    fn test_template_render_with_override() {
+        skip_if_server_unavailable();
+
+        let schema = json!({
+            "data": {
+                "text": "Hello!",
+                "number": 123
+            }
+        });
+
+        let mut template = NeutralIpcTemplate::from_src_value("Rust IPC client: {:;text:} {:;number:}", schema).unwrap();
+        let schema_before = template.schema.clone();
+        let result = template.render_with_override(json!({"data": {"text": "Hello! (override)"}})).unwrap();
+
+        assert!(!template.has_error());
+        assert_eq!(result, "Rust IPC client: Hello! (override) 123");
+        assert_eq!(template.schema, schema_before);
+        assert_eq!(template.render().unwrap(), "Rust IPC client: Hello! 123");
+    }
+
+    #[test]
+    fn test_template_render_count_words() {
+        skip_if_server_unavailable();
+
+        let schema = json!({
+            "data": {
+                "text": "Hello!",
+                "number": 123
+            }
+        });
+
+        let mut template = NeutralIpcTemplate::from_src_value("Rust IPC client: {:;text:} {:;number:}", schema).unwrap();
+        let words = template.render_count_words().unwrap();
+
+        assert_eq!(words, 5);
+        assert_eq!(template.last_render_word_count(), Some(5));
+    }
+
+    #[test]
+    fn test_template_typed_schema() {
+        skip_if_server_unavailable();
+
+        let schema = TypedSchema {
+            data: TypedData {
+                text: "Hello!".to_string(),
+                number: Some(123),
+                missing: None,
+            },
+        };
+
+        let mut template = NeutralIpcTemplate::from_src_typed("Rust IPC client: {:;text:} {:;number:}{:;missing:}", &schema).unwrap();
+        let result = template.render().unwrap();
+
+        assert!(!template.has_error());
+        assert_eq!(result, "Rust IPC client: Hello! 123");
+    }
+
+    #[test]
+    fn test_template_404() {
+        skip_if_server_unavailable();
+
+        let schema = json!({
+            "data": {
+                "text": "Hello!",
+                "number": 123
+            }
+        });
+
+        let mut template = NeutralIpcTemplate::from_src_value("Rust IPC client: {:exit; 404 :}", schema).unwrap();
+        let result = template.render().unwrap();
+        let status_code = template.get_status_code();
+        let status_text = template.get_status_text();
+        let status_param = template.get_status_param();
+
+        assert!(!template.has_error());
+        assert_eq!(status_code, "404");
+        assert_eq!(status_text, "Not Found");
+        assert_eq!(status_param, "");
+        assert_eq!(result, "404 Not Found");
+    }
+
+    #[test]
+    fn test_template_404_status_helpers() {
+        let server = MockServer::respond_with(
+            CTRL_STATUS_OK,
+            br#"{"has_error":false,"status_code":"404","status_text":"Not Found","status_param":""}"#,
+            b"404 Not Found",
+        );
+        let mut template = NeutralIpcTemplate::from_src_value("{:exit; 404 :}", json!({}))
+            .unwrap()
+            .with_config(server.config());
+        template.render().unwrap();
+
+        assert!(template.is_client_error());
+        assert!(!template.is_redirect());
+        assert_eq!(template.redirect(), None);
+    }
+
+    #[test]
+    fn test_template_redirect() {
+        skip_if_server_unavailable();
+
+        let schema = json!({
+            "data": {
+                "text": "Hello!",
+                "number": 123
+            }
+        });
+
+        let mut template = NeutralIpcTemplate::from_src_value("Rust IPC client: {:redirect; 301 >> https://crates.io/crates/neutralts :}", schema).unwrap();
+        let result = template.render().unwrap();
+        let status_code = template.get_status_code();
+        let status_text = template.get_status_text();
+        let status_param = template.get_status_param();
+
+        assert!(!template.has_error());
+        assert_eq!(status_code, "301");
+        assert_eq!(status_text, "Moved Permanently");
+        assert_eq!(status_param, "https://crates.io/crates/neutralts");
+        assert_eq!(result, "301 Moved Permanently\nhttps://crates.io/crates/neutralts");
+    }
+
+    #[test]
+    fn test_template_redirect_status_helpers() {
+        let server = MockServer::respond_with(
+            CTRL_STATUS_OK,
+            br#"{"has_error":false,"status_code":"301","status_text":"Moved Permanently","status_param":"https://crates.io/crates/neutralts"}"#,
+            b"301 Moved Permanently\nhttps://crates.io/crates/neutralts",
+        );
+        let mut template = NeutralIpcTemplate::from_src_value("{:redirect; 301 >> https://crates.io/crates/neutralts :}", json!({}))
+            .unwrap()
+            .with_config(server.config());
+        template.render().unwrap();
+
+        assert!(template.is_redirect());
+        assert!(!template.is_client_error());
+        assert!(!template.is_server_error());
+        assert_eq!(template.redirect(), Some(Redirect {
+            code: 301,
+            location: "https://crates.io/crates/neutralts".to_string(),
+            permanent: true,
+        }));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip_renders_identically() {
+        skip_if_server_unavailable();
+
+        let mut template = NeutralIpcTemplate::from_src_msgpack(
+            "{:;text:} {:;number:}",
+            &rmp_serde::to_vec(&json!({"data": {"text": "Hello", "number": 123}})).unwrap(),
+        ).unwrap();
+
+        let serialized = serde_json::to_string(&template).unwrap();
+        let mut restored: NeutralIpcTemplate = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(restored.render().unwrap(), template.render().unwrap());
+    }
+
+    #[test]
+    fn test_list_templates() {
+        skip_if_server_unavailable();
+
+        let templates = NeutralIpcTemplate::list_templates().unwrap();
+
+        assert!(!templates.is_empty());
+    }
+
+    #[test]
+    fn test_from_src_msgpack_and_merge_schema() {
+        let schema = json!({
+            "data": {
+                "text": "Hello!"
+            }
+        });
+        let msgpack = rmp_serde::to_vec(&schema).unwrap();
+
+        let mut template = NeutralIpcTemplate::from_src_msgpack("tpl", &msgpack).unwrap();
+        template.merge_schema(json!({"data": {"number": 123}})).unwrap();
+
+        assert_eq!(template.schema_type, CONTENT_MSGPACK);
+        let merged: Value = from_slice(&template.schema).unwrap();
+        assert_eq!(merged["data"]["text"], "Hello!");
+        assert_eq!(merged["data"]["number"], 123);
+    }
+
+    #[test]
+    fn test_schema_equals_ignores_key_order_and_encoding() {
+        let a = NeutralIpcTemplate::from_src_value("tpl", json!({"data": {"a": 1, "b": [1, 2]}})).unwrap();
+        let msgpack = rmp_serde::to_vec(&json!({"data": {"b": [1, 2], "a": 1}})).unwrap();
+        let b = NeutralIpcTemplate::from_src_msgpack("tpl", &msgpack).unwrap();
+        let c = NeutralIpcTemplate::from_src_value("tpl", json!({"data": {"a": 1, "b": [2, 1]}})).unwrap();
+
+        assert!(a.schema_equals(&b));
+        assert!(b.schema_equals(&a));
+        assert!(!a.schema_equals(&c));
+
+        let mut broken = NeutralIpcTemplate::new().unwrap();
+        broken.set_schema_msgpack(b"\xc1");
+        assert!(!broken.schema_equals(&broken));
+    }
+
+    #[test]
+    fn test_get_schema_keys_and_leaf_values() {
+        let template = NeutralIpcTemplate::from_src_value("tpl", json!({
+            "data": {
+                "text": "Hi",
+                "number": 1,
+                "items": [{"name": "a", "tags": ["x"]}, null],
+                "empty": {},
+                "none": []
+            },
+            "config": {"cache": false}
+        })).unwrap();
+
+        assert_eq!(template.get_schema_keys().unwrap(), [
+            "config.cache",
+            "data.empty",
+            "data.items.0.name",
+            "data.items.0.tags.0",
+            "data.items.1",
+            "data.none",
+            "data.number",
+            "data.text",
+        ]);
+        let leaves = template.get_schema_leaf_values().unwrap();
+        assert_eq!(leaves[0], ("config.cache".to_string(), json!(false)));
+        assert_eq!(leaves[3], ("data.items.0.tags.0".to_string(), json!("x")));
+        assert_eq!(leaves[5], ("data.none".to_string(), json!([])));
+
+        assert!(NeutralIpcTemplate::new().unwrap().get_schema_keys().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_schema_contains() {
+        let template = NeutralIpcTemplate::from_src_value("tpl", json!({
+            "config": {"cache": 0},
+            "data": {"text": "Hi", "list": [1, 2], "nested": {"a": 1, "b": 2}}
+        })).unwrap();
+
+        assert!(template.schema_contains(&json!({})).unwrap());
+        assert!(template.schema_contains(&json!({"data": {"nested": {"b": 2}, "list": [1, 2]}})).unwrap());
+        assert!(!template.schema_contains(&json!({"data": {"nested": {"c": 3}}})).unwrap());
+        assert!(!template.schema_contains(&json!({"data": {"list": [1]}})).unwrap());
+        assert!(!template.schema_contains(&json!({"data": {"text": {"nested": "Hi"}}})).unwrap());
+    }
+
+    #[test]
+    fn test_schema_diff_reports_nested_changes() {
+        let a = NeutralIpcTemplate::from_src_value("tpl", json!({
+            "data": {
+                "text": "Hello!",
+                "removed": true,
+                "nested": {"value": 1}
+            }
+        })).unwrap();
+        let msgpack = rmp_serde::to_vec(&json!({
+            "data": {
+                "text": "Hello! (changed)",
+                "nested": {"value": 2, "added": [1, 2]}
+            }
+        })).unwrap();
+        let b = NeutralIpcTemplate::from_src_msgpack("tpl", &msgpack).unwrap();
+
+        let diff = a.schema_diff(&b);
+
+        assert_eq!(diff["removed"], json!({"data.removed": true}));
+        assert_eq!(diff["added"], json!({"data.nested.added": [1, 2]}));
+        assert_eq!(diff["changed"], json!({
+            "data.text": {"from": "Hello!", "to": "Hello! (changed)"},
+            "data.nested.value": {"from": 1, "to": 2}
+        }));
+        assert_eq!(a.schema_diff(&a), json!({"removed": {}, "added": {}, "changed": {}}));
+    }
+
+    #[test]
+    fn test_get_headers_normalizes_object_and_array() {
+        let mut template = NeutralIpcTemplate::new().unwrap();
+        assert!(template.get_headers().is_empty());
+
+        template.result.insert("result".to_string(), json!({
+            "headers": {"Cache-Control": "no-cache", "Content-Length": 12, "X-Bad": {}}
+        }));
+        let mut headers = template.get_headers();
+        headers.sort();
+        assert_eq!(headers, vec![
+            ("cache-control".to_string(), "no-cache".to_string()),
+            ("content-length".to_string(), "12".to_string()),
+        ]);
+
+        template.result.insert("result".to_string(), json!({
+            "headers": [["Set-Cookie", "a=1"], ["Set-Cookie", "b=2"], ["broken"]]
+        }));
+        assert_eq!(template.get_headers(), vec![
+            ("set-cookie".to_string(), "a=1".to_string()),
+            ("set-cookie".to_string(), "b=2".to_string()),
+        ]);
+
+        template.result.insert("result".to_string(), json!({"headers": "invalid"}));
+        assert!(template.get_headers().is_empty());
+    }
+
+    #[test]
+    fn test_append_schema_array() {
+        let mut template = NeutralIpcTemplate::from_src_value("tpl", json!({
+            "data": {"items": [1], "text": "Hello!"}
+        })).unwrap();
+
+        template.append_schema_array("data.items", json!([2, 3])).unwrap();
+        template.append_schema_array("data.new.list", json!(["a"])).unwrap();
+
+        let schema: Value = serde_json::from_slice(&template.schema).unwrap();
+        assert_eq!(schema["data"]["items"], json!([1, 2, 3]));
+        assert_eq!(schema["data"]["new"]["list"], json!(["a"]));
+
+        match template.append_schema_array("data.text", json!([1])) {
+            Err(NeutralIpcError::SchemaMergeConflict(path)) => assert_eq!(path, "data.text"),
+            other => panic!("expected SchemaMergeConflict, got {:?}", other),
+        }
+        match template.append_schema_array("data.text.inner", json!([1])) {
+            Err(NeutralIpcError::SchemaMergeConflict(path)) => assert_eq!(path, "data.text.inner"),
+            other => panic!("expected SchemaMergeConflict, got {:?}", other),
+        }
+
+        template.merge_schema(json!({"data": {"empty": {}, "unset": null}})).unwrap();
+        for path in ["data.empty", "data.unset", "data.unset.list"] {
+            match template.append_schema_array(path, json!([1])) {
+                Err(NeutralIpcError::SchemaMergeConflict(conflict)) => assert_eq!(conflict, path),
+                other => panic!("expected SchemaMergeConflict, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_last_render_word_count() {
+        let mut template = NeutralIpcTemplate::new().unwrap();
+        assert_eq!(template.last_render_word_count(), None);
+
+        template.result.insert("content".to_string(), json!("  Hello\n\tbrave  new world "));
+        assert_eq!(template.last_render_word_count(), Some(4));
+    }
+
+    #[derive(Serialize)]
+    struct TypedData {
+        text: String,
+        number: Option<u32>,
+        missing: Option<String>,
+    }
+
+    #[derive(Serialize)]
+    struct TypedSchema {
+        data: TypedData,
+    }
+
+    #[test]
+    fn test_typed_schema_serializes_options() {
+        let schema = TypedSchema {
+            data: TypedData {
+                text: "Hello!".to_string(),
+                number: Some(123),
+                missing: None,
+            },
+        };
+
+        let template = NeutralIpcTemplate::from_file_typed("tpl", &schema).unwrap();
+        assert_eq!(template.tpl_type, CONTENT_PATH);
+        let decoded: Value = serde_json::from_slice(&template.schema).unwrap();
+        assert_eq!(decoded, json!({"data": {"text": "Hello!", "number": 123, "missing": null}}));
+
+        let msgpack = rmp_serde::to_vec(&json!({"data": {}})).unwrap();
+        let mut template = NeutralIpcTemplate::from_src_msgpack("tpl", &msgpack).unwrap();
+        template.replace_schema_typed(&schema).unwrap();
+        let decoded: Value = from_slice(&template.schema).unwrap();
+        assert_eq!(decoded["data"]["number"], 123);
+    }
+
+    #[test]
+    fn test_typed_schema_rejects_non_objects() {
+        assert!(matches!(
+            NeutralIpcTemplate::from_src_typed("tpl", &vec![1, 2, 3]),
+            Err(NeutralIpcError::InvalidSchema(_))
+        ));
+        let mut template = NeutralIpcTemplate::new().unwrap();
+        assert!(matches!(template.replace_schema_typed(&"text"), Err(NeutralIpcError::InvalidSchema(_))));
+        assert_eq!(template.schema, b"{}");
+    }
+
+    #[test]
+    fn test_merge_schema_files_deep_merges_in_order() {
+        let manifest_dir = env!("CARGO_MANIFEST_DIR");
+        let paths = [
+            PathBuf::from(format!("{}/tests/schema-base.json", manifest_dir)),
+            PathBuf::from(format!("{}/tests/schema-merge.json", manifest_dir)),
+        ];
+
+        let mut template = NeutralIpcTemplate::new().unwrap();
+        template.merge_schema_files(&paths).unwrap();
+
+        let merged: Value = serde_json::from_slice(&template.schema).unwrap();
+        assert_eq!(merged, json!({
+            "config": {"cache_lifetime": "3600"},
+            "data": {
+                "text": "Hello! (merged)",
+                "number": 123,
+                "list": ["c"]
+            }
+        }));
+
+        let template = NeutralIpcTemplate::from_file_with_schema_file("tpl", &paths[0]).unwrap();
+        let schema: Value = serde_json::from_slice(&template.schema).unwrap();
+        assert_eq!(schema["data"]["text"], "Hello!");
+    }
+
+    #[test]
+    fn test_merge_schema_file_reports_path_and_position() {
+        let dir = std::env::temp_dir();
+        let invalid = dir.join(format!("neutral-schema-invalid-{}.json", std::process::id()));
+        fs::write(&invalid, "{\n  \"data\": {\n    \"text\": \n}").unwrap();
+
+        let mut template = NeutralIpcTemplate::new().unwrap();
+        let err = template.merge_schema_file(&invalid).unwrap_err();
+        fs::remove_file(&invalid).unwrap();
+        match &err {
+            NeutralIpcError::SchemaFileJson { path, source } => {
+                assert_eq!(path, &invalid);
+                assert_eq!(source.line(), 4);
+            }
+            other => panic!("expected SchemaFileJson, got {:?}", other),
+        }
+        assert!(err.to_string().contains("line 4"));
+
+        let missing = dir.join("neutral-schema-missing.json");
+        match template.merge_schema_file(&missing) {
+            Err(NeutralIpcError::SchemaFileIo { path, .. }) => assert_eq!(path, missing),
+            other => panic!("expected SchemaFileIo, got {:?}", other),
+        }
+    }
+
+    #[cfg(all(feature = "yaml", feature = "toml"))]
+    #[test]
+    fn test_merge_schema_yaml_and_toml() {
+        let mut template = NeutralIpcTemplate::from_src_value("tpl", json!({"data": {"text": "Hello!"}})).unwrap();
+        template.merge_schema_yaml("data:\n  number: 123\n").unwrap();
+        template.merge_schema_toml("[data]\ntext = \"Hello! (toml)\"\n").unwrap();
+
+        let merged: Value = serde_json::from_slice(&template.schema).unwrap();
+        assert_eq!(merged, json!({"data": {"text": "Hello! (toml)", "number": 123}}));
+    }
+
+    #[test]
+    fn test_set_template_and_schema_from_env() {
+        env::set_var("NEUTRAL_TEST_TEMPLATE_FROM_ENV", "Rust IPC client: {:;text:}");
+        env::set_var("NEUTRAL_TEST_SCHEMA_FROM_ENV", r#"{"data":{"text":"Hello!"}}"#);
+
+        let msgpack = rmp_serde::to_vec(&json!({})).unwrap();
+        let mut template = NeutralIpcTemplate::from_file_msgpack("file.ntpl", &msgpack).unwrap();
+        template.set_template_from_env("NEUTRAL_TEST_TEMPLATE_FROM_ENV").unwrap();
+        template.set_schema_from_env("NEUTRAL_TEST_SCHEMA_FROM_ENV").unwrap();
+
+        assert_eq!(template.tpl_type, CONTENT_TEXT);
+        assert_eq!(template.template, "Rust IPC client: {:;text:}");
+        assert_eq!(template.schema_type, CONTENT_JSON);
+        assert_eq!(template.schema, br#"{"data":{"text":"Hello!"}}"#);
+    }
+
+    #[test]
+    fn test_source_base64_round_trip() {
+        let mut template = NeutralIpcTemplate::new().unwrap();
+        assert_eq!(template.get_source_as_base64(), None);
+
+        template.set_source_base64("SGVsbG8gezo7dGV4dDp9ICjDqSk=\n").unwrap();
+        assert_eq!(template.source(), Some("Hello {:;text:} (é)"));
+        assert_eq!(template.get_source_as_base64().as_deref(), Some("SGVsbG8gezo7dGV4dDp9ICjDqSk="));
+    }
+
+    #[test]
+    fn test_set_source_base64_errors() {
+        let mut template = NeutralIpcTemplate::from_src_value("{:;text:}", json!({})).unwrap();
+
+        assert!(matches!(template.set_source_base64("not base64!"), Err(NeutralIpcError::Base64(_))));
+        assert!(matches!(template.set_source_base64("/w=="), Err(NeutralIpcError::InvalidUtf8(_))));
+        assert_eq!(template.source(), Some("{:;text:}"));
+    }
+
+    #[test]
+    fn test_merge_schema_invalid_json_keeps_snippet() {
+        let mut template = NeutralIpcTemplate::new().unwrap();
+
+        match template.merge_schema(Value::String("{\"data\": {\"text\":\n}".to_string())) {
+            Err(err @ NeutralIpcError::InvalidSchemaJson { .. }) => {
+                let NeutralIpcError::InvalidSchemaJson { snippet, .. } = &err else { unreachable!() };
+                assert_eq!(snippet, "{\\\"data\\\": {\\\"text\\\":\\n}");
+                assert!(err.to_string().contains(snippet.as_str()));
+            }
+            other => panic!("expected InvalidSchemaJson, got {:?}", other),
+        }
+        assert_eq!(template.schema, b"{}");
+
+        let long = format!("[{}", "1,".repeat(500));
+        match template.merge_schema(Value::String(long)) {
+            Err(NeutralIpcError::InvalidSchemaJson { snippet, .. }) => {
+                assert_eq!(snippet.len(), 256 + "...".len());
+                assert!(snippet.ends_with("..."));
+            }
+            other => panic!("expected InvalidSchemaJson, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_render_invalid_result_json_keeps_snippet() {
+        let server = MockServer::respond_with(CTRL_STATUS_OK, b"<html>Bad Gateway</html>", b"");
+        let mut template = NeutralIpcTemplate::from_src_value("", json!({})).unwrap().with_config(server.config());
+
+        match template.render() {
+            Err(NeutralIpcError::Render { source, .. }) if matches!(*source, NeutralIpcError::InvalidResultJson { .. }) => {
+                let message = source.to_string();
+                assert!(message.contains("<html>Bad Gateway</html>"), "{}", message);
+            }
+            other => panic!("expected InvalidResultJson, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_set_from_env_errors() {
+        let mut template = NeutralIpcTemplate::new().unwrap();
+
+        match template.set_template_from_env("NEUTRAL_TEST_UNSET_VARIABLE") {
+            Err(NeutralIpcError::EnvVarNotFound(name)) => assert_eq!(name, "NEUTRAL_TEST_UNSET_VARIABLE"),
+            other => panic!("expected EnvVarNotFound, got {:?}", other),
+        }
+
+        env::set_var("NEUTRAL_TEST_INVALID_SCHEMA_FROM_ENV", "{not json");
+        assert!(matches!(
+            template.set_schema_from_env("NEUTRAL_TEST_INVALID_SCHEMA_FROM_ENV"),
+            Err(NeutralIpcError::Json(_))
+        ));
+        assert_eq!(template.schema, b"{}");
+
+        #[cfg(unix)]
+        {
+            use std::ffi::OsStr;
+            use std::os::unix::ffi::OsStrExt;
+
+            env::set_var("NEUTRAL_TEST_NON_UTF8_FROM_ENV", OsStr::from_bytes(b"caf\xe9"));
+            assert!(matches!(
+                template.set_template_from_env("NEUTRAL_TEST_NON_UTF8_FROM_ENV"),
+                Err(NeutralIpcError::InvalidUtf8(_))
+            ));
+        }
+    }
+
+    #[cfg(feature = "dedup")]
+    #[test]
+    fn test_request_key_identifies_template_and_schema() {
+        let a = NeutralIpcTemplate::from_src_value("tpl", json!({"data": {"text": "a"}})).unwrap();
+        let b = NeutralIpcTemplate::from_src_value("tpl", json!({"data": {"text": "a"}})).unwrap();
+        let c = NeutralIpcTemplate::from_src_value("tpl", json!({"data": {"text": "c"}})).unwrap();
+        let d = NeutralIpcTemplate::from_file_value("tpl", json!({"data": {"text": "a"}})).unwrap();
+
+        assert_eq!(a.request_key(&a.schema), b.request_key(&b.schema));
+        assert_ne!(a.request_key(&a.schema), c.request_key(&c.schema));
+        assert_ne!(a.request_key(&a.schema), d.request_key(&d.schema));
+    }
+
+    #[cfg(feature = "dedup")]
+    #[test]
+    fn test_request_key_identifies_the_resolved_connection() {
+        let template = |config: NeutralIpcConfig| {
+            NeutralIpcTemplate::from_src_value("tpl", json!({})).unwrap().with_config(config)
+        };
+        let key = |template: &NeutralIpcTemplate| template.request_key(&template.schema);
+        let a = template(NeutralIpcConfig::without_file());
+        assert_eq!(key(&a), key(&template(NeutralIpcConfig::without_file())));
+
+        let mut b = template(NeutralIpcConfig::without_file());
+        b.force_endpoint("10.0.0.7:4273".parse().unwrap());
+        assert_ne!(key(&a), key(&b));
+
+        let mut c = template(NeutralIpcConfig::without_file());
+        c.set_timeout(Duration::from_secs(1));
+        assert_ne!(key(&a), key(&c));
+
+        let mut config = NeutralIpcConfig::without_file();
+        config.set_port(4274);
+        assert_ne!(key(&a), key(&template(config)));
+
+        #[cfg(feature = "proxy")]
+        {
+            let config = NeutralIpcConfig::without_file().with_proxy("127.0.0.1", 1080);
+            assert_ne!(key(&a), key(&template(config)));
+        }
+    }
+
+    #[test]
+    fn test_infer_schema_types_and_coerce_numbers() {
+        let mut template = NeutralIpcTemplate::from_src_value("", json!({
+            "config": {},
+            "data": {
+                "text": "Hello",
+                "page": "2",
+                "ratio": "-1.5e3",
+                "zip": "007",
+                "padded": " 12",
+                "flag": true,
+                "none": null,
+                "ids": ["1", "x"]
+            }
+        })).unwrap();
+
+        let types = template.infer_schema_types().unwrap();
+        assert_eq!(types.len(), 9);
+        assert_eq!(types["config"], "object");
+        assert_eq!(types["data.page"], "string");
+        assert_eq!(types["data.flag"], "boolean");
+        assert_eq!(types["data.none"], "null");
+        assert_eq!(types["data.ids"], "array");
+
+        template.coerce_schema_numbers().unwrap();
+        let schema = template.decode_schema().unwrap();
+        assert_eq!(schema["data"]["page"], json!(2));
+        assert_eq!(schema["data"]["ratio"], json!(-1500.0));
+        assert_eq!(schema["data"]["zip"], json!("007"));
+        assert_eq!(schema["data"]["padded"], json!(" 12"));
+        assert_eq!(schema["data"]["ids"], json!([1, "x"]));
+        assert_eq!(template.infer_schema_types().unwrap()["data.page"], "number");
+    }
+
+    #[test]
+    fn test_kind_transitions_between_path_and_source() {
+        let mut template = NeutralIpcTemplate::new().unwrap();
+        assert_eq!(template.kind(), TemplateKind::Path);
+        assert_eq!(template.path(), Some(""));
+        assert_eq!(template.source(), None);
+
+        template.result.insert("content".to_string(), json!("cached"));
+        template.set_source("{:;text:}");
+        assert_eq!(template.kind(), TemplateKind::Source);
+        assert_eq!(template.source(), Some("{:;text:}"));
+        assert_eq!(template.path(), None);
+        assert!(template.result.is_empty());
+
+        template.result.insert("content".to_string(), json!("cached"));
+        template.set_path("/tmp/index.ntpl");
+        assert_eq!(template.kind(), TemplateKind::Path);
+        assert_eq!(template.path(), Some("/tmp/index.ntpl"));
+        assert_eq!(template.source(), None);
+        assert!(template.result.is_empty());
+        assert_eq!(template.last_render_word_count(), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip_skips_result() {
+        let mut template = NeutralIpcTemplate::from_file_value("index.ntpl", json!({"data": {"text": "Hello"}})).unwrap();
+        template.result.insert("content".to_string(), json!("cached"));
+
+        let serialized = serde_json::to_value(&template).unwrap();
+        assert!(serialized.get("result").is_none());
+
+        let restored: NeutralIpcTemplate = serde_json::from_value(serialized).unwrap();
+        assert_eq!(restored.kind(), TemplateKind::Path);
+        assert_eq!(restored.path(), Some("index.ntpl"));
+        assert_eq!(restored.decode_schema().unwrap(), template.decode_schema().unwrap());
+        assert!(restored.result.is_empty());
+    }
+
+    #[test]
+    fn test_preview_cached_truncates_by_characters() {
+        let mut template = NeutralIpcTemplate::new().unwrap();
+        assert_eq!(template.preview_cached(5), None);
+
+        template.result.insert("content".to_string(), json!("Ñandú 🦀 rápido"));
+        assert_eq!(template.preview_cached(5).unwrap(), "Ñandú…");
+        assert_eq!(template.preview_cached(7).unwrap(), "Ñandú 🦀…");
+        assert_eq!(template.preview_cached(0).unwrap(), "…");
+        assert_eq!(template.preview_cached(14).unwrap(), "Ñandú 🦀 rápido");
+        assert_eq!(template.preview_cached(100).unwrap(), "Ñandú 🦀 rápido");
+    }
+
+    #[test]
+    fn test_set_template_root_rejects_escaping_path_before_sending() {
+        let mut template = NeutralIpcTemplate::new().unwrap();
+        template.set_template_root(&std::env::temp_dir());
+        template.set_path("../neutralipcrs-outside.ntpl");
+
+        match template.render() {
+            Err(NeutralIpcError::PathTraversal(path)) => assert_eq!(path, "../neutralipcrs-outside.ntpl"),
+            other => panic!("expected PathTraversal, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_request_headers_switch_control_code() {
+        let mut template = NeutralIpcTemplate::new().unwrap();
+        assert_eq!(template.request_content(b"{}").unwrap(), (CTRL_PARSE_TEMPLATE, b"{}".to_vec()));
+
+        template.set_request_header("X-Request-Id", "1");
+        template.set_request_header("Accept-Language", "es");
+        template.set_request_header("x-request-id", "2");
+        assert_eq!(
+            template.request_content(b"{}").unwrap(),
+            (CTRL_PARSE_TEMPLATE_WITH_HEADERS, b"X-Request-Id: 2\nAccept-Language: es\n\0{}".to_vec())
+        );
+
+        template.set_request_header("Bad", "line\nbreak");
+        assert!(matches!(template.request_content(b"{}"), Err(NeutralIpcError::InvalidRequestHeader(_))));
+
+        template.clear_request_headers();
+        assert_eq!(template.request_content(b"{}").unwrap(), (CTRL_PARSE_TEMPLATE, b"{}".to_vec()));
+    }
+
+    #[test]
+    fn test_get_response_headers() {
+        let mut template = NeutralIpcTemplate::new().unwrap();
+        assert!(template.get_response_headers().is_empty());
+
+        template.result.insert("headers".to_string(), json!({"X-Cache": "miss"}));
+        assert_eq!(template.get_response_headers(), vec![("X-Cache".to_string(), "miss".to_string())]);
+    }
+
+    #[test]
+    fn test_with_config_renders_against_given_server() {
+        let server = MockServer::respond_with(CTRL_STATUS_OK, br#"{"has_error":false,"status_code":"200"}"#, b"Hello mock");
+        let mut template = NeutralIpcTemplate::from_src_value("{:;text:}", json!({"data": {"text": "Hello"}}))
+            .unwrap()
+            .with_config(server.config());
+
+        assert_eq!(template.render().unwrap(), "Hello mock");
+        assert_eq!(template.get_status_code(), "200");
+        assert!(!template.has_error());
+    }
+
+    #[test]
+    fn test_optional_result_fields() {
+        let mut template = NeutralIpcTemplate::new().unwrap();
+        assert_eq!(template.get_render_time_ms(), None);
+        assert_eq!(template.get_template_variables_used(), None);
+        assert_eq!(template.get_server_warnings(), None);
+
+        template.result.insert("result".to_string(), json!({
+            "render_time_ms": 1.25,
+            "variables_used": ["text", 1, "number"],
+            "warnings": "not a list"
+        }));
+        assert_eq!(template.get_render_time_ms(), Some(1.25));
+        assert_eq!(template.get_template_variables_used(), Some(vec!["text".to_string(), "number".to_string()]));
+        assert_eq!(template.get_server_warnings(), None);
+    }
+
+    #[cfg(feature = "validate")]
+    fn validated_template(schema: Value) -> NeutralIpcTemplate {
+        let mut template = NeutralIpcTemplate::from_src_value("{:;title:}", schema).unwrap();
+        template.set_validation_schema(json!({
+            "type": "object",
+            "required": ["data"],
+            "properties": {
+                "data": {
+                    "type": "object",
+                    "required": ["title", "count"],
+                    "properties": {
+                        "title": {"type": "string"},
+                        "count": {"type": "integer"}
+                    }
+                }
+            }
+        }));
+        template
+    }
+
+    #[cfg(feature = "validate")]
+    #[test]
+    fn test_validation_schema_passes() {
+        let server = MockServer::respond_with(CTRL_STATUS_OK, b"{}", b"ok");
+        let template = validated_template(json!({"data": {"title": "Hello", "count": 3}}));
+        let mut template = template.with_config(server.config());
+
+        assert_eq!(template.render().unwrap(), "ok");
+    }
+
+    #[cfg(feature = "validate")]
+    #[test]
+    fn test_validation_schema_missing_required_property() {
+        let mut template = validated_template(json!({"data": {"titel": "Typo", "count": 3}}));
+
+        match template.render() {
+            Err(NeutralIpcError::SchemaValidation(violations)) => {
+                assert_eq!(violations.len(), 1);
+                assert_eq!(violations[0].0, "/data");
+                assert!(violations[0].1.contains("title"));
+            }
+            other => panic!("expected SchemaValidation, got {:?}", other),
+        }
+        assert!(template.get_result().is_none());
+    }
+
+    #[cfg(feature = "validate")]
+    #[test]
+    fn test_validation_schema_wrong_type() {
+        let mut template = validated_template(json!({"data": {"title": 1, "count": "three"}}));
+
+        match template.render() {
+            Err(NeutralIpcError::SchemaValidation(violations)) => {
+                let mut paths: Vec<&str> = violations.iter().map(|(path, _)| path.as_str()).collect();
+                paths.sort();
+                assert_eq!(paths, ["/data/count", "/data/title"]);
+            }
+            other => panic!("expected SchemaValidation, got {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "validate")]
+    #[test]
+    fn test_render_unchecked_skips_validation() {
+        let server = MockServer::respond_with(CTRL_STATUS_OK, b"{}", b"ok");
+        let template = validated_template(json!({"data": {}}));
+        let mut template = template.with_config(server.config());
+
+        assert!(matches!(template.render(), Err(NeutralIpcError::SchemaValidation(_))));
+        assert_eq!(template.render_unchecked().unwrap(), "ok");
+    }
+
+    #[cfg(feature = "validate")]
+    #[test]
+    fn test_dry_run_validates_schema() {
+        let template = validated_template(json!({"data": {"title": "Hello"}}));
+        assert!(matches!(template.dry_run(), Err(NeutralIpcError::SchemaValidation(_))));
+
+        let template = validated_template(json!({"data": {"title": "Hello", "count": 3}}));
+        assert!(template.dry_run().is_ok());
+    }
+
+    #[cfg(all(feature = "validate", any(feature = "async-tokio", feature = "async-std")))]
+    #[test]
+    fn test_render_async_validates_schema() {
+        let mut template = validated_template(json!({"data": {"title": "Hello"}}));
+
+        assert!(matches!(block_on(template.render_async()), Err(NeutralIpcError::SchemaValidation(_))));
+        assert!(template.get_result().is_none());
+    }
+
+    #[test]
+    fn test_compile_and_render_by_token() {
+        let server = MockServer::start(|mut stream| {
+            let Some((header, content1, content2)) = read_request(&mut stream) else { return };
+            match header[1] {
+                CTRL_VALIDATE_TEMPLATE => {
+                    assert_eq!(content2, b"Hello {:;text:}!");
+                    write_response(&mut stream, CTRL_STATUS_OK, br#"{"token":"tpl-1"}"#, b"");
+                }
+                CTRL_RENDER_COMPILED if content2 == b"tpl-1" => {
+                    let schema: Value = serde_json::from_slice(&content1).unwrap();
+                    let rendered = format!("Hello {}!", schema["data"]["text"].as_str().unwrap());
+                    write_response(&mut stream, CTRL_STATUS_OK, b"{}", rendered.as_bytes());
+                }
+                _ => write_response(&mut stream, CTRL_STATUS_KO, b"{}", b""),
+            }
+        });
+        let mut template = NeutralIpcTemplate::from_src_value("Hello {:;text:}!", json!({}))
+            .unwrap()
+            .with_config(server.config());
+
+        let compiled = template.compile().unwrap();
+
+        assert_eq!(compiled.token(), "tpl-1");
+        assert_eq!(template.get_result(), Some(&json!({"token": "tpl-1"})));
+        assert_eq!(compiled.render(json!({"data": {"text": "World"}})).unwrap(), "Hello World!");
+        assert_eq!(compiled.render(r#"{"data":{"text":"again"}}"#.into()).unwrap(), "Hello again!");
+
+        let unknown = CompiledTemplate { token: "expired".to_string(), config: server.config() };
+        assert!(matches!(unknown.render(json!({})), Err(NeutralIpcError::NotSupported)));
+    }
+
+    #[test]
+    fn test_render_to_string_lossy_replaces_invalid_utf8() {
+        use std::error::Error;
+
+        let server = MockServer::start(|mut stream| {
+            if read_request(&mut stream).is_some() {
+                write_response(&mut stream, CTRL_STATUS_OK, br#"{"status_code":"200"}"#, b"caf\xe9");
+            }
+        });
+        let mut template = NeutralIpcTemplate::from_src_value("{:;text:}", json!({"data": {}}))
+            .unwrap()
+            .with_config(server.config());
+
+        let err = template.render().unwrap_err();
+        assert!(matches!(err.source().and_then(|source| source.downcast_ref()), Some(NeutralIpcError::InvalidUtf8(_))), "{:?}", err);
+
+        assert_eq!(template.render_to_string_lossy().unwrap(), "caf\u{FFFD}");
+        assert!(!template.config.as_ref().unwrap().get_lossy_utf8());
+        assert!(template.render().is_err());
+    }
+
+    #[test]
+    fn test_render_escaped() {
+        let server = MockServer::respond_with(CTRL_STATUS_OK, b"{}", b"<a href=\"x\">Tom & 'Jerry'</a>\n`\\");
+        let mut template = NeutralIpcTemplate::from_src_value("{:;link:}", json!({"data": {}}))
+            .unwrap()
+            .with_config(server.config());
+
+        assert_eq!(
+            template.render_to_html_escaped().unwrap(),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; &#39;Jerry&#39;&lt;/a&gt;\n`\\"
+        );
+        assert_eq!(template.get_content(), Some("<a href=\"x\">Tom & 'Jerry'</a>\n`\\"));
+        assert_eq!(
+            template.render_to_js_escaped().unwrap(),
+            "\\u003ca href=\\\"x\\\"\\u003eTom \\u0026 \\'Jerry\\'\\u003c/a\\u003e\\n\\`\\\\"
+        );
+        assert_eq!(NeutralIpcTemplate::escape_js("\u{2028}\u{1}é"), "\\u2028\\u0001é");
+        assert_eq!(NeutralIpcTemplate::escape_js("`${x}`"), "\\`\\u0024{x}\\`");
+    }
+
+    /// Poll `future` to completion on the current thread.
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        struct ThreadWaker(thread::Thread);
+
+        impl std::task::Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = std::task::Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut context = std::task::Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        loop {
+            match future.as_mut().poll(&mut context) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_with_timeout_future() {
+        let server = MockServer::respond_with(CTRL_STATUS_OK, br#"{"status_code":"200"}"#, b"Hi");
+        let template = NeutralIpcTemplate::from_src_value("{:;text:}", json!({"data": {"text": "Hi"}}))
+            .unwrap()
+            .with_config(server.config());
+        let rendered = template.render_with_timeout_future(Instant::now() + Duration::from_secs(5));
+        assert_eq!(block_on(rendered).unwrap(), "Hi");
+
+        let server = MockServer::start(|mut stream| {
+            if read_request(&mut stream).is_some() {
+                thread::sleep(Duration::from_millis(500));
+                write_response(&mut stream, CTRL_STATUS_OK, b"{}", b"late");
+            }
+        });
+        let template = NeutralIpcTemplate::from_src_value("{:;text:}", json!({"data": {}}))
+            .unwrap()
+            .with_config(server.config());
+        let started = Instant::now();
+        let rendered = template.render_with_timeout_future(started + Duration::from_millis(100));
+        assert!(matches!(block_on(rendered), Err(NeutralIpcError::DeadlineExceeded)));
+        assert!(started.elapsed() < Duration::from_millis(450));
+    }
+
+    #[test]
+    fn test_set_source_reader_streams_source() {
+        let server = MockServer::start(|mut stream| {
+            if let Some((_, _, content2)) = read_request(&mut stream) {
+                write_response(&mut stream, CTRL_STATUS_OK, b"{}", &content2);
+            }
+        });
+        let source: String = (0..50_000).map(|line| format!("{:05} {{:;text:}}\n", line)).collect();
+        let mut template = NeutralIpcTemplate::from_src_value("", json!({"data": {"text": "x"}}))
+            .unwrap()
+            .with_config(server.config());
+
+        template.set_source_reader(source.len() as u64, Box::new(io::Cursor::new(source.clone().into_bytes())));
+        assert_eq!(template.render().unwrap(), source);
+        assert!(matches!(template.prepare(), Err(NeutralIpcError::SourceReaderUnavailable(_))));
+
+        match template.render() {
+            Err(NeutralIpcError::Render { template, source: err, .. }) => {
+                assert_eq!(template, format!("source reader of {} bytes", source.len()));
+                assert!(matches!(*err, NeutralIpcError::SourceReaderUnavailable(_)));
+            }
+            other => panic!("expected Render, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_set_source_reader_length_mismatch() {
+        let server = MockServer::start(|mut stream| {
+            read_request(&mut stream);
+        });
+        let mut template = NeutralIpcTemplate::from_src_value("", json!({})).unwrap().with_config(server.config());
+        let mismatch = |template: &mut NeutralIpcTemplate| match template.render() {
+            Err(NeutralIpcError::Render { source, .. }) => match *source {
+                NeutralIpcError::ContentLengthMismatch { expected, actual } => (expected, actual),
+                other => panic!("expected ContentLengthMismatch, got {:?}", other),
+            },
+            other => panic!("expected Render, got {:?}", other),
+        };
+
+        template.set_source_reader(100, Box::new(io::Cursor::new(vec![b'a'; 10])));
+        assert_eq!(mismatch(&mut template), (100, 10));
+
+        template.set_source_reader(5, Box::new(io::Cursor::new(vec![b'a'; 10])));
+        assert_eq!(mismatch(&mut template), (5, 10));
+
+        template.set_source_reader(u32::MAX as u64 + 1, Box::new(io::empty()));
+        assert!(matches!(template.render(), Err(NeutralIpcError::TemplateTooLarge { .. })));
+    }
+
+    #[test]
+    fn test_set_locale() {
+        let mut template = NeutralIpcTemplate::from_src_value("", json!({"data": {"_neutral_locale": "user"}})).unwrap();
+        assert_eq!(template.get_locale(), None);
+
+        template.set_strict_schema_layout(true);
+        template.set_locale("es-ES").unwrap();
+        template.set_locale("en-US").unwrap();
+        assert_eq!(template.get_locale().as_deref(), Some("en-US"));
+        assert!(template.schema_contains(&json!({"data": {"_neutral_locale": "user"}})).unwrap());
+        template.merge_schema(json!({"data": {"text": "Hi"}})).unwrap();
+        assert_eq!(template.get_locale().as_deref(), Some("en-US"));
+
+        template.seal_schema();
+        assert!(matches!(template.set_locale("fr"), Err(NeutralIpcError::SchemaSealedError)));
+    }
+
+    #[test]
+    fn test_render_error_names_template() {
+        use std::error::Error;
+
+        let server = MockServer::respond_with(CTRL_STATUS_OK, b"", b"");
+        let mut template = NeutralIpcTemplate::from_file_value("tpl/home.ntpl", json!({"data": {}}))
+            .unwrap()
+            .with_config(server.config());
+
+        let err = template.render().unwrap_err();
+        assert!(matches!(err, NeutralIpcError::Render { schema_size: 11, .. }));
+        assert!(err.to_string().starts_with("render of 'tpl/home.ntpl' failed: "), "{}", err);
+        assert!(matches!(err.source().and_then(|source| source.downcast_ref()), Some(NeutralIpcError::InvalidResultJson { .. })));
+
+        template.set_source("{:;text:}");
+        match template.render() {
+            Err(NeutralIpcError::Render { template, .. }) => {
+                assert!(template.starts_with("source "), "{}", template);
+                assert_eq!(template.len(), "source ".len() + 8);
+            }
+            other => panic!("expected Render, got {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn test_watch_template_file_reloads_on_change() {
+        let dir = std::env::temp_dir().join(format!("neutralipcrs-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("page.ntpl");
+        std::fs::write(&file, "first").unwrap();
+
+        let server = MockServer::start(|mut stream| {
+            if let Some((header, _, content2)) = read_request(&mut stream) {
+                let rendered = format!("{}:{}", header[7], String::from_utf8_lossy(&content2));
+                write_response(&mut stream, CTRL_STATUS_OK, b"{}", rendered.as_bytes());
+            }
+        });
+        let mut template = NeutralIpcTemplate::from_file_value(file.to_str().unwrap(), json!({}))
+            .unwrap()
+            .with_config(server.config());
+        template.watch_template_file().unwrap();
+        assert_eq!(template.render().unwrap(), format!("{}:{}", CONTENT_PATH, file.display()));
+
+        std::fs::write(&file, "second").unwrap();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while !template.watch.as_ref().unwrap().dirty.load(Ordering::SeqCst) {
+            assert!(std::time::Instant::now() < deadline, "no change event");
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(template.render().unwrap(), format!("{}:second", CONTENT_TEXT));
+        assert_eq!(template.source(), Some("second"));
+
+        template.stop_watching_template();
+        assert!(template.watch.is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+
+        template.set_source("inline");
+        assert!(matches!(template.watch_template_file(), Err(NeutralIpcError::Watch(_))));
+    }
+
+    #[test]
+    fn test_template_cache_negotiation_hit_and_miss() {
+        use std::sync::Mutex;
+
+        let hash: String = Sha256::digest(b"Hello {:;text:}!").iter().map(|byte| format!("{:02x}", byte)).collect();
+        let cache: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let requests: Arc<Mutex<Vec<u8>>> = Arc::new(Mutex::new(Vec::new()));
+        let server = {
+            let (cache, requests) = (Arc::clone(&cache), Arc::clone(&requests));
+            MockServer::start(move |mut stream| {
+                let Some((header, _, content2)) = read_request(&mut stream) else { return };
+                requests.lock().unwrap().push(header[1]);
+                let content2 = String::from_utf8(content2).unwrap();
+                match header[1] {
+                    CTRL_PARSE_TEMPLATE_HASH if cache.lock().unwrap().contains(&content2) => {
+                        write_response(&mut stream, CTRL_STATUS_OK, b"{}", b"cached");
+                    }
+                    CTRL_PARSE_TEMPLATE_HASH => {
+                        write_response(&mut stream, CTRL_STATUS_KO, br#"{"message":"unknown template hash"}"#, b"")
+                    }
+                    _ => {
+                        let hash: String = Sha256::digest(content2.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect();
+                        cache.lock().unwrap().push(hash);
+                        write_response(&mut stream, CTRL_STATUS_OK, b"{}", b"full");
+                    }
+                }
+            })
+        };
+        let mut config = server.config();
+        config.set_template_cache_negotiation(true);
+        let mut template = NeutralIpcTemplate::from_src_value("Hello {:;text:}!", json!({})).unwrap().with_config(config);
+
+        assert_eq!(template.render().unwrap(), "full");
+        assert_eq!(cache.lock().unwrap().as_slice(), [hash]);
+        assert_eq!(template.render().unwrap(), "cached");
+        assert_eq!(
+            requests.lock().unwrap().as_slice(),
+            [CTRL_PARSE_TEMPLATE_HASH, CTRL_PARSE_TEMPLATE, CTRL_PARSE_TEMPLATE_HASH]
+        );
+    }
+
+    #[test]
+    fn test_template_cache_negotiation_falls_back_on_ko() {
+        let server = MockServer::start(|mut stream| {
+            let Some((header, _, content2)) = read_request(&mut stream) else { return };
+            match header[1] {
+                CTRL_PARSE_TEMPLATE => write_response(&mut stream, CTRL_STATUS_OK, b"{}", &content2),
+                _ => write_response(&mut stream, CTRL_STATUS_KO, b"{}", b""),
+            }
+        });
+        let mut config = server.config();
+        config.set_template_cache_negotiation(true);
+        let mut template = NeutralIpcTemplate::from_src_value("source", json!({})).unwrap().with_config(config);
+
+        assert_eq!(template.render().unwrap(), "source");
+    }
+
+    #[test]
+    fn test_force_endpoint_and_recorded_endpoint() {
+        let server = MockServer::respond_with(CTRL_STATUS_OK, b"{}", b"pinned");
+        let addr: SocketAddr = format!("127.0.0.1:{}", server.config().get_port()).parse().unwrap();
+        let mut config = server.config();
+        config.set_port(1);
+        let mut template = NeutralIpcTemplate::from_src_value("", json!({})).unwrap().with_config(config);
+        assert_eq!(template.get_endpoint(), None);
+
+        template.force_endpoint(addr);
+        assert_eq!(template.render().unwrap(), "pinned");
+        assert_eq!(template.get_endpoint(), Some(addr));
+
+        template.clear_forced_endpoint();
+        assert!(template.render().is_err());
+        assert_eq!(template.get_endpoint(), Some(addr));
+    }
+
+    #[test]
+    fn test_sealed_schema_rejects_modification() {
+        let mut template = NeutralIpcTemplate::from_src_value("", json!({"data": {"site": "Example"}})).unwrap();
+        assert!(!template.is_schema_sealed());
+        template.seal_schema();
+        assert!(template.is_schema_sealed());
+
+        let sealed = |result: Result<()>| matches!(result, Err(NeutralIpcError::SchemaSealedError));
+        assert!(sealed(template.merge_schema(json!({"data": {"site": "Other"}}))));
+        assert!(sealed(template.merge_schema_file(Path::new("missing.json"))));
+        assert!(sealed(template.append_schema_array("data.items", json!(1))));
+        assert!(sealed(template.coerce_schema_numbers()));
+        assert!(sealed(template.replace_schema_typed(&json!({}))));
+        assert!(sealed(template.try_set_schema_msgpack(b"\x80")));
+        assert!(sealed(template.try_set_schema_path("/schema.json")));
+        assert!(sealed(template.try_set_schema_content_type(ContentFormat::Json)));
+        template.set_schema_msgpack(b"\x80");
+        template.set_schema_path("/schema.json");
+        template.set_schema_content_type(ContentFormat::Other(70));
+        assert_eq!(template.schema_type, CONTENT_JSON);
+        assert!(sealed(template.set_schema_from_env("NEUTRAL_TEST_UNSET_VARIABLE")));
+        assert_eq!(template.schema, br#"{"data":{"site":"Example"}}"#);
+
+        template.unseal_schema();
+        template.merge_schema(json!({"data": {"site": "Other"}})).unwrap();
+        assert_eq!(template.schema, br#"{"data":{"site":"Other"}}"#);
+    }
+
+    #[test]
+    fn test_render_as_json() {
+        let server = MockServer::start(|mut stream| {
+            if let Some((_, _, content2)) = read_request(&mut stream) {
+                write_response(&mut stream, CTRL_STATUS_OK, b"{}", &content2);
+            }
+        });
+        let mut template = NeutralIpcTemplate::from_src_value(r#"{"id":7,"tags":["a"]}"#, json!({}))
+            .unwrap()
+            .with_config(server.config());
+
+        assert_eq!(template.render_as_json().unwrap(), json!({"id": 7, "tags": ["a"]}));
+        assert_eq!(template.render_as_json_pretty().unwrap(), "{\n  \"id\": 7,\n  \"tags\": [\n    \"a\"\n  ]\n}");
+
+        template.set_source("<p>not json</p>");
+        assert!(matches!(template.render_as_json(), Err(NeutralIpcError::Json(_))));
+        assert_eq!(template.last_render_word_count(), Some(2));
+    }
+
+    #[test]
+    fn test_set_schema_path_sends_path() {
+        let server = MockServer::start(|mut stream| {
+            let Some((header, content1, _)) = read_request(&mut stream) else { return };
+            assert_eq!(header[2], CONTENT_PATH);
+            write_response(&mut stream, CTRL_STATUS_OK, b"{}", &content1);
+        });
+        let mut template = NeutralIpcTemplate::from_src_value("", json!({"data": {}})).unwrap().with_config(server.config());
+        template.set_schema_path("/shared/schema.json");
+
+        assert_eq!(template.render().unwrap(), "/shared/schema.json");
+        assert!(matches!(template.merge_schema(json!({"data": {}})), Err(NeutralIpcError::InvalidSchema(_))));
+
+        template.replace_schema_typed(&json!({"data": {"text": "inline"}})).unwrap();
+        assert_eq!(template.schema_type, CONTENT_JSON);
+    }
+
+    #[test]
+    fn test_custom_content_types() {
+        let server = MockServer::start(|mut stream| {
+            let Some((header, content1, content2)) = read_request(&mut stream) else { return };
+            assert_eq!((header[2], header[7]), (70, 80));
+            assert_eq!(content1, b"raw schema");
+            write_response(&mut stream, CTRL_STATUS_OK, b"{}", &content2);
+        });
+        let mut template = NeutralIpcTemplate::from_src_value("custom template", json!({})).unwrap().with_config(server.config());
+        template.set_schema_msgpack(b"raw schema");
+        template.set_schema_content_type(ContentFormat::Other(70));
+        template.set_template_content_type(ContentFormat::from(80));
+
+        assert_eq!(template.kind(), TemplateKind::Path);
+        assert_eq!(template.render().unwrap(), "custom template");
+
+        template.set_template_content_type(ContentFormat::Text);
+        assert_eq!(template.kind(), TemplateKind::Source);
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let source = "Hello {:;text:}! ".repeat(100);
+        let mut config = NeutralIpcConfig::default();
+        config.set_read_buffer_size(7);
+        let mut template = NeutralIpcTemplate::new().unwrap().with_config(config);
+        template.set_source_from_reader(source.as_bytes()).unwrap();
+        assert_eq!(template.source(), Some(source.as_str()));
+
+        let template = NeutralIpcTemplate::from_reader(&b"{:;text:}"[..], json!({"data": {}})).unwrap();
+        assert_eq!(template.source(), Some("{:;text:}"));
+
+        let mut template = NeutralIpcTemplate::from_reader(&b"kept"[..], json!({})).unwrap();
+        let err = template.set_source_from_reader(&b"\xff\xfe"[..]).unwrap_err();
+        assert!(matches!(err, NeutralIpcError::InvalidUtf8(_)));
+        assert_eq!(template.source(), Some("kept"));
+    }
+
+    #[test]
+    fn test_set_schema_path_unreadable() {
+        let server = MockServer::respond_with(CTRL_STATUS_KO, b"{}", b"cannot read /missing.json: No such file");
+        let mut template = NeutralIpcTemplate::from_src_value("", json!({})).unwrap().with_config(server.config());
+        template.set_schema_path("/missing.json");
+
+        match template.render() {
+            Err(NeutralIpcError::Render { source, .. }) => match *source {
+                NeutralIpcError::ServerError(diagnostics) => {
+                    assert!(diagnostics.contains("/missing.json"));
+                    assert!(diagnostics.contains("No such file"));
+                }
+                other => panic!("expected ServerError, got {:?}", other),
+            },
+            other => panic!("expected Render, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_last_server_diagnostics() {
+        let diagnostics = br#"{"message":"unexpected end of block","code":"E_PARSE","line":12}"#;
+        let server = MockServer::respond_with(CTRL_STATUS_KO, diagnostics, b"");
+        let mut template = NeutralIpcTemplate::from_src_value("{:;text:", json!({})).unwrap().with_config(server.config());
+        assert_eq!(template.last_server_diagnostics(), None);
+
+        assert_eq!(template.render().unwrap(), "");
+        assert!(template.has_error());
+        assert_eq!(template.get_result().unwrap()["code"], "E_PARSE");
+        assert_eq!(template.last_server_diagnostics().unwrap().message, "unexpected end of block");
+
+        template.set_fail_on_server_error(true);
+        match template.render() {
+            Err(NeutralIpcError::Render { source, .. }) => {
+                assert!(matches!(*source, NeutralIpcError::ServerError(ref message) if message == "unexpected end of block"));
+            }
+            other => panic!("expected Render, got {:?}", other),
+        }
+        let parsed = template.last_server_diagnostics().unwrap();
+        assert_eq!(parsed.message, "unexpected end of block");
+        assert_eq!(parsed.code.as_deref(), Some("E_PARSE"));
+        assert_eq!(parsed.line, Some(12));
+        assert_eq!(parsed.stack, None);
+        assert!(template.has_error());
+
+        let server = MockServer::respond_with(CTRL_STATUS_KO, b"", b"template not found: <missing>");
+        let mut template = NeutralIpcTemplate::from_src_value("", json!({})).unwrap().with_config(server.config());
+        assert_eq!(template.render().unwrap(), "template not found: <missing>");
+        assert_eq!(template.get_result(), Some(&Value::Null));
+        let parsed = template.last_server_diagnostics().unwrap();
+        assert_eq!(parsed.raw, "template not found: <missing>");
+        assert_eq!(parsed.message, "template not found: <missing>");
+        assert_eq!(parsed.code, None);
+    }
+
+    #[test]
+    fn test_to_netcat_command_and_hex_dump() {
+        let mut config = NeutralIpcConfig::default();
+        config.set_port(4321);
+        let template = NeutralIpcTemplate::from_src_value("hi", json!({})).unwrap().with_config(config);
+        let request = NeutralIpcRecord::encode_record(CTRL_PARSE_TEMPLATE, CONTENT_JSON, b"{}", CONTENT_TEXT, b"hi");
+
+        let command = template.to_netcat_command().unwrap();
+        let (comment, command) = command.split_once('\n').unwrap();
+        assert_eq!(comment, format!("# header: {} bytes, body: 4 bytes", HEADER_LEN));
+        assert!(command.ends_with("' | nc 127.0.0.1 4321"));
+        let escaped: String = command.split('\'').filter(|part| part.starts_with('\\')).collect();
+        assert!(escaped.split('\\').skip(1).all(|octal| octal.len() == 3));
+        let bytes: Vec<u8> = escaped.split('\\').skip(1).map(|octal| u8::from_str_radix(octal, 8).unwrap()).collect();
+        assert_eq!(bytes, request);
+
+        let dump = template.to_hex_dump().unwrap();
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with(&format!("00000000  {:02x} {:02x} ", request[0], request[1])));
+        assert!(lines[0].ends_with("{}hi|"));
     }
 
-    /// Check if the last rendering operation resulted in an error.
-    ///
-    /// This method examines the result from the last `render()` call and
-    /// determines if an error occurred based on:
-    /// - The status code (non-zero indicates error)
-    /// - The `has_error` field in the result data
-    ///
-    /// # Returns
-    ///
-    /// `true` if an error occurred, `false` otherwise.
-    pub fn has_error(&self) -> bool {
-        if let Some(status) = self.result.get("status").and_then(|v| v.as_u64()) {
-            if status != 0 {
-                return true;
+    #[test]
+    fn test_connection_overrides() {
+        let server = MockServer::start(|mut stream| {
+            if let Some((_, _, content2)) = read_request(&mut stream) {
+                thread::sleep(Duration::from_millis(300));
+                write_response(&mut stream, CTRL_STATUS_OK, b"{}", &content2);
             }
+        });
+        let mut template = NeutralIpcTemplate::from_src_value("slow", json!({})).unwrap().with_config(server.config());
+        assert_eq!(template.render().unwrap(), "slow");
+
+        template.set_timeout(Duration::from_millis(50));
+        match template.render() {
+            Err(NeutralIpcError::Render { source, .. }) => match *source {
+                NeutralIpcError::Io(err) => {
+                    assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+                    assert_eq!(err.to_string(), "timeout of 50ms exceeded during read");
+                }
+                other => panic!("expected Io, got {:?}", other),
+            },
+            other => panic!("expected Render, got {:?}", other),
         }
+        assert!(template.render().unwrap_err().is_timeout());
 
-        if let Some(result) = self.result.get("result") {
-            if let Some(has_error) = result.get("has_error").and_then(|v| v.as_bool()) {
-                return has_error;
+        template.set_connect_timeout(Duration::from_millis(20));
+        template.set_buffer_size(64);
+        template.set_timeout(Duration::ZERO);
+        template.set_connect_timeout(Duration::ZERO);
+        template.set_buffer_size(0);
+        let config = template.connection_config();
+        assert_eq!(config.get_timeout_duration(), Duration::from_millis(50));
+        assert_eq!(config.get_connect_timeout(), Duration::from_millis(20));
+        assert_eq!(config.get_read_buffer_size(), 64);
+        assert_eq!(config.get_write_buffer_size(), 64);
+
+        template.clear_connection_overrides();
+        let config = template.connection_config();
+        assert_eq!(config.get_timeout_duration(), Duration::from_secs(config.get_timeout() as u64));
+        assert_eq!(config.get_read_buffer_size(), server.config().get_read_buffer_size());
+        assert_eq!(template.render().unwrap(), "slow");
+    }
+
+    #[test]
+    fn test_iter_schema_values() {
+        let template = NeutralIpcTemplate::from_src_value("", json!({
+            "config": {"locale": "en"},
+            "data": {"items": [{"name": "a"}, 2], "empty": {}, "title": "Home"}
+        })).unwrap();
+
+        let leaves: Vec<_> = template.iter_schema_values().unwrap().collect();
+        assert_eq!(leaves, template.get_schema_leaf_values().unwrap());
+        assert_eq!(leaves.len(), 5);
+
+        let at = |prefix: &str| template.iter_schema_values_at(prefix).unwrap().collect::<Vec<_>>();
+        assert_eq!(at("data.items"), [
+            ("data.items.0.name".to_string(), json!("a")),
+            ("data.items.1".to_string(), json!(2)),
+        ]);
+        assert_eq!(at("data.title"), [("data.title".to_string(), json!("Home"))]);
+        assert_eq!(at("data.empty"), [("data.empty".to_string(), json!({}))]);
+        assert!(at("data.missing").is_empty());
+        assert!(at("data.items.7").is_empty());
+        assert!(at("data.title.x").is_empty());
+        assert!(NeutralIpcTemplate::from_src_value("", json!({})).unwrap().iter_schema_values().unwrap().next().is_none());
+    }
+
+    #[test]
+    fn test_schema_layout() {
+        let server = MockServer::respond_with(CTRL_STATUS_OK, b"{}", b"rendered");
+        let mut template = NeutralIpcTemplate::from_src_value("", json!({"data": {}}))
+            .unwrap()
+            .with_config(server.config());
+        template.merge_schema(json!({"title": "Home", "config": {}})).unwrap();
+        assert_eq!(template.render().unwrap(), "rendered");
+
+        template.set_strict_schema_layout(true);
+        assert!(matches!(template.render(), Err(NeutralIpcError::SchemaLayout(keys)) if keys == ["title"]));
+        let err = template.merge_schema(json!({"user": {}})).unwrap_err();
+        assert!(matches!(err, NeutralIpcError::SchemaLayout(keys) if keys == ["title", "user"]));
+        assert_eq!(template.decode_schema().unwrap(), json!({"data": {}, "title": "Home", "config": {}}));
+
+        template.set_schema_sections(&["data", "config", "title"]);
+        assert_eq!(template.render().unwrap(), "rendered");
+    }
+
+    #[test]
+    fn test_reload_server_config() {
+        let server = MockServer::start(|mut stream| {
+            if let Some((header, _, _)) = read_request(&mut stream) {
+                // Wire value expected by the server.
+                assert_eq!(header[1], 15);
+                write_response(&mut stream, CTRL_STATUS_OK, b"{}", b"");
             }
+        });
+        NeutralIpcTemplate::reload_server_config_with(&server.config()).unwrap();
+
+        let server = MockServer::respond_with(CTRL_STATUS_KO, br#"{"code":"permission_denied","message":"uid 1000 not allowed"}"#, b"");
+        match NeutralIpcTemplate::reload_server_config_with(&server.config()) {
+            Err(NeutralIpcError::PermissionDenied(reason)) => assert_eq!(reason, "uid 1000 not allowed"),
+            other => panic!("expected PermissionDenied, got {:?}", other),
         }
 
-        false
+        let server = MockServer::respond_with(CTRL_STATUS_KO, b"", b"invalid configuration file");
+        match NeutralIpcTemplate::reload_server_config_with(&server.config()) {
+            Err(NeutralIpcError::ServerError(reason)) => assert_eq!(reason, "invalid configuration file"),
+            other => panic!("expected ServerError, got {:?}", other),
+        }
     }
 
-    /// Get the status code from the last rendering result.
-    ///
-    /// # Returns
-    ///
-    /// The status code as `&str` from the JSON, or an empty string if not present or if
-    /// any error occurs during extraction.
-    pub fn get_status_code(&self) -> &str {
-        self.result.get("result")
-            .and_then(|r| r.get("status_code"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-    }
+    #[test]
+    fn test_extend_schema_and_merge_from_other() {
+        let base = NeutralIpcTemplate::from_src_value("", json!({
+            "config": {"locale": "en"},
+            "data": {"site": "Example", "title": "Example"}
+        })).unwrap();
+        let page_schema = json!({"data": {"title": "Home", "items": [1]}});
 
-    /// Get the status text from the last rendering result.
-    ///
-    /// # Returns
-    ///
-    /// The status text as `&str` from the JSON, or an empty string reference
-    /// if not present or if any error occurs during extraction.
-    pub fn get_status_text(&self) -> &str {
-        self.result.get("result")
-            .and_then(|r| r.get("status_text"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
+        let mut page = NeutralIpcTemplate::from_src_value("", page_schema.clone()).unwrap();
+        page.extend_schema(&base).unwrap();
+        assert_eq!(page.decode_schema().unwrap(), json!({
+            "config": {"locale": "en"},
+            "data": {"site": "Example", "title": "Home", "items": [1]}
+        }));
+
+        let mut page = NeutralIpcTemplate::from_src_value("", page_schema).unwrap();
+        page.merge_from_other(&base).unwrap();
+        assert_eq!(page.decode_schema().unwrap(), json!({
+            "config": {"locale": "en"},
+            "data": {"site": "Example", "title": "Example", "items": [1]}
+        }));
+
+        page.seal_schema();
+        assert!(matches!(page.extend_schema(&base), Err(NeutralIpcError::SchemaSealedError)));
+        let mut page = NeutralIpcTemplate::new().unwrap();
+        page.set_schema_path("/srv/schema.json");
+        assert!(matches!(page.extend_schema(&base), Err(NeutralIpcError::InvalidSchema(_))));
     }
 
-    /// Get the status parameter from the last rendering result.
-    ///
-    /// # Returns
-    ///
-    /// The status parameter as `&str` from the JSON, or an empty string reference
-    /// if not present or if any error occurs during extraction.
-    pub fn get_status_param(&self) -> &str {
-        self.result.get("result")
-            .and_then(|r| r.get("status_param"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
+    #[test]
+    fn test_last_result_accessors() {
+        let server = MockServer::respond_with(CTRL_STATUS_OK, br#"{"status_code":"200"}"#, b"Hello");
+        let mut template = NeutralIpcTemplate::from_src_value("Hello", json!({})).unwrap().with_config(server.config());
+        assert_eq!(template.get_raw_status(), None);
+        assert_eq!(template.get_content(), None);
+        assert_eq!(template.get_result(), None);
+
+        template.render().unwrap();
+        assert_eq!(template.get_raw_status(), Some(CTRL_STATUS_OK));
+        assert_eq!(template.get_content(), Some("Hello"));
+        assert_eq!(template.get_result(), Some(&json!({"status_code": "200"})));
+
+        let server = MockServer::respond_with(CTRL_STATUS_KO, br#"{"message":"boom"}"#, b"");
+        let mut template = template.with_config(server.config());
+        assert_eq!(template.render().unwrap(), "");
+        assert_eq!(template.get_raw_status(), Some(CTRL_STATUS_KO));
+        assert_eq!(template.get_content(), Some(""));
+        assert_eq!(template.get_result(), Some(&json!({"message": "boom"})));
+
+        template.set_fail_on_server_error(true);
+        assert!(template.render().is_err());
+        assert_eq!(template.get_raw_status(), Some(CTRL_STATUS_KO));
+        assert_eq!(template.get_result(), Some(&json!({"message": "boom"})));
     }
 
-    /// Get the complete result data from the last rendering operation.
-    ///
-    /// # Returns
-    ///
-    /// A reference to the complete result `Value` if available, or `None`
-    /// if no result has been stored yet.
-    pub fn get_result(&self) -> Option<&Value> {
-        self.result.get("result")
+    #[test]
+    fn test_output_normalization_options() {
+        let server = MockServer::respond_with(CTRL_STATUS_OK, b"{}", "\u{feff}<p>one</p>  \r\n<p>two</p>\t\n\r\n".as_bytes());
+        let render = |configure: &dyn Fn(&mut NeutralIpcTemplate)| {
+            let mut template = NeutralIpcTemplate::from_src_value("legacy", json!({})).unwrap().with_config(server.config());
+            configure(&mut template);
+            let content = template.render().unwrap();
+            assert_eq!(template.result.get("content").and_then(|v| v.as_str()), Some(content.as_str()));
+            content
+        };
+
+        assert_eq!(render(&|_| {}), "\u{feff}<p>one</p>  \r\n<p>two</p>\t\n\r\n");
+        assert_eq!(render(&|t| t.set_strip_bom(true)), "<p>one</p>  \r\n<p>two</p>\t\n\r\n");
+        assert_eq!(
+            render(&|t| t.set_normalize_newlines(Some(NewlineStyle::Lf))),
+            "\u{feff}<p>one</p>  \n<p>two</p>\t\n\n"
+        );
+        assert_eq!(
+            render(&|t| t.set_normalize_newlines(Some(NewlineStyle::CrLf))),
+            "\u{feff}<p>one</p>  \r\n<p>two</p>\t\r\n\r\n"
+        );
+        assert_eq!(
+            render(&|t| t.set_trim_trailing_whitespace(true)),
+            "\u{feff}<p>one</p>\r\n<p>two</p>\n\r\n"
+        );
     }
 
-    /// Recursively merge two JSON values.
-    ///
-    /// For objects, this performs a deep merge where fields from `b` override
-    /// or are added to fields in `a`. For all other types, `b` completely
-    /// replaces `a`.
-    ///
-    /// # Arguments
-    ///
-    /// * `a` - The base JSON value
-    /// * `b` - The JSON value to merge into `a`
+    /// Start a mock server that sends the status, then `chunks` of content 50 ms apart.
     ///
-    /// # Returns
-    ///
-    /// The merged JSON value.
-    fn deep_merge(a: Value, b: Value) -> Value {
-        match (a, b) {
-            (Value::Object(mut map_a), Value::Object(map_b)) => {
-                for (key, value_b) in map_b {
-                    if let Some(value_a) = map_a.get_mut(&key) {
-                        *value_a = Self::deep_merge(value_a.clone(), value_b);
-                    } else {
-                        map_a.insert(key, value_b);
+    /// After the last chunk, the result of reading from the connection is sent to `closed`.
+    fn dribbling_server(chunks: &'static [&'static [u8]], closed: std::sync::mpsc::Sender<io::Result<usize>>) -> MockServer {
+        MockServer::start(move |mut stream| {
+            use std::io::Write;
+            if read_request(&mut stream).is_some() {
+                let content2 = chunks.concat();
+                let response = NeutralIpcRecord::encode_record(CTRL_STATUS_OK, CONTENT_JSON, br#"{"status_code":"200"}"#, CONTENT_TEXT, &content2);
+                let head = response.len() - content2.len();
+                stream.write_all(&response[..head]).unwrap();
+                for chunk in chunks {
+                    thread::sleep(Duration::from_millis(50));
+                    if stream.write_all(chunk).is_err() {
+                        break;
                     }
                 }
-                Value::Object(map_a)
+                let _ = closed.send(stream.read(&mut [0u8; 1]));
             }
-            (_, b) => b,
-        }
+        })
     }
-}
 
+    #[test]
+    fn test_render_stream_yields_chunks_as_they_arrive() {
+        let (closed, _) = std::sync::mpsc::channel();
+        let server = dribbling_server(&[b"first ", b"second ", b"third"], closed);
+        let mut template = NeutralIpcTemplate::from_src_value("", json!({})).unwrap().with_config(server.config());
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rmp_serde::from_slice;
-    use serde_json::json;
-    use crate::client::is_server_available;
+        let stream = template.render_stream().unwrap();
+        assert_eq!(template.get_status_code(), "200");
+        let chunks: Vec<Vec<u8>> = stream.collect::<Result<_>>().unwrap();
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.concat(), b"first second third");
 
-    /// Skip test if the Neutral server is not available.
-    ///
-    /// This helper function checks server availability and panics with a
-    /// clear message if the server is not running, allowing tests to be
-    /// skipped gracefully during development.
-    fn skip_if_server_unavailable() {
-        if !is_server_available() {
-            panic!("Neutral TS server not available - skipping test");
-        }
+        let mut content = String::new();
+        template.render_stream().unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "first second third");
     }
 
     #[test]
-    fn test_template_src() {
-        skip_if_server_unavailable();
+    fn test_render_stream_drop_closes_connection() {
+        let (closed, observed) = std::sync::mpsc::channel();
+        let server = dribbling_server(&[b"first", b"second"], closed);
+        let mut template = NeutralIpcTemplate::from_src_value("", json!({})).unwrap().with_config(server.config());
 
-        let schema = json!({
-            "data": {
-                "text": "Hello!",
-                "number": 123
+        let mut stream = template.render_stream().unwrap();
+        assert_eq!(stream.next().unwrap().unwrap(), b"first");
+        drop(stream);
+
+        let read = observed.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(!matches!(read, Ok(n) if n > 0));
+    }
+
+    #[test]
+    fn test_render_respects_retry_after() {
+        let attempts = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = Arc::clone(&attempts);
+        let server = MockServer::start(move |mut stream| {
+            if let Some((_, _, content2)) = read_request(&mut stream) {
+                if counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst) != 3 {
+                    write_response(&mut stream, CTRL_STATUS_KO, br#"{"message":"busy","retry_after_ms":30}"#, b"");
+                } else {
+                    write_response(&mut stream, CTRL_STATUS_OK, b"{}", &content2);
+                }
             }
         });
+        let mut config = server.config();
+        let mut template = NeutralIpcTemplate::from_src_value("ok", json!({})).unwrap().with_config(config.clone());
+        assert_eq!(template.render().unwrap(), "");
+        assert!(template.has_error());
+        assert_eq!(template.last_server_diagnostics().unwrap().message, "busy");
 
-        let mut template = NeutralIpcTemplate::from_src_value("Rust IPC client: {:;text:} {:;number:}", schema).unwrap();
-        let result = template.render().unwrap();
-        let status_code = template.get_status_code();
-        let status_text = template.get_status_text();
-        let status_param = template.get_status_param();
+        config.set_respect_retry_after(true);
+        let mut template = template.with_config(config.clone());
+        let started = std::time::Instant::now();
+        assert_eq!(template.render().unwrap(), "ok");
+        assert!(started.elapsed() >= Duration::from_millis(60));
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 4);
 
-        assert!(!template.has_error());
-        assert_eq!(status_code, "200");
-        assert_eq!(status_text, "OK");
-        assert_eq!(status_param, "");
-        assert_eq!(result, "Rust IPC client: Hello! 123");
+        config.set_max_retry_delay_ms(40);
+        let mut template = template.with_config(config);
+        match template.render() {
+            Err(NeutralIpcError::Render { source, .. }) => {
+                assert!(matches!(*source, NeutralIpcError::RateLimited { retry_after_ms: 30 }));
+            }
+            other => panic!("expected Render, got {:?}", other),
+        }
     }
 
     #[test]
-    fn test_template_file() {
-        skip_if_server_unavailable();
-
-        let schema = json!({
-            "data": {
-                "text": "Hello!",
-                "number": 123
+    fn test_render_diff() {
+        let server = MockServer::start(|mut stream| {
+            if let Some((_, content1, _)) = read_request(&mut stream) {
+                write_response(&mut stream, CTRL_STATUS_OK, b"{}", &content1);
             }
         });
+        let schema = json!({"data": {"variant": "stored"}});
+        let mut template = NeutralIpcTemplate::from_src_value("", schema.clone()).unwrap().with_config(server.config());
 
-        let manifest_dir = env!("CARGO_MANIFEST_DIR");
-        let tpl_file = format!("{}/tests/template.ntpl", manifest_dir);
+        let (a, b) = template.render_diff(json!({"data": {"variant": "a"}}), json!({"data": {"variant": "b"}})).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&a).unwrap(), json!({"data": {"variant": "a"}}));
+        assert_eq!(serde_json::from_str::<Value>(&b).unwrap(), json!({"data": {"variant": "b"}}));
+        assert_eq!(template.decode_schema().unwrap(), schema);
 
-        let mut template = NeutralIpcTemplate::from_file_value(&tpl_file, schema).unwrap();
-        let result = template.render().unwrap();
-        let status_code = template.get_status_code();
-        let status_text = template.get_status_text();
-        let status_param = template.get_status_param();
+        let (current, empty) = template.render_schema_diff().unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&current).unwrap(), schema);
+        assert_eq!(empty, "{}");
 
-        assert!(!template.has_error());
-        assert_eq!(status_code, "200");
-        assert_eq!(status_text, "OK");
-        assert_eq!(status_param, "");
-        assert_eq!(result, "Rust IPC client: Hello! 123");
+        template.set_schema_path("/etc/schema.json");
+        assert!(matches!(template.render_schema_diff(), Err(NeutralIpcError::InvalidSchema(_))));
+        assert!(matches!(template.render_diff(json!({}), json!({})), Err(NeutralIpcError::InvalidSchema(_))));
     }
 
-
     #[test]
-    fn test_template_merge_schema() {
-        skip_if_server_unavailable();
+    fn test_compile_unsupported() {
+        let server = MockServer::respond_with(CTRL_STATUS_KO, b"{}", b"");
+        let mut template = NeutralIpcTemplate::from_src_value("", json!({})).unwrap().with_config(server.config());
 
-        let schema = json!({
-            "data": {
-                "text": "Hello!",
-                "number": 123
-            }
-        });
+        assert!(matches!(template.compile(), Err(NeutralIpcError::NotSupported)));
+    }
 
-        let schema_merge = json!({
-            "data": {
-                "text": "Hello! (merged)"
+    #[test]
+    fn test_render_parallel_keeps_order() {
+        let server = MockServer::start(|mut stream| {
+            if let Some((_, _, content2)) = read_request(&mut stream) {
+                write_response(&mut stream, CTRL_STATUS_OK, b"{}", &content2);
             }
         });
+        let templates: Vec<NeutralIpcTemplate> = (0..20)
+            .map(|i| {
+                let template = NeutralIpcTemplate::from_src_value(&format!("template {}", i), json!({})).unwrap();
+                template.with_config(server.config())
+            })
+            .collect();
 
-        let mut template = NeutralIpcTemplate::from_src_value("Rust IPC client: {:;text:} {:;number:}", schema).unwrap();
-        let _ = template.merge_schema(schema_merge).unwrap();
-        let result = template.render().unwrap();
-        let status_code = template.get_status_code();
-        let status_text = template.get_status_text();
-        let status_param = template.get_status_param();
+        let results = NeutralIpcTemplate::render_parallel(templates);
 
-        assert!(!template.has_error());
-        assert_eq!(status_code, "200");
-        assert_eq!(status_text, "OK");
-        assert_eq!(status_param, "");
-        assert_eq!(result, "Rust IPC client: Hello! (merged) 123");
+        assert_eq!(results.len(), 20);
+        for (i, result) in results.into_iter().enumerate() {
+            assert_eq!(result.unwrap(), format!("template {}", i));
+        }
     }
 
     #[test]
-    fn test_template_404() {
-        skip_if_server_unavailable();
+    fn test_render_parallel_reports_each_failure() {
+        let server = MockServer::respond_with(CTRL_STATUS_OK, b"{}", b"ok");
+        let mut too_large = NeutralIpcTemplate::from_src_value("", json!({"data": {}})).unwrap();
+        too_large.set_max_schema_size(Some(1));
+        let templates = vec![
+            NeutralIpcTemplate::from_src_value("", json!({})).unwrap().with_config(server.config()),
+            too_large.with_config(server.config()),
+        ];
 
-        let schema = json!({
-            "data": {
-                "text": "Hello!",
-                "number": 123
-            }
-        });
+        let results = NeutralIpcTemplate::render_parallel(templates);
 
-        let mut template = NeutralIpcTemplate::from_src_value("Rust IPC client: {:exit; 404 :}", schema).unwrap();
-        let result = template.render().unwrap();
-        let status_code = template.get_status_code();
-        let status_text = template.get_status_text();
-        let status_param = template.get_status_param();
+        assert_eq!(results[0].as_deref().unwrap(), "ok");
+        assert!(matches!(results[1], Err(NeutralIpcError::SchemaTooLarge { .. })));
+    }
 
-        assert!(!template.has_error());
-        assert_eq!(status_code, "404");
-        assert_eq!(status_text, "Not Found");
-        assert_eq!(status_param, "");
-        assert_eq!(result, "404 Not Found");
+    #[test]
+    fn test_global_defaults_apply_to_new_templates() {
+        // Only a large schema limit, so templates created by concurrent tests are unaffected.
+        const LIMIT: usize = 1 << 30;
+        let before = NeutralIpcTemplate::new().unwrap();
+        NeutralIpcTemplate::set_global_defaults(TemplateOptions::new().max_schema_size(LIMIT));
+
+        let mut template = NeutralIpcTemplate::from_src_value("", json!({})).unwrap();
+        let from_msgpack = NeutralIpcTemplate::from_file_msgpack("index.ntpl", b"\x80").unwrap();
+        NeutralIpcTemplate::clear_global_defaults();
+        let after = NeutralIpcTemplate::new().unwrap();
+
+        assert_eq!(template.max_schema_size, Some(LIMIT));
+        assert_eq!(from_msgpack.max_schema_size, Some(LIMIT));
+        assert_eq!(before.max_schema_size, None);
+        assert_eq!(after.max_schema_size, None);
+
+        template.set_max_schema_size(Some(16));
+        assert_eq!(template.max_schema_size, Some(16));
     }
 
     #[test]
-    fn test_template_redirect() {
-        skip_if_server_unavailable();
+    fn test_template_options_precedence() {
+        let server = MockServer::respond_with(CTRL_STATUS_OK, b"{}", b"ok");
+        let options = TemplateOptions::new()
+            .config(server.config())
+            .template_root(Path::new("/srv/templates"))
+            .max_schema_size(4)
+            .request_header("X-Service", "shop")
+            .request_header("x-service", "blog");
+        let mut template = NeutralIpcTemplate::from_src_value("", json!({"data": {}})).unwrap();
+        template.apply_options(&options);
 
-        let schema = json!({
-            "data": {
-                "text": "Hello!",
-                "number": 123
-            }
-        });
+        assert_eq!(template.template_root.as_deref(), Some(Path::new("/srv/templates")));
+        assert_eq!(template.request_headers, [("X-Service".to_string(), "blog".to_string())]);
+        assert_eq!(template.timeout, None);
+        assert!(matches!(template.render(), Err(NeutralIpcError::SchemaTooLarge { limit: 4, .. })));
 
-        let mut template = NeutralIpcTemplate::from_src_value("Rust IPC client: {:redirect; 301 >> https://crates.io/crates/neutralts :}", schema).unwrap();
-        let result = template.render().unwrap();
-        let status_code = template.get_status_code();
-        let status_text = template.get_status_text();
-        let status_param = template.get_status_param();
+        template.set_max_schema_size(Some(0));
+        template.clear_request_headers();
+        assert_eq!(template.render().unwrap(), "ok");
+    }
 
-        assert!(!template.has_error());
-        assert_eq!(status_code, "301");
-        assert_eq!(status_text, "Moved Permanently");
-        assert_eq!(status_param, "https://crates.io/crates/neutralts");
-        assert_eq!(result, "301 Moved Permanently\nhttps://crates.io/crates/neutralts");
+    #[test]
+    fn test_template_options_connection_and_output() {
+        let options = TemplateOptions::new()
+            .timeout(Duration::from_secs(60))
+            .connect_timeout(Duration::from_millis(500))
+            .buffer_size(1024)
+            .strip_bom(true)
+            .normalize_newlines(NewlineStyle::Lf)
+            .trim_trailing_whitespace(true)
+            .strict_schema_layout(true)
+            .schema_sections(&["data", "meta"]);
+        let mut template = NeutralIpcTemplate::from_src_value("", json!({"data": {}})).unwrap();
+        template.apply_options(&options);
+
+        assert_eq!(template.timeout, Some(Duration::from_secs(60)));
+        assert_eq!(template.connect_timeout, Some(Duration::from_millis(500)));
+        assert_eq!(template.buffer_size, Some(1024));
+        assert!(template.strip_bom);
+        assert_eq!(template.normalize_newlines, Some(NewlineStyle::Lf));
+        assert!(template.trim_trailing_whitespace);
+        assert!(template.strict_schema_layout);
+        template.merge_schema(json!({"meta": {}})).unwrap();
+        assert!(matches!(template.merge_schema(json!({"title": "Home"})), Err(NeutralIpcError::SchemaLayout(_))));
+
+        template.apply_options(&TemplateOptions::new().buffer_size(0));
+        assert_eq!(template.buffer_size, Some(1024));
     }
 
     #[test]
-    fn test_from_src_msgpack_and_merge_schema() {
-        let schema = json!({
-            "data": {
-                "text": "Hello!"
-            }
-        });
-        let msgpack = rmp_serde::to_vec(&schema).unwrap();
+    fn test_max_schema_size_boundary() {
+        let server = MockServer::respond_with(CTRL_STATUS_OK, b"{}", b"ok");
+        let template = NeutralIpcTemplate::from_src_value("", json!({"data": {"text": "Hello"}})).unwrap();
+        let size = template.schema_size();
+        assert_eq!(size, br#"{"data":{"text":"Hello"}}"#.len());
 
-        let mut template = NeutralIpcTemplate::from_src_msgpack("tpl", &msgpack).unwrap();
-        template.merge_schema(json!({"data": {"number": 123}})).unwrap();
+        let mut config = server.config();
+        config.set_max_schema_size(Some(size));
+        let mut template = template.with_config(config);
+        assert_eq!(template.render().unwrap(), "ok");
 
-        assert_eq!(template.schema_type, CONTENT_MSGPACK);
-        let merged: Value = from_slice(&template.schema).unwrap();
-        assert_eq!(merged["data"]["text"], "Hello!");
-        assert_eq!(merged["data"]["number"], 123);
+        template.set_max_schema_size(Some(size - 1));
+        match template.render() {
+            Err(NeutralIpcError::SchemaTooLarge { size: actual, limit }) => {
+                assert_eq!((actual, limit), (size, size - 1));
+            }
+            other => panic!("expected SchemaTooLarge, got {:?}", other),
+        }
+
+        template.set_max_schema_size(Some(0));
+        assert_eq!(template.render().unwrap(), "ok");
     }
 
     #[test]